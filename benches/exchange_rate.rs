@@ -0,0 +1,94 @@
+//! Benchmarks for [CommodityTypeID](commodity::CommodityTypeID) lookups
+//! within an [ExchangeRate](commodity::exchange_rate::ExchangeRate).
+
+use arrayvec::ArrayString;
+use commodity::exchange_rate::ExchangeRate;
+use commodity::{Commodity, CommodityTypeID};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Ids of the form `"CUR0"`, `"CUR1"`, ...
+fn id_strings(n: usize) -> Vec<String> {
+    (0..n).map(|i| format!("CUR{}", i)).collect()
+}
+
+fn exchange_rate(ids: &[String]) -> ExchangeRate {
+    let rates = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| {
+            (
+                CommodityTypeID::from_str(id).unwrap(),
+                Decimal::new(i as i64 + 1, 2),
+            )
+        })
+        .collect();
+
+    ExchangeRate {
+        date: None,
+        obtained_datetime: None,
+        base: None,
+        rates,
+    }
+}
+
+fn bench_lookup(c: &mut Criterion) {
+    let ids = id_strings(150);
+
+    // The previous representation of `CommodityTypeID`, used as a
+    // point of comparison.
+    let array_rates: BTreeMap<ArrayString<[u8; 8]>, Decimal> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| {
+            (
+                ArrayString::from(id).unwrap(),
+                Decimal::new(i as i64 + 1, 2),
+            )
+        })
+        .collect();
+    let array_keys: Vec<ArrayString<[u8; 8]>> = ids
+        .iter()
+        .map(|id| ArrayString::from(id).unwrap())
+        .collect();
+
+    let exchange_rate = exchange_rate(&ids);
+    let keys: Vec<CommodityTypeID> = ids
+        .iter()
+        .map(|id| CommodityTypeID::from_str(id).unwrap())
+        .collect();
+
+    let mut group = c.benchmark_group("rate lookup");
+    group.bench_function("BTreeMap<ArrayString, Decimal>", |b| {
+        b.iter(|| {
+            for key in &array_keys {
+                black_box(array_rates.get(key));
+            }
+        })
+    });
+    group.bench_function("ExchangeRate::get_rate", |b| {
+        b.iter(|| {
+            for key in &keys {
+                black_box(exchange_rate.get_rate(key));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_convert(c: &mut Criterion) {
+    let ids = id_strings(150);
+    let exchange_rate = exchange_rate(&ids);
+    let from = CommodityTypeID::from_str(&ids[3]).unwrap();
+    let to = CommodityTypeID::from_str(&ids[120]).unwrap();
+    let commodity = Commodity::new(Decimal::new(10000, 2), from);
+
+    c.bench_function("ExchangeRate::convert", |b| {
+        b.iter(|| exchange_rate.convert(black_box(commodity), black_box(to)))
+    });
+}
+
+criterion_group!(benches, bench_lookup, bench_convert);
+criterion_main!(benches);
@@ -0,0 +1,470 @@
+//! High precision versions of [Commodity](crate::Commodity) and
+//! [ExchangeRate](crate::exchange_rate::ExchangeRate), backed by
+//! [BigDecimal](bigdecimal::BigDecimal) instead of
+//! [rust_decimal::Decimal](rust_decimal::Decimal).
+//!
+//! [Decimal](rust_decimal::Decimal) has a 96 bit mantissa (28-29
+//! significant digits), which is not enough to exactly represent
+//! large cryptocurrency balances in their smallest units (e.g. ETH to
+//! 18 decimal places). The types in this module have arbitrary
+//! precision, and can be explicitly converted back to their standard
+//! counterparts when the value fits.
+
+use crate::exchange_rate::ExchangeRate;
+use crate::{Commodity, CommodityTypeID};
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, ToPrimitive, Zero};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[cfg(feature = "serde-support")]
+use serde::{Deserialize, Serialize};
+
+/// The maximum scale (number of decimal places) supported by
+/// [Decimal](rust_decimal::Decimal).
+const DECIMAL_MAX_SCALE: i64 = 28;
+
+/// An error associated with functionality in the [big](crate::big) module.
+#[derive(Error, Debug, PartialEq)]
+pub enum BigError {
+    #[error("The value {0} cannot be represented exactly as a Decimal.")]
+    PrecisionLoss(BigDecimal),
+    #[error("The value {0} is out of the range of Decimal.")]
+    OutOfRange(BigDecimal),
+    #[error(
+        "This commodity {this_commodity} is incompatible with {other_commodity} because {reason}"
+    )]
+    IncompatableCommodity {
+        this_commodity: BigCommodity,
+        other_commodity: BigCommodity,
+        reason: String,
+    },
+    #[error("The commodity type with id {0} is not present in the exchange rate.")]
+    CommodityTypeNotPresent(CommodityTypeID),
+    #[error("There was a divide by zero while computing the exchange rate, performing the division {0}/{1}.")]
+    DivideByZero(BigDecimal, BigDecimal),
+    #[error("The provided string {0} is invalid, it should be a decimal followed by a commodity_type. e.g. 1.234 USD")]
+    InvalidCommodityString(String),
+}
+
+/// Convert a [Decimal](rust_decimal::Decimal) to a
+/// [BigDecimal](bigdecimal::BigDecimal) (always exact).
+pub fn decimal_to_big(decimal: Decimal) -> BigDecimal {
+    BigDecimal::new(BigInt::from(decimal.mantissa()), decimal.scale() as i64)
+}
+
+/// Convert a [BigDecimal](bigdecimal::BigDecimal) to a
+/// [Decimal](rust_decimal::Decimal). Returns an error if the value
+/// cannot be represented exactly.
+pub fn big_to_decimal(big: &BigDecimal) -> Result<Decimal, BigError> {
+    let (mut mantissa, mut scale) = big.normalized().into_bigint_and_exponent();
+
+    if scale < 0 {
+        mantissa *= BigInt::from(10).pow((-scale) as u32);
+        scale = 0;
+    }
+
+    if scale > DECIMAL_MAX_SCALE {
+        return Err(BigError::PrecisionLoss(big.clone()));
+    }
+
+    let mantissa = mantissa
+        .to_i128()
+        .ok_or_else(|| BigError::OutOfRange(big.clone()))?;
+
+    Decimal::try_from_i128_with_scale(mantissa, scale as u32)
+        .map_err(|_| BigError::OutOfRange(big.clone()))
+}
+
+/// A high precision version of [Commodity](Commodity).
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigCommodity {
+    /// The value of this commodity
+    pub value: BigDecimal,
+    /// The id of the type of this commodity
+    pub type_id: CommodityTypeID,
+}
+
+impl BigCommodity {
+    /// Create a new [BigCommodity](BigCommodity).
+    pub fn new<T: Into<CommodityTypeID>>(value: BigDecimal, type_id: T) -> BigCommodity {
+        BigCommodity {
+            value,
+            type_id: type_id.into(),
+        }
+    }
+
+    /// Create a commodity with a value of zero
+    pub fn zero(type_id: CommodityTypeID) -> BigCommodity {
+        BigCommodity::new(BigDecimal::zero(), type_id)
+    }
+
+    fn check_compatible(&self, other: &BigCommodity, reason: &str) -> Result<(), BigError> {
+        if !self.compatible_with(other) {
+            return Err(BigError::IncompatableCommodity {
+                this_commodity: self.clone(),
+                other_commodity: other.clone(),
+                reason: String::from(reason),
+            });
+        }
+        Ok(())
+    }
+
+    /// Add the value of commodity `other` to `self`
+    /// such that `result = self + other`.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::big::BigCommodity;
+    /// use std::str::FromStr;
+    ///
+    /// let a = BigCommodity::from_str("123456789.123456789123456789 ETH").unwrap();
+    /// let b = BigCommodity::from_str("1000000000000.000000000000000001 ETH").unwrap();
+    ///
+    /// let result = a.add(&b).unwrap();
+    /// assert_eq!(BigCommodity::from_str("1000123456789.12345678912345679 ETH").unwrap(), result);
+    ///
+    /// // the result has too many significant digits for `rust_decimal::Decimal`.
+    /// assert!(result.to_commodity().is_err());
+    /// ```
+    pub fn add(&self, other: &BigCommodity) -> Result<BigCommodity, BigError> {
+        self.check_compatible(other, "cannot add commodities with different currencies")?;
+        Ok(BigCommodity::new(&self.value + &other.value, self.type_id))
+    }
+
+    /// Subtract the value of commodity `other` from `self`
+    /// such that `result = self - other`.
+    pub fn sub(&self, other: &BigCommodity) -> Result<BigCommodity, BigError> {
+        self.check_compatible(
+            other,
+            "cannot subtract commodities with different currencies",
+        )?;
+        Ok(BigCommodity::new(&self.value - &other.value, self.type_id))
+    }
+
+    /// Negate the value of this commodity such that `result = -self`
+    pub fn neg(&self) -> BigCommodity {
+        BigCommodity::new(-self.value.clone(), self.type_id)
+    }
+
+    /// Returns true if the currencies of both this commodity, and
+    /// the `other` commodity are compatible for numeric operations.
+    pub fn compatible_with(&self, other: &BigCommodity) -> bool {
+        self.type_id == other.type_id
+    }
+
+    /// Convert this commodity to a standard [Commodity](Commodity).
+    /// Returns an error if the value cannot be represented exactly
+    /// by a [Decimal](rust_decimal::Decimal).
+    pub fn to_commodity(&self) -> Result<Commodity, BigError> {
+        Ok(Commodity::new(big_to_decimal(&self.value)?, self.type_id))
+    }
+}
+
+impl From<Commodity> for BigCommodity {
+    fn from(commodity: Commodity) -> BigCommodity {
+        BigCommodity::new(decimal_to_big(commodity.value), commodity.type_id)
+    }
+}
+
+impl TryFrom<&BigCommodity> for Commodity {
+    type Error = BigError;
+
+    fn try_from(commodity: &BigCommodity) -> Result<Commodity, BigError> {
+        commodity.to_commodity()
+    }
+}
+
+impl FromStr for BigCommodity {
+    type Err = BigError;
+
+    /// Construct a [BigCommodity](BigCommodity) from a string, e.g.
+    /// `"1.000000000000000000000000000001 ETH"`.
+    fn from_str(commodity_string: &str) -> Result<BigCommodity, BigError> {
+        let elements: Vec<&str> = commodity_string.split_whitespace().collect();
+        let invalid = || BigError::InvalidCommodityString(String::from(commodity_string));
+
+        if elements.len() != 2 {
+            return Err(invalid());
+        }
+
+        let value = BigDecimal::from_str(elements[0]).map_err(|_| invalid())?;
+        let type_id = CommodityTypeID::from_str(elements[1]).map_err(|_| invalid())?;
+        Ok(BigCommodity::new(value, type_id))
+    }
+}
+
+impl fmt::Display for BigCommodity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.value, self.type_id)
+    }
+}
+
+/// A high precision version of [ExchangeRate](ExchangeRate).
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BigExchangeRate {
+    /// The datetime that this exchange rate represents
+    pub date: Option<NaiveDate>,
+    /// The datetime that this exchange rate was obtained.
+    pub obtained_datetime: Option<DateTime<Utc>>,
+    /// The id of the base commodity type for the exchange rate
+    pub base: Option<CommodityTypeID>,
+    /// Maps commodity type ids, to the conversion rate from that
+    /// [CommodityType](crate::CommodityType) to the `base`
+    /// [CommodityType](crate::CommodityType).
+    pub rates: BTreeMap<CommodityTypeID, BigDecimal>,
+}
+
+impl BigExchangeRate {
+    /// Get the rate for the specified commodity type (the number of
+    /// units of it per one unit of the `base`), or `None` if it is not
+    /// present in this exchange rate.
+    pub fn get_rate(&self, commodity_type_id: &CommodityTypeID) -> Option<&BigDecimal> {
+        self.rates.get(commodity_type_id)
+    }
+
+    /// Get the exchange rate between two commodity types present in
+    /// this exchange rate. Returns `None` if one of the commodity
+    /// types is not present.
+    pub fn rate_between(
+        &self,
+        from: &CommodityTypeID,
+        to: &CommodityTypeID,
+    ) -> Result<Option<BigDecimal>, BigError> {
+        let one = BigDecimal::from(1);
+
+        let from_rate = match (&self.base, self.get_rate(from)) {
+            (_, Some(rate)) => rate,
+            (Some(base), None) if base == from => &one,
+            _ => return Ok(None),
+        };
+
+        let to_rate = match (&self.base, self.get_rate(to)) {
+            (_, Some(rate)) => rate,
+            (Some(base), None) if base == to => &one,
+            _ => return Ok(None),
+        };
+
+        if from_rate.is_zero() {
+            return Err(BigError::DivideByZero(to_rate.clone(), from_rate.clone()));
+        }
+
+        Ok(Some(to_rate / from_rate))
+    }
+
+    /// Convert the [CommodityType](crate::CommodityType) of a
+    /// [BigCommodity](BigCommodity) to another
+    /// [CommodityType](crate::CommodityType) using this
+    /// [BigExchangeRate](BigExchangeRate).
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::big::{BigCommodity, BigExchangeRate};
+    /// use bigdecimal::BigDecimal;
+    /// use commodity::CommodityTypeID;
+    /// use std::collections::BTreeMap;
+    /// use std::str::FromStr;
+    ///
+    /// let eth = CommodityTypeID::from_str("ETH").unwrap();
+    /// let wei = CommodityTypeID::from_str("WEI").unwrap();
+    ///
+    /// let mut rates = BTreeMap::new();
+    /// rates.insert(wei, BigDecimal::from_str("1000000000000000000").unwrap());
+    ///
+    /// let exchange_rate = BigExchangeRate {
+    ///     date: None,
+    ///     obtained_datetime: None,
+    ///     base: Some(eth),
+    ///     rates,
+    /// };
+    ///
+    /// let balance = BigCommodity::from_str("98765432109.876543210987654321 ETH").unwrap();
+    /// let converted = exchange_rate.convert(&balance, wei).unwrap();
+    /// assert_eq!(
+    ///     BigCommodity::from_str("98765432109876543210987654321 WEI").unwrap(),
+    ///     converted
+    /// );
+    /// ```
+    pub fn convert(
+        &self,
+        commodity: &BigCommodity,
+        target_commodity_type: CommodityTypeID,
+    ) -> Result<BigCommodity, BigError> {
+        if !self.rates.contains_key(&commodity.type_id) && self.base != Some(commodity.type_id) {
+            return Err(BigError::CommodityTypeNotPresent(commodity.type_id));
+        }
+
+        match self.rate_between(&commodity.type_id, &target_commodity_type)? {
+            Some(rate) => Ok(BigCommodity::new(
+                (&commodity.value * rate).normalized(),
+                target_commodity_type,
+            )),
+            None => Err(BigError::CommodityTypeNotPresent(target_commodity_type)),
+        }
+    }
+
+    /// Convert this exchange rate to a standard
+    /// [ExchangeRate](ExchangeRate). Returns an error if any of the
+    /// rates cannot be represented exactly by a
+    /// [Decimal](rust_decimal::Decimal).
+    pub fn to_exchange_rate(&self) -> Result<ExchangeRate, BigError> {
+        let rates = self
+            .rates
+            .iter()
+            .map(|(id, rate)| Ok((*id, big_to_decimal(rate)?)))
+            .collect::<Result<BTreeMap<CommodityTypeID, Decimal>, BigError>>()?;
+
+        Ok(ExchangeRate {
+            date: self.date,
+            obtained_datetime: self.obtained_datetime,
+            base: self.base,
+            rates,
+        })
+    }
+}
+
+impl From<&ExchangeRate> for BigExchangeRate {
+    fn from(exchange_rate: &ExchangeRate) -> BigExchangeRate {
+        BigExchangeRate {
+            date: exchange_rate.date,
+            obtained_datetime: exchange_rate.obtained_datetime,
+            base: exchange_rate.base,
+            rates: exchange_rate
+                .rates
+                .iter()
+                .map(|(id, rate)| (*id, decimal_to_big(*rate)))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<&BigExchangeRate> for ExchangeRate {
+    type Error = BigError;
+
+    fn try_from(exchange_rate: &BigExchangeRate) -> Result<ExchangeRate, BigError> {
+        exchange_rate.to_exchange_rate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{big_to_decimal, decimal_to_big, BigCommodity, BigError, BigExchangeRate};
+    use crate::exchange_rate::ExchangeRate;
+    use crate::{Commodity, CommodityTypeID};
+    use bigdecimal::BigDecimal;
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn decimal_conversion() {
+        for value in &[
+            "0",
+            "1.5",
+            "-2.02",
+            "79228162514264337593543950335",
+            "1e-28",
+        ] {
+            let decimal = Decimal::from_str(value)
+                .or_else(|_| Decimal::from_scientific(value))
+                .unwrap();
+            let big = decimal_to_big(decimal);
+            assert_eq!(decimal, big_to_decimal(&big).unwrap());
+        }
+
+        // trailing zeros and negative exponents are fine
+        assert_eq!(
+            Decimal::new(1_000_000, 0),
+            big_to_decimal(&BigDecimal::from_str("1e6").unwrap()).unwrap()
+        );
+        assert_eq!(
+            Decimal::new(15, 1),
+            big_to_decimal(&BigDecimal::from_str("1.50000000000000000000000000000000").unwrap())
+                .unwrap()
+        );
+
+        let precise = BigDecimal::from_str("1.00000000000000000000000000001").unwrap();
+        assert_eq!(
+            BigError::PrecisionLoss(precise.clone()),
+            big_to_decimal(&precise).unwrap_err()
+        );
+
+        let huge = BigDecimal::from_str("79228162514264337593543950336").unwrap();
+        assert_eq!(
+            BigError::OutOfRange(huge.clone()),
+            big_to_decimal(&huge).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn wei_balances() {
+        // 30 significant digits, which doesn't fit in a Decimal.
+        let balance = BigCommodity::from_str("123456789012.123456789012345678 ETH").unwrap();
+        let small = BigCommodity::from_str("0.000000000000000001 ETH").unwrap();
+        let sum = balance.add(&small).unwrap();
+
+        assert_eq!(
+            BigCommodity::from_str("123456789012.123456789012345679 ETH").unwrap(),
+            sum
+        );
+        assert_eq!(balance, sum.sub(&small).unwrap());
+        assert!(balance.to_commodity().is_err());
+
+        let small_commodity = small.to_commodity().unwrap();
+        assert_eq!(
+            Commodity::from_str("0.000000000000000001 ETH").unwrap(),
+            small_commodity
+        );
+        assert_eq!(small, BigCommodity::from(small_commodity));
+
+        assert!(balance
+            .add(&BigCommodity::from_str("1 BTC").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn exchange_rate_conversion() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let eth = CommodityTypeID::from_str("ETH").unwrap();
+        let btc = CommodityTypeID::from_str("BTC").unwrap();
+
+        let mut rates = BTreeMap::new();
+        rates.insert(eth, Decimal::from_str("0.0005").unwrap());
+        rates.insert(btc, Decimal::from_str("0.00002").unwrap());
+        let exchange_rate = ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(usd),
+            rates,
+        };
+
+        let big_exchange_rate = BigExchangeRate::from(&exchange_rate);
+        assert_eq!(exchange_rate, big_exchange_rate.to_exchange_rate().unwrap());
+
+        let balance = BigCommodity::from_str("123456789012.123456789012345678 ETH").unwrap();
+        let converted = big_exchange_rate.convert(&balance, btc).unwrap();
+        assert_eq!(
+            BigCommodity::from_str("4938271560.48493827156049382712 BTC").unwrap(),
+            converted
+        );
+
+        let converted = big_exchange_rate.convert(&balance, usd).unwrap();
+        assert_eq!(
+            BigCommodity::from_str("246913578024246.913578024691356 USD").unwrap(),
+            converted
+        );
+
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        assert_eq!(
+            BigError::CommodityTypeNotPresent(aud),
+            big_exchange_rate.convert(&balance, aud).unwrap_err()
+        );
+    }
+}
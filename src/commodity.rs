@@ -1,9 +1,13 @@
+use crate::security::{SecurityIdentifier, SecurityIdentifierKind};
+use crate::unit::Unit;
 use arrayvec::ArrayString;
+use chrono::NaiveDate;
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::prelude::Zero;
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, RoundingStrategy};
 #[cfg(feature = "serde-support")]
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryFrom;
 use std::fmt;
 use std::{hash::Hash, str::FromStr};
 use thiserror::Error;
@@ -32,12 +36,44 @@ pub enum CommodityError {
         COMMODITY_TYPE_ID_LENGTH
     )]
     TooLongCommodityTypeID(String),
+    #[error("The commodity id {0:?} is invalid, it must be valid utf8 and may not contain any nul characters.")]
+    InvalidCommodityTypeID(String),
     #[cfg(feature = "iso4217")]
     #[error("The provided alpha3 code {0} doesn't match any in the iso4217 database")]
     #[cfg(feature = "iso4217")]
     InvalidISO4217Alpha3(String),
+    #[cfg(feature = "iso4217")]
+    #[error("The provided numeric code {0:03} doesn't match any in the iso4217 database")]
+    InvalidISO4217Numeric(u16),
     #[error("The provided string {0} is invalid, it should be a decimal followed by a commodity_type. e.g. 1.234 USD")]
     InvalidCommodityString(String),
+    #[error("The commodity type {type_id} is not a denomination of {family}")]
+    NotADenomination {
+        type_id: CommodityTypeID,
+        family: CommodityTypeID,
+    },
+    #[error("The commodity {0} cannot be converted to the denomination {1} without loss of precision or overflow")]
+    DenominationOverflow(Commodity, CommodityTypeID),
+}
+
+/// The kind of asset that a [CommodityType](CommodityType) represents.
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommodityKind {
+    /// A government issued currency, e.g. `USD`.
+    Fiat,
+    /// A cryptocurrency, e.g. `BTC`.
+    Crypto,
+    /// A share in a company, e.g. `AAPL`.
+    Equity,
+    /// A debt security.
+    Bond,
+    /// A precious metal, e.g. `XAU` (gold).
+    PreciousMetal,
+    /// Points in a loyalty/rewards program.
+    LoyaltyPoints,
+    /// Any other kind of commodity.
+    Other,
 }
 
 /// Represents a type of [Commodity](Commodity).
@@ -48,12 +84,91 @@ pub enum CommodityError {
 #[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Eq)]
 pub struct CommodityType {
-    /// Stores the id of this commodity type in a fixed length
-    /// [ArrayString](ArrayString), with a maximum length of
-    /// [COMMODITY_TYPE_ID_LENGTH](COMMODITY_TYPE_ID_LENGTH).
+    /// Stores the id of this commodity type, with a maximum length
+    /// of [COMMODITY_TYPE_ID_LENGTH](COMMODITY_TYPE_ID_LENGTH).
     pub id: CommodityTypeID,
     /// The human readable name of this commodity_type.
     pub name: Option<String>,
+    /// The `ISO4217` numeric code for this commodity type (if it is a
+    /// currency).
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub numeric_code: Option<u16>,
+    /// The number of decimal places used by the minor unit of this
+    /// commodity type, e.g. `2` for `USD` (cents), or `0` for `JPY`.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub minor_units: Option<u32>,
+    /// The `ISO3166` alpha2 codes of the countries which use this
+    /// commodity type (if it is a currency).
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub countries: Vec<String>,
+    /// The symbol used to denote this commodity type, e.g. `"US$"`.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub symbol: Option<String>,
+    /// A shorter symbol used to denote this commodity type where the
+    /// context makes it unambiguous, e.g. `"$"`.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub narrow_symbol: Option<String>,
+    /// The number of decimal places to use when displaying values of
+    /// this commodity type.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub precision: Option<u32>,
+    /// The kind of asset this commodity type represents.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub kind: Option<CommodityKind>,
+    /// The entity which issues this commodity type, e.g. a central
+    /// bank, a company, or a loyalty program operator.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub issuer: Option<String>,
+    /// The exchange where this commodity type is traded, e.g. `"NASDAQ"`.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub exchange: Option<String>,
+    /// Free-form tags associated with this commodity type.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub tags: Vec<String>,
+    /// The first date on which this commodity type was valid (if
+    /// known).
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub valid_from: Option<NaiveDate>,
+    /// The last date on which this commodity type was valid, for
+    /// example the date a currency was withdrawn (if it has been).
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub valid_until: Option<NaiveDate>,
+    /// The unit of measure for this commodity type, if it is a
+    /// physical commodity, e.g. [Unit::TroyOunce](Unit::TroyOunce) for
+    /// gold. The value of a [Commodity](Commodity) of this type is in
+    /// this unit.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub unit: Option<Unit>,
+    /// Standard identifiers for this commodity type, if it is a
+    /// security, e.g. its [Isin](crate::security::Isin).
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub identifiers: Vec<SecurityIdentifier>,
+    /// The denominations (sub-units or multiples) of this commodity
+    /// type, e.g. `SAT` for `BTC`.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub denominations: Vec<Denomination>,
+}
+
+/// A denomination of a [CommodityType](CommodityType), where one
+/// unit of the [CommodityType](CommodityType) is equal to
+/// `10^exponent` units of the denomination. For example `SAT` is a
+/// denomination of `BTC` with an exponent of `8`.
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Denomination {
+    /// The id of this denomination.
+    pub id: CommodityTypeID,
+    /// The power of ten which relates this denomination to its
+    /// [CommodityType](CommodityType). Negative exponents are
+    /// multiples of the commodity type, e.g. `kBTC`.
+    pub exponent: i32,
+}
+
+impl Denomination {
+    /// Create a new [Denomination](Denomination).
+    pub fn new(id: CommodityTypeID, exponent: i32) -> Denomination {
+        Denomination { id, exponent }
+    }
 }
 
 impl CommodityType {
@@ -74,7 +189,274 @@ impl CommodityType {
     /// assert_eq!(Some(String::from("Australian Dollar")), commodity_type.name);
     /// ```
     pub fn new(id: CommodityTypeID, name: Option<String>) -> CommodityType {
-        CommodityType { id, name }
+        CommodityType {
+            id,
+            name,
+            numeric_code: None,
+            minor_units: None,
+            countries: Vec::new(),
+            symbol: None,
+            narrow_symbol: None,
+            precision: None,
+            kind: None,
+            issuer: None,
+            exchange: None,
+            tags: Vec::new(),
+            valid_from: None,
+            valid_until: None,
+            unit: None,
+            identifiers: Vec::new(),
+            denominations: Vec::new(),
+        }
+    }
+
+    /// Set the `symbol` of this [CommodityType](CommodityType).
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::{CommodityKind, CommodityType};
+    ///
+    /// let commodity_type = CommodityType::from_str("AUD", "Australian dollar")
+    ///     .unwrap()
+    ///     .with_symbol("A$")
+    ///     .with_narrow_symbol("$")
+    ///     .with_kind(CommodityKind::Fiat)
+    ///     .with_minor_units(2)
+    ///     .with_issuer("Reserve Bank of Australia")
+    ///     .with_tag("g20");
+    ///
+    /// assert_eq!(Some(String::from("A$")), commodity_type.symbol);
+    /// assert_eq!(Some(CommodityKind::Fiat), commodity_type.kind);
+    /// assert_eq!(vec![String::from("g20")], commodity_type.tags);
+    /// ```
+    pub fn with_symbol<S: Into<String>>(mut self, symbol: S) -> CommodityType {
+        self.symbol = Some(symbol.into());
+        self
+    }
+
+    /// Set the `narrow_symbol` of this [CommodityType](CommodityType).
+    pub fn with_narrow_symbol<S: Into<String>>(mut self, narrow_symbol: S) -> CommodityType {
+        self.narrow_symbol = Some(narrow_symbol.into());
+        self
+    }
+
+    /// Set the display `precision` of this [CommodityType](CommodityType).
+    pub fn with_precision(mut self, precision: u32) -> CommodityType {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Set the `minor_units` of this [CommodityType](CommodityType).
+    pub fn with_minor_units(mut self, minor_units: u32) -> CommodityType {
+        self.minor_units = Some(minor_units);
+        self
+    }
+
+    /// Set the `kind` of this [CommodityType](CommodityType).
+    pub fn with_kind(mut self, kind: CommodityKind) -> CommodityType {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Set the `issuer` of this [CommodityType](CommodityType).
+    pub fn with_issuer<S: Into<String>>(mut self, issuer: S) -> CommodityType {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Set the `exchange` of this [CommodityType](CommodityType).
+    pub fn with_exchange<S: Into<String>>(mut self, exchange: S) -> CommodityType {
+        self.exchange = Some(exchange.into());
+        self
+    }
+
+    /// Add a tag to the `tags` of this [CommodityType](CommodityType).
+    pub fn with_tag<S: Into<String>>(mut self, tag: S) -> CommodityType {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Set the `valid_from` and `valid_until` dates of this
+    /// [CommodityType](CommodityType).
+    pub fn with_validity(
+        mut self,
+        valid_from: Option<NaiveDate>,
+        valid_until: Option<NaiveDate>,
+    ) -> CommodityType {
+        self.valid_from = valid_from;
+        self.valid_until = valid_until;
+        self
+    }
+
+    /// Returns true if this [CommodityType](CommodityType) was valid
+    /// on the specified `date`, according to its `valid_from` and
+    /// `valid_until` dates (both inclusive).
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::CommodityType;
+    /// use chrono::NaiveDate;
+    ///
+    /// let dem = CommodityType::from_str("DEM", "Deutsche Mark")
+    ///     .unwrap()
+    ///     .with_validity(None, NaiveDate::from_ymd_opt(2002, 2, 28));
+    ///
+    /// assert!(dem.is_valid_on(NaiveDate::from_ymd_opt(1995, 6, 1).unwrap()));
+    /// assert!(!dem.is_valid_on(NaiveDate::from_ymd_opt(2002, 3, 1).unwrap()));
+    /// ```
+    pub fn is_valid_on(&self, date: NaiveDate) -> bool {
+        let after_start = match self.valid_from {
+            Some(valid_from) => date >= valid_from,
+            None => true,
+        };
+        let before_end = match self.valid_until {
+            Some(valid_until) => date <= valid_until,
+            None => true,
+        };
+        after_start && before_end
+    }
+
+    /// Add a [Denomination](Denomination) to this
+    /// [CommodityType](CommodityType), where one unit of this
+    /// commodity type is equal to `10^exponent` units of the
+    /// denomination.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::{Commodity, CommodityType, CommodityTypeID};
+    /// use std::str::FromStr;
+    ///
+    /// let btc = CommodityType::from_str("BTC", "Bitcoin")
+    ///     .unwrap()
+    ///     .with_denomination(CommodityTypeID::from_str("mBTC").unwrap(), 3)
+    ///     .with_denomination(CommodityTypeID::from_str("SAT").unwrap(), 8);
+    ///
+    /// let sat = Commodity::from_str("150000 SAT").unwrap();
+    /// let mbtc = sat
+    ///     .to_denomination(&btc, CommodityTypeID::from_str("mBTC").unwrap())
+    ///     .unwrap();
+    /// assert_eq!(Commodity::from_str("1.5 mBTC").unwrap(), mbtc);
+    ///
+    /// // arithmetic between denominations of the same commodity type
+    /// // gives a result in the denomination of the left hand side.
+    /// let sum = mbtc.add_denominated(&sat, &btc).unwrap();
+    /// assert_eq!(Commodity::from_str("3.0 mBTC").unwrap(), sum);
+    /// ```
+    pub fn with_denomination(mut self, id: CommodityTypeID, exponent: i32) -> CommodityType {
+        self.denominations.push(Denomination::new(id, exponent));
+        self
+    }
+
+    /// Set the `unit` of measure of this [CommodityType](CommodityType).
+    pub fn with_unit(mut self, unit: Unit) -> CommodityType {
+        self.unit = Some(unit);
+        self
+    }
+
+    /// Add a standard security identifier (such as an
+    /// [Isin](crate::security::Isin)) to this
+    /// [CommodityType](CommodityType).
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::CommodityType;
+    /// use commodity::security::{Isin, SecurityIdentifierKind};
+    /// use std::str::FromStr;
+    ///
+    /// let apple = CommodityType::from_str("AAPL", "Apple Inc.")
+    ///     .unwrap()
+    ///     .with_identifier(Isin::from_str("US0378331005").unwrap());
+    ///
+    /// assert_eq!("US0378331005", apple.identifier(SecurityIdentifierKind::Isin).unwrap().as_str());
+    /// ```
+    pub fn with_identifier<I: Into<SecurityIdentifier>>(mut self, identifier: I) -> CommodityType {
+        self.identifiers.push(identifier.into());
+        self
+    }
+
+    /// Get the first security identifier of the specified `kind`
+    /// associated with this [CommodityType](CommodityType).
+    pub fn identifier(&self, kind: SecurityIdentifierKind) -> Option<&SecurityIdentifier> {
+        self.identifiers
+            .iter()
+            .find(|identifier| identifier.kind() == kind)
+    }
+
+    /// Returns true if this [CommodityType](CommodityType) has a
+    /// security identifier which
+    /// [matches](crate::security::SecurityIdentifier::matches) the
+    /// specified `identifier`.
+    pub fn has_identifier(&self, identifier: &SecurityIdentifier) -> bool {
+        self.identifiers
+            .iter()
+            .any(|existing| existing.matches(identifier))
+    }
+
+    /// The exponent relating the commodity type or denomination with
+    /// the id `type_id` to this [CommodityType](CommodityType). This
+    /// commodity type itself has an exponent of `0`. Returns `None` if
+    /// `type_id` is not this commodity type or one of its
+    /// denominations.
+    pub fn denomination_exponent(&self, type_id: &CommodityTypeID) -> Option<i32> {
+        if *type_id == self.id {
+            return Some(0);
+        }
+
+        self.denominations
+            .iter()
+            .find(|denomination| denomination.id == *type_id)
+            .map(|denomination| denomination.exponent)
+    }
+
+    /// Returns true if `type_id` is this commodity type, or one of its
+    /// denominations.
+    pub fn has_denomination(&self, type_id: &CommodityTypeID) -> bool {
+        self.denomination_exponent(type_id).is_some()
+    }
+
+    /// The number of decimal places to use when displaying values of
+    /// this commodity type. This is the `precision` if it has been
+    /// set, otherwise the `minor_units`.
+    pub fn display_precision(&self) -> Option<u32> {
+        self.precision.or(self.minor_units)
+    }
+
+    /// Format a value of this commodity type for display, rounded to
+    /// the [display_precision()](CommodityType::display_precision)
+    /// (if there is one), and prefixed by the `symbol` (if there is
+    /// one), otherwise followed by the `id`. A negative sign is placed
+    /// before the symbol (e.g. `-$5.00`).
+    ///
+    /// Values are rounded using
+    /// [RoundingStrategy::MidpointNearestEven](RoundingStrategy::MidpointNearestEven)
+    /// (banker's rounding), so half way values round to the nearest
+    /// even digit.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::CommodityType;
+    /// use rust_decimal::Decimal;
+    ///
+    /// let usd = CommodityType::from_str("USD", "United States dollar").unwrap();
+    /// assert_eq!("2.025 USD", usd.format_value(Decimal::new(2025, 3)));
+    ///
+    /// let usd = usd.with_symbol("$").with_precision(2);
+    /// assert_eq!("$2.02", usd.format_value(Decimal::new(2025, 3)));
+    /// assert_eq!("-$5.00", usd.format_value(Decimal::new(-500, 2)));
+    /// ```
+    pub fn format_value(&self, value: Decimal) -> String {
+        let value = match self.display_precision() {
+            Some(precision) => {
+                value.round_dp_with_strategy(precision, RoundingStrategy::MidpointNearestEven)
+            }
+            None => value,
+        };
+
+        match &self.symbol {
+            Some(symbol) if value.is_sign_negative() => format!("-{}{}", symbol, value.abs()),
+            Some(symbol) => format!("{}{}", symbol, value),
+            None => format!("{} {}", value, self.id),
+        }
     }
 
     /// Create a [CommodityType](CommodityType) from strings, usually
@@ -122,16 +504,59 @@ impl CommodityType {
     /// let commodity_type = CommodityType::from_currency_alpha3("AUD").unwrap();
     /// assert_eq!("AUD", commodity_type.id);
     /// assert_eq!(Some(String::from("Australian dollar")), commodity_type.name);
+    /// assert_eq!(Some(36), commodity_type.numeric_code);
+    /// assert_eq!(Some(2), commodity_type.minor_units);
+    /// assert!(commodity_type.countries.contains(&String::from("AU")));
     /// ```
     #[cfg(feature = "iso4217")]
     pub fn from_currency_alpha3<S: AsRef<str>>(alpha3: S) -> Result<CommodityType, CommodityError> {
         match iso4217::alpha3(alpha3.as_ref()) {
-            Some(id) => CommodityType::from_str(alpha3, id.name),
+            Some(currency) => Ok(CommodityType::from_iso4217(currency)),
             None => Err(CommodityError::InvalidISO4217Alpha3(String::from(
                 alpha3.as_ref(),
             ))),
         }
     }
+
+    /// Construct a [CommodityType](CommodityType) by looking up its
+    /// numeric code in the `ISO4217` currencies database.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::CommodityType;
+    ///
+    /// let commodity_type = CommodityType::from_currency_numeric(392).unwrap();
+    /// assert_eq!("JPY", commodity_type.id);
+    /// assert_eq!(Some(0), commodity_type.minor_units);
+    /// ```
+    #[cfg(feature = "iso4217")]
+    pub fn from_currency_numeric(numeric: u16) -> Result<CommodityType, CommodityError> {
+        match iso4217::num(&format!("{:03}", numeric)) {
+            Some(currency) => Ok(CommodityType::from_iso4217(currency)),
+            None => Err(CommodityError::InvalidISO4217Numeric(numeric)),
+        }
+    }
+
+    /// Construct a [CommodityType](CommodityType) from an entry in
+    /// the `ISO4217` currencies database.
+    #[cfg(feature = "iso4217")]
+    fn from_iso4217(currency: &iso4217::CurrencyCode) -> CommodityType {
+        let mut commodity_type = CommodityType::from_str(currency.alpha3, currency.name)
+            .expect("iso4217 alpha3 codes should be valid commodity type ids");
+        commodity_type.numeric_code = currency.num.parse().ok();
+        commodity_type.minor_units = if currency.exp >= 0 {
+            Some(currency.exp as u32)
+        } else {
+            None
+        };
+        commodity_type.countries = currency
+            .countries
+            .iter()
+            .map(|country| String::from(*country))
+            .collect();
+        commodity_type.kind = Some(CommodityKind::Fiat);
+        commodity_type
+    }
 }
 
 /// This implementation only checks that the ids match. It assumes
@@ -149,8 +574,17 @@ impl Hash for CommodityType {
     }
 }
 
+/// Displays the `id` followed by the `name` (if present), e.g. `AUD
+/// (Australian dollar)`. The alternate form (`{:#}`) displays the
+/// `symbol` instead (if present).
 impl fmt::Display for CommodityType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            if let Some(symbol) = &self.symbol {
+                return write!(f, "{}", symbol);
+            }
+        }
+
         match &self.name {
             Some(name) => write!(f, "{} ({})", self.id, name),
             None => write!(f, "{}", self.id),
@@ -161,28 +595,155 @@ impl fmt::Display for CommodityType {
 /// Return a vector of all `ISO4217` currencies
 #[cfg(feature = "iso4217")]
 pub fn all_iso4217_currencies() -> Vec<CommodityType> {
-    let mut currencies = Vec::new();
-    for iso_commodity_type in iso4217::all() {
-        currencies.push(
-            CommodityType::from_str(iso_commodity_type.alpha3, iso_commodity_type.name).unwrap(),
-        );
-    }
+    iso4217::all()
+        .iter()
+        .map(CommodityType::from_iso4217)
+        .collect()
+}
 
-    currencies
+/// Return a vector of the `ISO4217` currencies used by the country
+/// with the specified `ISO3166` alpha2 code.
+///
+/// # Example
+/// ```
+/// # use commodity::iso4217_currencies_for_country;
+///
+/// let currencies = iso4217_currencies_for_country("NZ");
+/// assert_eq!(1, currencies.len());
+/// assert_eq!("NZD", currencies[0].id);
+/// ```
+#[cfg(feature = "iso4217")]
+pub fn iso4217_currencies_for_country<S: AsRef<str>>(country: S) -> Vec<CommodityType> {
+    iso4217::country(country.as_ref())
+        .into_iter()
+        .map(CommodityType::from_iso4217)
+        .collect()
 }
 
-/// The id of a [CommodityType](CommodityType) stored in a fixed
-/// length array using [ArrayString](ArrayString), with a maximum
-/// length of [COMMODITY_TYPE_ID_LENGTH](COMMODITY_TYPE_ID_LENGTH).
+/// The id of a [CommodityType](CommodityType), with a maximum length
+/// of [COMMODITY_TYPE_ID_LENGTH](COMMODITY_TYPE_ID_LENGTH) bytes.
+///
+/// Internally the bytes of the id are packed into a `u64` (big-endian,
+/// padded with trailing zero bytes, the length being implied by the
+/// first zero byte), which makes hashing and comparison a single
+/// integer operation. The ordering is identical to byte-wise string
+/// ordering of the ids.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct CommodityTypeID {
-    id_array: CommodityTypeIDArray,
+    packed: u64,
 }
 
 impl CommodityTypeID {
     /// Create a new [CommodityTypeID](CommodityTypeID).
     pub fn new(id_array: CommodityTypeIDArray) -> CommodityTypeID {
-        CommodityTypeID { id_array }
+        CommodityTypeID::pack(id_array.as_bytes())
+    }
+
+    /// Pack the supplied bytes (which must be no longer than
+    /// [COMMODITY_TYPE_ID_LENGTH](COMMODITY_TYPE_ID_LENGTH)) into a
+    /// [CommodityTypeID](CommodityTypeID).
+    fn pack(bytes: &[u8]) -> CommodityTypeID {
+        let mut array = [0u8; COMMODITY_TYPE_ID_LENGTH];
+        array[..bytes.len()].copy_from_slice(bytes);
+        CommodityTypeID {
+            packed: u64::from_be_bytes(array),
+        }
+    }
+
+    /// Obtain the packed `u64` representation of this
+    /// [CommodityTypeID](CommodityTypeID).
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::CommodityTypeID;
+    /// use std::convert::TryFrom;
+    /// use std::str::FromStr;
+    ///
+    /// let aud = CommodityTypeID::from_str("AUD").unwrap();
+    /// assert_eq!(aud, CommodityTypeID::try_from(aud.to_u64()).unwrap());
+    /// ```
+    pub fn to_u64(&self) -> u64 {
+        self.packed
+    }
+
+    /// The bytes of the id, padded with trailing zero bytes.
+    fn padded_bytes(&self) -> [u8; COMMODITY_TYPE_ID_LENGTH] {
+        self.packed.to_be_bytes()
+    }
+
+    /// The length of the id in bytes.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::CommodityTypeID;
+    /// use std::str::FromStr;
+    ///
+    /// let aud = CommodityTypeID::from_str("AUD").unwrap();
+    /// assert_eq!(3, aud.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        // the number of trailing zero bytes in the big-endian
+        // representation is the amount of padding.
+        let padding = if self.packed == 0 {
+            COMMODITY_TYPE_ID_LENGTH
+        } else {
+            (self.packed.trailing_zeros() / 8) as usize
+        };
+        COMMODITY_TYPE_ID_LENGTH - padding
+    }
+
+    /// Returns true if this id is an empty string.
+    pub fn is_empty(&self) -> bool {
+        self.packed == 0
+    }
+
+    /// Obtain the id as a fixed length [ArrayString](ArrayString).
+    pub fn to_array_string(&self) -> CommodityTypeIDArray {
+        let bytes = self.padded_bytes();
+        // the packed value is always validated on construction to
+        // contain a valid utf8 id.
+        let id = std::str::from_utf8(&bytes[..self.len()])
+            .expect("CommodityTypeID should always contain valid utf8");
+        CommodityTypeIDArray::from(id).expect("CommodityTypeID should always fit in its array")
+    }
+}
+
+/// Construct a [CommodityTypeID](CommodityTypeID) from its packed
+/// representation, as returned by
+/// [CommodityTypeID::to_u64()](CommodityTypeID::to_u64).
+///
+/// The packed value is validated the same way as
+/// [CommodityTypeID::from_str()](CommodityTypeID::from_str), it must
+/// contain a valid utf8 id followed only by zero bytes of padding,
+/// otherwise a
+/// [CommodityError::InvalidCommodityTypeID](CommodityError::InvalidCommodityTypeID)
+/// will be returned.
+impl TryFrom<u64> for CommodityTypeID {
+    type Error = CommodityError;
+
+    fn try_from(packed: u64) -> Result<CommodityTypeID, CommodityError> {
+        let id = CommodityTypeID { packed };
+        let bytes = id.padded_bytes();
+        let (id_bytes, padding) = bytes.split_at(id.len());
+
+        let valid = !id_bytes.contains(&0)
+            && padding.iter().all(|b| *b == 0)
+            && std::str::from_utf8(id_bytes).is_ok();
+
+        if !valid {
+            return Err(CommodityError::InvalidCommodityTypeID(format!(
+                "{:#018x}",
+                packed
+            )));
+        }
+
+        Ok(id)
+    }
+}
+
+impl From<CommodityTypeID> for u64 {
+    fn from(id: CommodityTypeID) -> u64 {
+        id.to_u64()
     }
 }
 
@@ -191,10 +752,13 @@ impl FromStr for CommodityTypeID {
 
     /// Create a new [CommodityType](CommodityType).
     ///
-    /// `code` is an array backed string that has a fixed maximum size
-    /// of [COMMODITY_TYPE_ID_LENGTH](COMMODITY_TYPE_ID_LENGTH). The supplied
-    /// string must not exeed this, or a
+    /// `code` is a string that has a fixed maximum size of
+    /// [COMMODITY_TYPE_ID_LENGTH](COMMODITY_TYPE_ID_LENGTH) bytes. The
+    /// supplied string must not exeed this, or a
     /// [CommodityError::TooLongCommodityTypeID](CommodityError::TooLongCommodityTypeID)
+    /// will be returned. It also may not contain any `'\0'`
+    /// characters, or a
+    /// [CommodityError::InvalidCommodityTypeID](CommodityError::InvalidCommodityTypeID)
     /// will be returned.
     ///
     /// # Example
@@ -209,9 +773,11 @@ impl FromStr for CommodityTypeID {
             return Err(CommodityError::TooLongCommodityTypeID(String::from(id)));
         }
 
-        Ok(CommodityTypeID::new(
-            CommodityTypeIDArray::from(id).unwrap(),
-        ))
+        if id.contains('\0') {
+            return Err(CommodityError::InvalidCommodityTypeID(String::from(id)));
+        }
+
+        Ok(CommodityTypeID::pack(id.as_bytes()))
     }
 }
 
@@ -267,14 +833,14 @@ impl Serialize for CommodityTypeID {
     where
         S: Serializer,
     {
-        serializer.serialize_str(&self.id_array)
+        serializer.serialize_str(&self.to_array_string())
     }
 }
 
 impl PartialEq<CommodityTypeID> for &str {
     fn eq(&self, other: &CommodityTypeID) -> bool {
-        match CommodityTypeIDArray::from_str(self) {
-            Ok(self_as_code) => self_as_code == other.id_array,
+        match CommodityTypeID::from_str(self) {
+            Ok(self_as_id) => self_as_id == *other,
             Err(_) => false,
         }
     }
@@ -282,7 +848,7 @@ impl PartialEq<CommodityTypeID> for &str {
 
 impl fmt::Display for CommodityTypeID {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.id_array)
+        write!(f, "{}", self.to_array_string())
     }
 }
 
@@ -337,7 +903,7 @@ impl Commodity {
     /// use std::str::FromStr;
     /// use rust_decimal::Decimal;
     ///
-    /// let commodity_type = CommodityType::from_currency_alpha3("USD").unwrap();
+    /// let commodity_type = CommodityType::from_str("USD", "United States dollar").unwrap();
     /// let commodity = Commodity::new(Decimal::new(202, 2), &commodity_type);
     /// ```
     pub fn new<T: Into<CommodityTypeID>>(value: Decimal, type_id: T) -> Commodity {
@@ -355,6 +921,12 @@ impl Commodity {
     /// Add the value of commodity `other` to `self`
     /// such that `result = self + other`.
     ///
+    /// Both commodities need to have the same type id. Denominations
+    /// of the same [CommodityType](CommodityType) (e.g. `BTC` and
+    /// `SAT`) are not normalized automatically because a
+    /// [Commodity](Commodity) does not know its family, use
+    /// [add_denominated()](Commodity::add_denominated) for those.
+    ///
     /// # Example
     /// ```
     /// # use commodity::{Commodity, CommodityTypeID};
@@ -384,6 +956,10 @@ impl Commodity {
     /// Subtract the value of commodity `other` from `self`
     /// such that `result = self - other`.
     ///
+    /// Both commodities need to have the same type id, use
+    /// [sub_denominated()](Commodity::sub_denominated) for
+    /// denominations of the same [CommodityType](CommodityType).
+    ///
     /// # Example
     /// ```
     /// # use commodity::{Commodity, CommodityTypeID};
@@ -635,6 +1211,78 @@ impl Commodity {
 
         diff <= epsilon
     }
+
+    /// Convert this commodity to another denomination of the same
+    /// [CommodityType](CommodityType) `family`. The conversion is
+    /// exact, an error is returned if the result cannot be represented
+    /// without loss of precision, or if either this commodity or the
+    /// `target` are not denominations of `family`.
+    pub fn to_denomination(
+        &self,
+        family: &CommodityType,
+        target: CommodityTypeID,
+    ) -> Result<Commodity, CommodityError> {
+        let not_a_denomination = |type_id| CommodityError::NotADenomination {
+            type_id,
+            family: family.id,
+        };
+
+        let from_exponent = family
+            .denomination_exponent(&self.type_id)
+            .ok_or_else(|| not_a_denomination(self.type_id))?;
+        let to_exponent = family
+            .denomination_exponent(&target)
+            .ok_or_else(|| not_a_denomination(target))?;
+
+        let value = shift_decimal(self.value, to_exponent as i64 - from_exponent as i64)
+            .ok_or(CommodityError::DenominationOverflow(*self, target))?;
+
+        Ok(Commodity::new(value, target))
+    }
+
+    /// Add the value of commodity `other` to `self`, where both are
+    /// denominations of the same [CommodityType](CommodityType)
+    /// `family`. The result is in the denomination of `self`.
+    pub fn add_denominated(
+        &self,
+        other: &Commodity,
+        family: &CommodityType,
+    ) -> Result<Commodity, CommodityError> {
+        self.add(&other.to_denomination(family, self.type_id)?)
+    }
+
+    /// Subtract the value of commodity `other` from `self`, where
+    /// both are denominations of the same
+    /// [CommodityType](CommodityType) `family`. The result is in the
+    /// denomination of `self`.
+    pub fn sub_denominated(
+        &self,
+        other: &Commodity,
+        family: &CommodityType,
+    ) -> Result<Commodity, CommodityError> {
+        self.sub(&other.to_denomination(family, self.type_id)?)
+    }
+}
+
+/// Multiply `value` by `10^shift` exactly, returning `None` if the
+/// result cannot be represented by a [Decimal](Decimal).
+fn shift_decimal(value: Decimal, shift: i64) -> Option<Decimal> {
+    const MAX_SCALE: i64 = 28;
+
+    let value = value.normalize();
+    let mut mantissa = value.mantissa();
+    let mut scale = value.scale() as i64 - shift;
+
+    if scale < 0 {
+        mantissa = mantissa.checked_mul(10_i128.checked_pow((-scale) as u32)?)?;
+        scale = 0;
+    }
+
+    if scale > MAX_SCALE {
+        return None;
+    }
+
+    Decimal::try_from_i128_with_scale(mantissa, scale as u32).ok()
 }
 
 impl FromStr for Commodity {
@@ -662,7 +1310,7 @@ impl FromStr for Commodity {
         }
 
         Ok(Commodity::new(
-            Decimal::from_str(elements.get(0).unwrap()).unwrap(),
+            Decimal::from_str(elements.first().unwrap()).unwrap(),
             CommodityTypeID::from_str(elements.get(1).unwrap())?,
         ))
     }
@@ -670,14 +1318,7 @@ impl FromStr for Commodity {
 
 impl PartialOrd for Commodity {
     fn partial_cmp(&self, other: &Commodity) -> Option<std::cmp::Ordering> {
-        check_commodity_type_compatible(
-            self,
-            other,
-            String::from("cannot compare commodities with different currencies"),
-        )
-        .unwrap();
-
-        self.value.partial_cmp(&other.value)
+        Some(self.cmp(other))
     }
 }
 
@@ -704,6 +1345,7 @@ impl fmt::Display for Commodity {
 mod tests {
     use super::{Commodity, CommodityError, CommodityType, CommodityTypeID};
     use rust_decimal::Decimal;
+    use std::convert::TryFrom;
     use std::str::FromStr;
 
     // #[test]
@@ -754,8 +1396,8 @@ mod tests {
 
         assert_eq!(
             CommodityError::IncompatableCommodity {
-                this_commodity: commodity1.clone(),
-                other_commodity: commodity2.clone(),
+                this_commodity: commodity1,
+                other_commodity: commodity2,
                 reason: String::from("cannot add commodities with different currencies"),
             },
             error1
@@ -822,6 +1464,219 @@ mod tests {
         assert_eq!("AUD (Australian dollar)", &format!("{}", aud));
 
         let test = CommodityType::new(CommodityTypeID::from_str("TEST").unwrap(), None);
-        assert_eq!("TEST", &format!("{}", test))
+        assert_eq!("TEST", &format!("{}", test));
+        assert_eq!("TEST", &format!("{:#}", test));
+
+        let aud = aud.with_symbol("A$");
+        assert_eq!("AUD (Australian dollar)", &format!("{}", aud));
+        assert_eq!("A$", &format!("{:#}", aud));
+    }
+
+    /// Test formatting values with `CommodityType::format_value()`.
+    #[test]
+    fn test_format_value() {
+        let usd = CommodityType::from_str("USD", "United States dollar")
+            .unwrap()
+            .with_minor_units(2);
+        assert_eq!("-5.00 USD", usd.format_value(Decimal::new(-500, 2)));
+
+        let usd = usd.with_symbol("$");
+        assert_eq!("-$5.00", usd.format_value(Decimal::new(-500, 2)));
+        assert_eq!("$0.00", usd.format_value(Decimal::new(-1, 3)));
+        // banker's rounding
+        assert_eq!("$2.02", usd.format_value(Decimal::new(2025, 3)));
+        assert_eq!("$2.04", usd.format_value(Decimal::new(2035, 3)));
+        assert_eq!("-$2.04", usd.format_value(Decimal::new(-2035, 3)));
+    }
+
+    /// Test that the packed representation of `CommodityTypeID` orders
+    /// identically to the string representation.
+    #[test]
+    fn test_commodity_type_id_ordering() {
+        let ids = [
+            "", "A", "AA", "AAA", "AB", "AUD", "AUDX", "B", "BTC", "EUR", "USD", "USDT",
+            "ZZZZZZZZ", "a", "\u{e9}",
+        ];
+
+        for a in ids.iter() {
+            for b in ids.iter() {
+                let id_a = CommodityTypeID::from_str(a).unwrap();
+                let id_b = CommodityTypeID::from_str(b).unwrap();
+                assert_eq!(a.cmp(b), id_a.cmp(&id_b), "comparing {:?} to {:?}", a, b);
+            }
+        }
+    }
+
+    /// Test conversion of `CommodityTypeID` to and from its packed `u64` representation.
+    #[test]
+    fn test_commodity_type_id_u64() {
+        for id_str in ["", "A", "AUD", "ABCDEFGH", "\u{e9}"].iter() {
+            let id = CommodityTypeID::from_str(id_str).unwrap();
+            assert_eq!(id_str.len(), id.len());
+            assert_eq!(*id_str, &format!("{}", id));
+
+            let packed = id.to_u64();
+            assert_eq!(id, CommodityTypeID::try_from(packed).unwrap());
+        }
+
+        // interior zero bytes, bytes after the padding and invalid
+        // utf8 are rejected.
+        for packed in [
+            0x4100_4400_0000_0000u64,
+            0x4155_4400_0000_0001,
+            0xff00_0000_0000_0000,
+            0xc300_0000_0000_0000,
+        ]
+        .iter()
+        {
+            assert_eq!(
+                CommodityError::InvalidCommodityTypeID(format!("{:#018x}", packed)),
+                CommodityTypeID::try_from(*packed).unwrap_err()
+            );
+        }
+
+        assert_eq!(
+            0x4155_4400_0000_0000,
+            CommodityTypeID::from_str("AUD").unwrap().to_u64()
+        );
+        assert_eq!(
+            CommodityError::InvalidCommodityTypeID(String::from("A\0")),
+            CommodityTypeID::from_str("A\0").unwrap_err()
+        );
+    }
+
+    /// Test the `ISO4217` metadata stored on `CommodityType`.
+    #[cfg(feature = "iso4217")]
+    #[test]
+    fn test_iso4217_metadata() {
+        use super::{all_iso4217_currencies, iso4217_currencies_for_country};
+
+        let eur = CommodityType::from_currency_numeric(978).unwrap();
+        assert_eq!("EUR", eur.id);
+        assert_eq!(Some(978), eur.numeric_code);
+        assert_eq!(Some(2), eur.minor_units);
+        assert!(eur.countries.contains(&String::from("DE")));
+        assert_eq!(eur, CommodityType::from_currency_alpha3("EUR").unwrap());
+
+        assert_eq!(
+            CommodityError::InvalidISO4217Numeric(1),
+            CommodityType::from_currency_numeric(1).unwrap_err()
+        );
+
+        let kwd = CommodityType::from_currency_alpha3("KWD").unwrap();
+        assert_eq!(Some(3), kwd.minor_units);
+
+        let de_currencies = iso4217_currencies_for_country("DE");
+        assert_eq!(vec![eur], de_currencies);
+
+        let all = all_iso4217_currencies();
+        assert!(all.iter().all(|currency| currency.numeric_code.is_some()));
+        assert!(all.iter().all(|currency| currency.minor_units.is_some()));
+    }
+
+    /// Test that `CommodityType` can be deserialized without the
+    /// optional metadata fields.
+    #[cfg(feature = "serde-support")]
+    #[test]
+    fn test_commodity_type_deserialization() {
+        use serde_json;
+
+        let original_data = r#"{
+  "id": "AUD",
+  "name": "Australian dollar"
+}"#;
+        let commodity_type: CommodityType = serde_json::from_str(original_data).unwrap();
+
+        assert_eq!("AUD", commodity_type.id);
+        assert_eq!(Some(String::from("Australian dollar")), commodity_type.name);
+        assert_eq!(None, commodity_type.numeric_code);
+        assert!(commodity_type.countries.is_empty());
+        assert_eq!(None, commodity_type.kind);
+    }
+
+    /// Test serialization of the metadata stored on `CommodityType`.
+    #[cfg(feature = "serde-support")]
+    #[test]
+    fn test_commodity_type_metadata_serialization() {
+        use super::CommodityKind;
+        use serde_json;
+
+        let btc = CommodityType::from_str("BTC", "Bitcoin")
+            .unwrap()
+            .with_symbol("\u{20bf}")
+            .with_kind(CommodityKind::Crypto)
+            .with_minor_units(8)
+            .with_precision(4)
+            .with_tag("pow");
+
+        let serialized_data = serde_json::to_string(&btc).unwrap();
+        let deserialized: CommodityType = serde_json::from_str(&serialized_data).unwrap();
+
+        assert_eq!(btc.symbol, deserialized.symbol);
+        assert_eq!(Some(CommodityKind::Crypto), deserialized.kind);
+        assert_eq!(Some(8), deserialized.minor_units);
+        assert_eq!(Some(4), deserialized.display_precision());
+        assert_eq!(btc.tags, deserialized.tags);
+    }
+
+    /// Test conversion between denominations of a `CommodityType`.
+    #[test]
+    fn test_denominations() {
+        let eth_id = CommodityTypeID::from_str("ETH").unwrap();
+        let gwei = CommodityTypeID::from_str("GWEI").unwrap();
+        let wei = CommodityTypeID::from_str("WEI").unwrap();
+        let btc = CommodityTypeID::from_str("BTC").unwrap();
+
+        let eth = CommodityType::from_str("ETH", "Ether")
+            .unwrap()
+            .with_denomination(gwei, 9)
+            .with_denomination(wei, 18);
+
+        let one_wei = Commodity::from_str("1 WEI").unwrap();
+        assert_eq!(
+            Commodity::from_str("0.000000000000000001 ETH").unwrap(),
+            one_wei.to_denomination(&eth, eth_id).unwrap()
+        );
+        assert_eq!(
+            Commodity::from_str("0.000000001 GWEI").unwrap(),
+            one_wei.to_denomination(&eth, gwei).unwrap()
+        );
+
+        let balance = Commodity::from_str("12.5 ETH").unwrap();
+        let in_wei = balance.to_denomination(&eth, wei).unwrap();
+        assert_eq!(
+            Commodity::from_str("12500000000000000000 WEI").unwrap(),
+            in_wei
+        );
+        assert_eq!(balance, in_wei.to_denomination(&eth, eth_id).unwrap());
+
+        let sum = balance.add_denominated(&one_wei, &eth).unwrap();
+        assert_eq!(
+            Commodity::from_str("12.500000000000000001 ETH").unwrap(),
+            sum
+        );
+        assert_eq!(balance, sum.sub_denominated(&one_wei, &eth).unwrap());
+
+        assert_eq!(
+            CommodityError::NotADenomination {
+                type_id: btc,
+                family: eth_id
+            },
+            balance.to_denomination(&eth, btc).unwrap_err()
+        );
+
+        // too many wei to be represented by a Decimal
+        let huge = Commodity::from_str("100000000000 ETH").unwrap();
+        assert_eq!(
+            CommodityError::DenominationOverflow(huge, wei),
+            huge.to_denomination(&eth, wei).unwrap_err()
+        );
+
+        // too small a fraction of an ETH to be represented by a Decimal
+        let tiny = Commodity::from_str("0.00000000001 WEI").unwrap();
+        assert_eq!(
+            CommodityError::DenominationOverflow(tiny, eth_id),
+            tiny.to_denomination(&eth, eth_id).unwrap_err()
+        );
     }
 }
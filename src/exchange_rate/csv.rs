@@ -0,0 +1,699 @@
+//! Reading and writing [ExchangeRate](ExchangeRate)s as csv, available
+//! with the `csv` feature.
+//!
+//! Two layouts are supported:
+//!
+//! + Wide form, with a `date` and `base` column, and then one column
+//!   per [CommodityTypeID](CommodityTypeID) containing the rate for
+//!   it. Each row is one [ExchangeRate](ExchangeRate), and empty cells
+//!   are rates which are not present.
+//! + Long form, with `date`, `base`, `quote` and `rate` columns, and
+//!   one row per rate. Consecutive rows with the same `date` and
+//!   `base` are combined into one [ExchangeRate](ExchangeRate), and may
+//!   not repeat a `quote`.
+//!
+//! The [WideReader](WideReader) and [LongReader](LongReader) read the
+//! exchange rates one at a time, so that large histories do not need
+//! to be loaded into memory at once. An invalid row results in a
+//! [CsvError](CsvError) with its line number, and reading can
+//! continue with the following rows.
+//!
+//! ```
+//! # use commodity::exchange_rate::csv::{CsvOptions, WideReader};
+//! # use commodity::CommodityTypeID;
+//! use rust_decimal::Decimal;
+//! use std::str::FromStr;
+//!
+//! let data = "date;base;AUD;NZD\n2020-02-07;USD;1,49;1,55\n2020-02-08;USD;1,5;\n";
+//! let options = CsvOptions::new()
+//!     .with_delimiter(b';')
+//!     .with_decimal_separator(',');
+//!
+//! let exchange_rates = WideReader::new(data.as_bytes(), options)
+//!     .unwrap()
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//!
+//! let aud = CommodityTypeID::from_str("AUD").unwrap();
+//! let nzd = CommodityTypeID::from_str("NZD").unwrap();
+//! assert_eq!(2, exchange_rates.len());
+//! assert_eq!(Some(&Decimal::from_str("1.49").unwrap()), exchange_rates[0].get_rate(&aud));
+//! assert_eq!(None, exchange_rates[1].get_rate(&nzd));
+//! ```
+
+use super::ExchangeRate;
+use crate::CommodityTypeID;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An error associated with functionality in the [csv](crate::exchange_rate::csv) module.
+#[derive(Error, Debug)]
+pub enum CsvError {
+    #[error("There was an error reading or writing csv.")]
+    Csv(#[from] csv::Error),
+    #[error("The csv header is missing the column {0:?}.")]
+    MissingColumn(String),
+    #[error("The csv header column {0:?} is not a valid commodity type id.")]
+    InvalidColumn(String),
+    #[error("The csv header contains the column {0:?} more than once.")]
+    DuplicateColumn(String),
+    #[error("The value {value:?} in the column {column:?} on line {line} is invalid.")]
+    InvalidValue {
+        line: u64,
+        column: String,
+        value: String,
+    },
+    #[error("The value in the column {column:?} on line {line} is missing.")]
+    MissingValue { line: u64, column: String },
+    #[error("The rate for {quote} on line {line} duplicates an earlier row with the same date and base.")]
+    DuplicateRate { line: u64, quote: CommodityTypeID },
+}
+
+/// Options for reading and writing exchange rates as csv.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    /// The delimiter between fields, `b','` by default.
+    pub delimiter: u8,
+    /// The separator between the integer and fractional parts of the
+    /// rates, `'.'` by default.
+    pub decimal_separator: char,
+    /// The `chrono` format of the dates, `"%Y-%m-%d"` by default.
+    pub date_format: String,
+}
+
+impl CsvOptions {
+    /// Create the default [CsvOptions](CsvOptions).
+    pub fn new() -> CsvOptions {
+        CsvOptions::default()
+    }
+
+    /// Set the delimiter between fields.
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Set the separator between the integer and fractional parts of
+    /// the rates.
+    pub fn with_decimal_separator(mut self, decimal_separator: char) -> Self {
+        self.decimal_separator = decimal_separator;
+        self
+    }
+
+    /// Set the `chrono` format of the dates.
+    pub fn with_date_format<S: Into<String>>(mut self, date_format: S) -> Self {
+        self.date_format = date_format.into();
+        self
+    }
+
+    fn reader<R: io::Read>(&self, reader: R) -> csv::Reader<R> {
+        csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .trim(csv::Trim::All)
+            .from_reader(reader)
+    }
+
+    fn writer<W: io::Write>(&self, writer: W) -> csv::Writer<W> {
+        csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .from_writer(writer)
+    }
+
+    fn format_date(&self, date: Option<NaiveDate>) -> String {
+        date.map(|date| date.format(&self.date_format).to_string())
+            .unwrap_or_default()
+    }
+
+    fn format_rate(&self, rate: &Decimal) -> String {
+        let rate = rate.to_string();
+        if self.decimal_separator == '.' {
+            rate
+        } else {
+            rate.replace('.', &self.decimal_separator.to_string())
+        }
+    }
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: b',',
+            decimal_separator: '.',
+            date_format: "%Y-%m-%d".to_string(),
+        }
+    }
+}
+
+/// Parses the fields of a single csv record, reporting errors with the
+/// line number of the record.
+struct RecordParser<'a> {
+    options: &'a CsvOptions,
+    record: &'a csv::StringRecord,
+    line: u64,
+}
+
+impl<'a> RecordParser<'a> {
+    fn new(options: &'a CsvOptions, record: &'a csv::StringRecord) -> RecordParser<'a> {
+        RecordParser {
+            options,
+            record,
+            line: record.position().map(|p| p.line()).unwrap_or_default(),
+        }
+    }
+
+    fn field(&self, index: usize) -> &'a str {
+        self.record.get(index).unwrap_or_default()
+    }
+
+    fn invalid(&self, column: &str, value: &str) -> CsvError {
+        CsvError::InvalidValue {
+            line: self.line,
+            column: column.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn date(&self, index: usize, column: &str) -> Result<Option<NaiveDate>, CsvError> {
+        let value = self.field(index);
+        if value.is_empty() {
+            return Ok(None);
+        }
+
+        NaiveDate::parse_from_str(value, &self.options.date_format)
+            .map(Some)
+            .map_err(|_| self.invalid(column, value))
+    }
+
+    fn id(&self, index: usize, column: &str) -> Result<Option<CommodityTypeID>, CsvError> {
+        let value = self.field(index);
+        if value.is_empty() {
+            return Ok(None);
+        }
+
+        CommodityTypeID::from_str(value)
+            .map(Some)
+            .map_err(|_| self.invalid(column, value))
+    }
+
+    fn rate(&self, index: usize, column: &str) -> Result<Option<Decimal>, CsvError> {
+        let value = self.field(index);
+        if value.is_empty() {
+            return Ok(None);
+        }
+
+        let rate = if self.options.decimal_separator == '.' {
+            Decimal::from_str(value)
+        } else if value.contains('.') {
+            return Err(self.invalid(column, value));
+        } else {
+            Decimal::from_str(&value.replace(self.options.decimal_separator, "."))
+        };
+
+        rate.map(Some).map_err(|_| self.invalid(column, value))
+    }
+
+    fn required<T>(&self, value: Option<T>, column: &str) -> Result<T, CsvError> {
+        value.ok_or_else(|| CsvError::MissingValue {
+            line: self.line,
+            column: column.to_string(),
+        })
+    }
+}
+
+/// Find the index of the column with the name `column` in the header.
+fn column_index(headers: &csv::StringRecord, column: &str) -> Result<usize, CsvError> {
+    headers
+        .iter()
+        .position(|header| header.eq_ignore_ascii_case(column))
+        .ok_or_else(|| CsvError::MissingColumn(column.to_string()))
+}
+
+/// Reads [ExchangeRate](ExchangeRate)s from wide form csv, one per row.
+pub struct WideReader<R> {
+    reader: csv::Reader<R>,
+    options: CsvOptions,
+    date_index: usize,
+    base_index: usize,
+    columns: Vec<(usize, CommodityTypeID, String)>,
+    record: csv::StringRecord,
+}
+
+impl<R: io::Read> WideReader<R> {
+    /// Create a new [WideReader](WideReader), reading the header from
+    /// `reader`.
+    pub fn new(reader: R, options: CsvOptions) -> Result<WideReader<R>, CsvError> {
+        let mut reader = options.reader(reader);
+        let headers = reader.headers()?.clone();
+        let date_index = column_index(&headers, "date")?;
+        let base_index = column_index(&headers, "base")?;
+
+        let mut names = BTreeSet::new();
+        for header in &headers {
+            if !names.insert(header.to_ascii_uppercase()) {
+                return Err(CsvError::DuplicateColumn(header.to_string()));
+            }
+        }
+
+        let columns = headers
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != date_index && *index != base_index)
+            .map(|(index, header)| {
+                CommodityTypeID::from_str(header)
+                    .map(|id| (index, id, header.to_string()))
+                    .map_err(|_| CsvError::InvalidColumn(header.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(WideReader {
+            reader,
+            options,
+            date_index,
+            base_index,
+            columns,
+            record: csv::StringRecord::new(),
+        })
+    }
+
+    /// The commodity types with a column in the csv.
+    pub fn commodity_types(&self) -> impl Iterator<Item = &CommodityTypeID> {
+        self.columns.iter().map(|(_, id, _)| id)
+    }
+
+    fn parse_record(&self) -> Result<ExchangeRate, CsvError> {
+        let parser = RecordParser::new(&self.options, &self.record);
+        let mut rates = BTreeMap::new();
+        for (index, id, column) in &self.columns {
+            if let Some(rate) = parser.rate(*index, column)? {
+                rates.insert(*id, rate);
+            }
+        }
+
+        Ok(ExchangeRate {
+            date: parser.date(self.date_index, "date")?,
+            obtained_datetime: None,
+            base: parser.id(self.base_index, "base")?,
+            rates,
+        })
+    }
+}
+
+impl<R: io::Read> Iterator for WideReader<R> {
+    type Item = Result<ExchangeRate, CsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.reader.read_record(&mut self.record) {
+            Ok(true) => Some(self.parse_record()),
+            Ok(false) => None,
+            Err(error) => Some(Err(error.into())),
+        }
+    }
+}
+
+/// Reads [ExchangeRate](ExchangeRate)s from long form csv, combining
+/// consecutive rows with the same `date` and `base`.
+pub struct LongReader<R> {
+    reader: csv::Reader<R>,
+    options: CsvOptions,
+    date_index: usize,
+    base_index: usize,
+    quote_index: usize,
+    rate_index: usize,
+    record: csv::StringRecord,
+    pending: Option<ExchangeRate>,
+}
+
+impl<R: io::Read> LongReader<R> {
+    /// Create a new [LongReader](LongReader), reading the header from
+    /// `reader`.
+    pub fn new(reader: R, options: CsvOptions) -> Result<LongReader<R>, CsvError> {
+        let mut reader = options.reader(reader);
+        let headers = reader.headers()?.clone();
+
+        Ok(LongReader {
+            date_index: column_index(&headers, "date")?,
+            base_index: column_index(&headers, "base")?,
+            quote_index: column_index(&headers, "quote")?,
+            rate_index: column_index(&headers, "rate")?,
+            reader,
+            options,
+            record: csv::StringRecord::new(),
+            pending: None,
+        })
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn parse_record(
+        &self,
+    ) -> Result<
+        (
+            Option<NaiveDate>,
+            Option<CommodityTypeID>,
+            CommodityTypeID,
+            Decimal,
+        ),
+        CsvError,
+    > {
+        let parser = RecordParser::new(&self.options, &self.record);
+        let date = parser.date(self.date_index, "date")?;
+        let base = parser.id(self.base_index, "base")?;
+        let quote = parser.required(parser.id(self.quote_index, "quote")?, "quote")?;
+        let rate = parser.required(parser.rate(self.rate_index, "rate")?, "rate")?;
+        Ok((date, base, quote, rate))
+    }
+}
+
+impl<R: io::Read> Iterator for LongReader<R> {
+    type Item = Result<ExchangeRate, CsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.read_record(&mut self.record) {
+                Ok(true) => {}
+                Ok(false) => return self.pending.take().map(Ok),
+                Err(error) => return Some(Err(error.into())),
+            }
+
+            let (date, base, quote, rate) = match self.parse_record() {
+                Ok(row) => row,
+                Err(error) => return Some(Err(error)),
+            };
+
+            match &mut self.pending {
+                Some(pending) if pending.date == date && pending.base == base => {
+                    if pending.rates.contains_key(&quote) {
+                        let line = self.record.position().map(|p| p.line()).unwrap_or_default();
+                        return Some(Err(CsvError::DuplicateRate { line, quote }));
+                    }
+                    pending.rates.insert(quote, rate);
+                }
+                _ => {
+                    let mut rates = BTreeMap::new();
+                    rates.insert(quote, rate);
+                    let next = ExchangeRate {
+                        date,
+                        obtained_datetime: None,
+                        base,
+                        rates,
+                    };
+
+                    if let Some(previous) = self.pending.replace(next) {
+                        return Some(Ok(previous));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Write `exchange_rates` as wide form csv, with a column for every
+/// commodity type present in any of the exchange rates (sorted by id).
+///
+/// # Example
+/// ```
+/// # use commodity::exchange_rate::ExchangeRate;
+/// # use commodity::exchange_rate::csv::{write_wide, CsvOptions};
+/// # use commodity::CommodityTypeID;
+/// use chrono::NaiveDate;
+/// use rust_decimal::Decimal;
+/// use std::collections::BTreeMap;
+/// use std::str::FromStr;
+///
+/// let mut rates = BTreeMap::new();
+/// rates.insert(CommodityTypeID::from_str("AUD").unwrap(), Decimal::from_str("1.49").unwrap());
+/// let exchange_rate = ExchangeRate {
+///     date: NaiveDate::from_ymd_opt(2020, 2, 7),
+///     obtained_datetime: None,
+///     base: Some(CommodityTypeID::from_str("USD").unwrap()),
+///     rates,
+/// };
+///
+/// let mut data = Vec::new();
+/// write_wide(&mut data, &[exchange_rate], &CsvOptions::new()).unwrap();
+/// assert_eq!("date,base,AUD\n2020-02-07,USD,1.49\n", String::from_utf8(data).unwrap());
+/// ```
+pub fn write_wide<W: io::Write>(
+    writer: W,
+    exchange_rates: &[ExchangeRate],
+    options: &CsvOptions,
+) -> Result<(), CsvError> {
+    let columns: BTreeSet<CommodityTypeID> = exchange_rates
+        .iter()
+        .flat_map(|exchange_rate| exchange_rate.rates.keys().copied())
+        .collect();
+
+    let mut writer = options.writer(writer);
+    let mut header = vec!["date".to_string(), "base".to_string()];
+    header.extend(columns.iter().map(|id| id.to_string()));
+    writer.write_record(&header)?;
+
+    for exchange_rate in exchange_rates {
+        let mut record = vec![
+            options.format_date(exchange_rate.date),
+            exchange_rate
+                .base
+                .map(|base| base.to_string())
+                .unwrap_or_default(),
+        ];
+        record.extend(columns.iter().map(|id| {
+            exchange_rate
+                .get_rate(id)
+                .map(|rate| options.format_rate(rate))
+                .unwrap_or_default()
+        }));
+        writer.write_record(&record)?;
+    }
+
+    writer.flush().map_err(csv::Error::from)?;
+    Ok(())
+}
+
+/// Write `exchange_rates` as long form csv, with one row per rate.
+pub fn write_long<'a, W, I>(
+    writer: W,
+    exchange_rates: I,
+    options: &CsvOptions,
+) -> Result<(), CsvError>
+where
+    W: io::Write,
+    I: IntoIterator<Item = &'a ExchangeRate>,
+{
+    let mut writer = options.writer(writer);
+    writer.write_record(["date", "base", "quote", "rate"])?;
+
+    for exchange_rate in exchange_rates {
+        let date = options.format_date(exchange_rate.date);
+        let base = exchange_rate
+            .base
+            .map(|base| base.to_string())
+            .unwrap_or_default();
+
+        for (quote, rate) in &exchange_rate.rates {
+            writer.write_record([
+                date.as_str(),
+                base.as_str(),
+                quote.to_string().as_str(),
+                options.format_rate(rate).as_str(),
+            ])?;
+        }
+    }
+
+    writer.flush().map_err(csv::Error::from)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_long, write_wide, CsvError, CsvOptions, LongReader, WideReader};
+    use crate::exchange_rate::ExchangeRate;
+    use crate::CommodityTypeID;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    fn test_exchange_rates() -> Vec<ExchangeRate> {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let nzd = CommodityTypeID::from_str("NZD").unwrap();
+
+        let mut rates_1 = BTreeMap::new();
+        rates_1.insert(aud, Decimal::from_str("1.49").unwrap());
+        rates_1.insert(nzd, Decimal::from_str("1.55").unwrap());
+        let mut rates_2 = BTreeMap::new();
+        rates_2.insert(aud, Decimal::from_str("1.5").unwrap());
+
+        vec![
+            ExchangeRate {
+                date: NaiveDate::from_ymd_opt(2020, 2, 7),
+                obtained_datetime: None,
+                base: Some(usd),
+                rates: rates_1,
+            },
+            ExchangeRate {
+                date: NaiveDate::from_ymd_opt(2020, 2, 8),
+                obtained_datetime: None,
+                base: Some(usd),
+                rates: rates_2,
+            },
+        ]
+    }
+
+    #[test]
+    fn wide_round_trip() {
+        let exchange_rates = test_exchange_rates();
+        let options = CsvOptions::new()
+            .with_delimiter(b';')
+            .with_decimal_separator(',')
+            .with_date_format("%d/%m/%Y");
+
+        let mut data = Vec::new();
+        write_wide(&mut data, &exchange_rates, &options).unwrap();
+        let data = String::from_utf8(data).unwrap();
+        assert_eq!(
+            "date;base;AUD;NZD\n07/02/2020;USD;1,49;1,55\n08/02/2020;USD;1,5;\n",
+            data
+        );
+
+        let read = WideReader::new(data.as_bytes(), options)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(exchange_rates, read);
+    }
+
+    #[test]
+    fn long_round_trip() {
+        let exchange_rates = test_exchange_rates();
+
+        let mut data = Vec::new();
+        write_long(&mut data, &exchange_rates, &CsvOptions::new()).unwrap();
+        let data = String::from_utf8(data).unwrap();
+        assert_eq!(
+            "date,base,quote,rate\n\
+             2020-02-07,USD,AUD,1.49\n\
+             2020-02-07,USD,NZD,1.55\n\
+             2020-02-08,USD,AUD,1.5\n",
+            data
+        );
+
+        let read = LongReader::new(data.as_bytes(), CsvOptions::new())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(exchange_rates, read);
+    }
+
+    /// The columns of the long form can be in any order, and the
+    /// rows for different dates can be interleaved.
+    #[test]
+    fn long_column_order() {
+        let data = "rate,quote,base,date\n1.49,AUD,USD,2020-02-07\n0.9,EUR,USD,2020-02-08\n1.55,NZD,USD,2020-02-07\n";
+        let read = LongReader::new(data.as_bytes(), CsvOptions::new())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(3, read.len());
+        assert_eq!(NaiveDate::from_ymd_opt(2020, 2, 8), read[1].date);
+    }
+
+    /// Invalid rows are reported with their line number, and the
+    /// following rows can still be read.
+    #[test]
+    fn row_errors() {
+        let data = "date,base,AUD\n2020-02-07,USD,1.49\n2020-02-08,USD,abc\n2020-02-31,USD,1.5\n2020-02-10,USD,1.51\n";
+        let mut reader = WideReader::new(data.as_bytes(), CsvOptions::new()).unwrap();
+        assert_eq!(
+            vec![&CommodityTypeID::from_str("AUD").unwrap()],
+            reader.commodity_types().collect::<Vec<_>>()
+        );
+
+        assert!(reader.next().unwrap().is_ok());
+        match reader.next().unwrap() {
+            Err(CsvError::InvalidValue {
+                line,
+                column,
+                value,
+            }) => {
+                assert_eq!(3, line);
+                assert_eq!("AUD", column);
+                assert_eq!("abc", value);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        match reader.next().unwrap() {
+            Err(CsvError::InvalidValue { line, column, .. }) => {
+                assert_eq!(4, line);
+                assert_eq!("date", column);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().is_none());
+
+        let data = "date,base,quote,rate\n2020-02-07,USD,AUD,\n2020-02-07,USD,AUD,1.2,3\n";
+        let mut reader = LongReader::new(data.as_bytes(), CsvOptions::new()).unwrap();
+        match reader.next().unwrap() {
+            Err(CsvError::MissingValue { line, column }) => {
+                assert_eq!(2, line);
+                assert_eq!("rate", column);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(matches!(reader.next().unwrap(), Err(CsvError::Csv(_))));
+    }
+
+    /// A rate repeated within the same date and base is reported, and
+    /// the first rate is kept.
+    #[test]
+    fn duplicate_rate() {
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let data = "date,base,quote,rate\n2020-02-07,USD,AUD,1.49\n2020-02-07,USD,AUD,1.5\n2020-02-07,USD,NZD,1.55\n";
+        let mut reader = LongReader::new(data.as_bytes(), CsvOptions::new()).unwrap();
+        match reader.next().unwrap() {
+            Err(CsvError::DuplicateRate { line, quote }) => {
+                assert_eq!(3, line);
+                assert_eq!(aud, quote);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        let exchange_rate = reader.next().unwrap().unwrap();
+        assert_eq!(
+            Some(&Decimal::from_str("1.49").unwrap()),
+            exchange_rate.get_rate(&aud)
+        );
+        assert_eq!(2, exchange_rate.rates.len());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn invalid_header() {
+        assert!(matches!(
+            WideReader::new("date,AUD\n".as_bytes(), CsvOptions::new()),
+            Err(CsvError::MissingColumn(column)) if column == "base"
+        ));
+        assert!(matches!(
+            WideReader::new(
+                "date,base,Australian dollar\n".as_bytes(),
+                CsvOptions::new()
+            ),
+            Err(CsvError::InvalidColumn(_))
+        ));
+        assert!(matches!(
+            WideReader::new("date,base,AUD,NZD,AUD\n".as_bytes(), CsvOptions::new()),
+            Err(CsvError::DuplicateColumn(column)) if column == "AUD"
+        ));
+        assert!(matches!(
+            WideReader::new("date,base,AUD,Date\n".as_bytes(), CsvOptions::new()),
+            Err(CsvError::DuplicateColumn(column)) if column == "Date"
+        ));
+        assert!(matches!(
+            LongReader::new("date,base,rate\n".as_bytes(), CsvOptions::new()),
+            Err(CsvError::MissingColumn(column)) if column == "quote"
+        ));
+    }
+}
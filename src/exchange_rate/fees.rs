@@ -0,0 +1,496 @@
+//! [Fee](Fee)s and markups charged on a conversion with
+//! [ExchangeRate::convert_with_fees()](ExchangeRate::convert_with_fees),
+//! and the resulting [ConversionBreakdown](ConversionBreakdown).
+
+use super::{ExchangeRate, ExchangeRateError};
+use crate::{Commodity, CommodityTypeID};
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+#[cfg(feature = "serde-support")]
+use serde::{Deserialize, Serialize};
+
+/// An error associated with charging [Fee](Fee)s on a conversion.
+#[derive(Error, Debug, PartialEq)]
+pub enum FeeError {
+    #[error("The fees {fees} are larger than the amount {amount} they are charged on.")]
+    FeesExceedAmount { amount: Commodity, fees: Commodity },
+    #[error("There was an error converting the fees with the exchange rate.")]
+    ExchangeRate(#[from] ExchangeRateError),
+}
+
+/// When a [Fee](Fee) is charged, relative to the conversion.
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeeTiming {
+    /// The fee is charged in the source commodity type, and deducted
+    /// before the conversion.
+    BeforeConversion,
+    /// The fee is charged in the target commodity type, and deducted
+    /// from the converted amount.
+    AfterConversion,
+}
+
+/// How the amount of a [Fee](Fee) is calculated.
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeeAmount {
+    /// A proportion of the amount the fee is charged on, e.g. `0.03`
+    /// for a 3% markup.
+    Percentage(Decimal),
+    /// A fixed amount, which is converted to the commodity type the fee
+    /// is charged in if required.
+    Fixed(Commodity),
+}
+
+/// A fee or markup applied to a conversion.
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fee {
+    /// A description of this fee.
+    pub name: String,
+    /// How the amount of this fee is calculated.
+    pub amount: FeeAmount,
+    /// When this fee is charged.
+    pub timing: FeeTiming,
+    /// The minimum amount charged for this fee.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub minimum: Option<Commodity>,
+    /// The maximum amount charged for this fee.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub maximum: Option<Commodity>,
+    /// The commodity type this fee is charged in. The fee is converted
+    /// with the exchange rate to the commodity type it is deducted
+    /// from. When `None`, the fee is charged in the commodity type it
+    /// is deducted from.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub currency: Option<CommodityTypeID>,
+}
+
+impl Fee {
+    /// Create a new [Fee](Fee), without a minimum or maximum.
+    pub fn new<S: Into<String>>(name: S, amount: FeeAmount, timing: FeeTiming) -> Fee {
+        Fee {
+            name: name.into(),
+            amount,
+            timing,
+            minimum: None,
+            maximum: None,
+            currency: None,
+        }
+    }
+
+    /// Create a percentage markup on the converted amount, e.g. `0.03`
+    /// for 3%.
+    pub fn markup(percentage: Decimal) -> Fee {
+        Fee::new(
+            "markup",
+            FeeAmount::Percentage(percentage),
+            FeeTiming::AfterConversion,
+        )
+    }
+
+    /// Set the `minimum` amount charged for this fee.
+    pub fn with_minimum(mut self, minimum: Commodity) -> Fee {
+        self.minimum = Some(minimum);
+        self
+    }
+
+    /// Set the `maximum` amount charged for this fee.
+    pub fn with_maximum(mut self, maximum: Commodity) -> Fee {
+        self.maximum = Some(maximum);
+        self
+    }
+
+    /// Set the commodity type this fee is charged in, e.g. a fixed
+    /// `2 USD` fee on a conversion from `EUR` to `JPY`.
+    pub fn with_currency(mut self, currency: CommodityTypeID) -> Fee {
+        self.currency = Some(currency);
+        self
+    }
+
+    /// Calculate the amount of this fee charged on `commodity`,
+    /// returning the amount charged (in the fee's `currency`) and the
+    /// amount deducted (in the same commodity type as `commodity`).
+    fn calculate(
+        &self,
+        exchange_rate: &ExchangeRate,
+        commodity: &Commodity,
+    ) -> Result<(Commodity, Commodity), ExchangeRateError> {
+        let type_id = self.currency.unwrap_or(commodity.type_id);
+        let in_type = |fixed: &Commodity| -> Result<Decimal, ExchangeRateError> {
+            Ok(convert_to(exchange_rate, *fixed, type_id)?.value)
+        };
+
+        let mut value = match &self.amount {
+            FeeAmount::Percentage(percentage) => {
+                let amount = in_type(commodity)?;
+                amount
+                    .checked_mul(*percentage)
+                    .ok_or(ExchangeRateError::MultiplyOverflow(amount, *percentage))?
+            }
+            FeeAmount::Fixed(fixed) => in_type(fixed)?,
+        };
+
+        if let Some(minimum) = &self.minimum {
+            value = value.max(in_type(minimum)?);
+        }
+
+        if let Some(maximum) = &self.maximum {
+            value = value.min(in_type(maximum)?);
+        }
+
+        let charged = Commodity::new(value, type_id);
+        let deducted = convert_to(exchange_rate, charged, commodity.type_id)?;
+        Ok((charged, deducted))
+    }
+}
+
+/// Convert `commodity` to `type_id` using `exchange_rate`, unless it
+/// is already of that commodity type.
+fn convert_to(
+    exchange_rate: &ExchangeRate,
+    commodity: Commodity,
+    type_id: CommodityTypeID,
+) -> Result<Commodity, ExchangeRateError> {
+    if commodity.type_id == type_id {
+        Ok(commodity)
+    } else {
+        exchange_rate.convert(commodity, type_id)
+    }
+}
+
+/// A [Fee](Fee) which was charged during a conversion.
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedFee {
+    /// The description of the fee.
+    pub name: String,
+    /// When the fee was charged.
+    pub timing: FeeTiming,
+    /// The amount charged, in the fee's `currency` if it has one.
+    pub amount: Commodity,
+    /// The amount deducted, in the source commodity type for
+    /// [FeeTiming::BeforeConversion](FeeTiming::BeforeConversion), or
+    /// the target commodity type for
+    /// [FeeTiming::AfterConversion](FeeTiming::AfterConversion).
+    pub deducted: Commodity,
+}
+
+/// The result of a conversion with fees, see
+/// [ExchangeRate::convert_with_fees()](ExchangeRate::convert_with_fees).
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionBreakdown {
+    /// The amount being converted.
+    pub original: Commodity,
+    /// The converted amount (in the target commodity type), after
+    /// fees charged before the conversion, but before fees charged
+    /// after the conversion.
+    pub gross: Commodity,
+    /// The fees charged, in the order they were applied.
+    pub fees: Vec<AppliedFee>,
+    /// The amount received after all fees.
+    pub net: Commodity,
+    /// The effective rate, the `net` amount per unit of the `original`
+    /// amount, or `None` if the `original` amount is zero.
+    pub effective_rate: Option<Decimal>,
+}
+
+impl ConversionBreakdown {
+    /// The total of the fees charged in the specified commodity type
+    /// (see [AppliedFee::amount](AppliedFee::amount)).
+    pub fn total_fees(&self, type_id: CommodityTypeID) -> Result<Commodity, ExchangeRateError> {
+        let mut total = Decimal::ZERO;
+        for fee in self.fees.iter().filter(|fee| fee.amount.type_id == type_id) {
+            total = total
+                .checked_add(fee.amount.value)
+                .ok_or(ExchangeRateError::AddOverflow(total, fee.amount.value))?;
+        }
+        Ok(Commodity::new(total, type_id))
+    }
+}
+
+/// Apply the fees with the specified `timing` to `commodity`,
+/// returning the remaining amount.
+fn apply_fees(
+    exchange_rate: &ExchangeRate,
+    fees: &[Fee],
+    timing: FeeTiming,
+    commodity: Commodity,
+    applied: &mut Vec<AppliedFee>,
+) -> Result<Commodity, FeeError> {
+    let mut total = Decimal::ZERO;
+
+    for fee in fees.iter().filter(|fee| fee.timing == timing) {
+        let (amount, deducted) = fee.calculate(exchange_rate, &commodity)?;
+        total = total
+            .checked_add(deducted.value)
+            .ok_or(ExchangeRateError::AddOverflow(total, deducted.value))?;
+        applied.push(AppliedFee {
+            name: fee.name.clone(),
+            timing,
+            amount,
+            deducted,
+        });
+    }
+
+    if total > commodity.value {
+        return Err(FeeError::FeesExceedAmount {
+            amount: commodity,
+            fees: Commodity::new(total, commodity.type_id),
+        });
+    }
+
+    Ok(Commodity::new(commodity.value - total, commodity.type_id))
+}
+
+impl ExchangeRate {
+    /// Convert the [CommodityType](crate::CommodityType) of a
+    /// [Commodity](Commodity) to another
+    /// [CommodityType](crate::CommodityType) using this
+    /// [ExchangeRate](ExchangeRate), charging the specified `fees`,
+    /// and returning a [ConversionBreakdown](ConversionBreakdown).
+    /// Fees with a `currency` are converted with this exchange rate to
+    /// the commodity type they are deducted from.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::exchange_rate::{ExchangeRate, Fee, FeeAmount, FeeTiming};
+    /// # use commodity::{Commodity, CommodityTypeID};
+    /// use rust_decimal::Decimal;
+    /// use std::collections::BTreeMap;
+    /// use std::str::FromStr;
+    ///
+    /// let aud = CommodityTypeID::from_str("AUD").unwrap();
+    /// let usd = CommodityTypeID::from_str("USD").unwrap();
+    /// let mut rates = BTreeMap::new();
+    /// rates.insert(usd, Decimal::from_str("0.65").unwrap());
+    /// let exchange_rate = ExchangeRate {
+    ///     date: None,
+    ///     obtained_datetime: None,
+    ///     base: Some(aud),
+    ///     rates,
+    /// };
+    ///
+    /// let fees = vec![
+    ///     Fee::new(
+    ///         "transfer fee",
+    ///         FeeAmount::Fixed(Commodity::from_str("10 AUD").unwrap()),
+    ///         FeeTiming::BeforeConversion,
+    ///     ),
+    ///     Fee::markup(Decimal::from_str("0.02").unwrap()),
+    /// ];
+    ///
+    /// let breakdown = exchange_rate
+    ///     .convert_with_fees(Commodity::from_str("1010 AUD").unwrap(), usd, &fees)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(Commodity::from_str("650 USD").unwrap(), breakdown.gross);
+    /// assert_eq!(Commodity::from_str("13 USD").unwrap(), breakdown.fees[1].amount);
+    /// assert_eq!(Commodity::from_str("637 USD").unwrap(), breakdown.net);
+    /// ```
+    pub fn convert_with_fees(
+        &self,
+        commodity: Commodity,
+        target_commodity_type: CommodityTypeID,
+        fees: &[Fee],
+    ) -> Result<ConversionBreakdown, FeeError> {
+        let mut applied = Vec::new();
+
+        let principal = apply_fees(
+            self,
+            fees,
+            FeeTiming::BeforeConversion,
+            commodity,
+            &mut applied,
+        )?;
+
+        let gross = if principal.type_id == target_commodity_type {
+            principal
+        } else {
+            self.convert(principal, target_commodity_type)?
+        };
+
+        let net = apply_fees(self, fees, FeeTiming::AfterConversion, gross, &mut applied)?;
+
+        let effective_rate = if commodity.value.is_zero() {
+            None
+        } else {
+            Some(net.value.checked_div(commodity.value).ok_or(
+                ExchangeRateError::DivideOverflow(net.value, commodity.value),
+            )?)
+        };
+
+        Ok(ConversionBreakdown {
+            original: commodity,
+            gross,
+            fees: applied,
+            net,
+            effective_rate,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AppliedFee, ConversionBreakdown, Fee, FeeAmount, FeeError, FeeTiming};
+    use crate::exchange_rate::{ExchangeRate, ExchangeRateError};
+    use crate::{Commodity, CommodityTypeID};
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    fn test_exchange_rate() -> ExchangeRate {
+        let mut rates = BTreeMap::new();
+        rates.insert(
+            CommodityTypeID::from_str("EUR").unwrap(),
+            Decimal::from_str("0.8").unwrap(),
+        );
+        rates.insert(
+            CommodityTypeID::from_str("JPY").unwrap(),
+            Decimal::from_str("120").unwrap(),
+        );
+        ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(CommodityTypeID::from_str("USD").unwrap()),
+            rates,
+        }
+    }
+
+    fn commodity(commodity: &str) -> Commodity {
+        Commodity::from_str(commodity).unwrap()
+    }
+
+    /// Percentage fees with minimums and maximums, charged before and
+    /// after the conversion, with fixed fees in another currency.
+    #[test]
+    fn fee_breakdown() {
+        let exchange_rate = test_exchange_rate();
+        let eur = CommodityTypeID::from_str("EUR").unwrap();
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+
+        let fees = vec![
+            // 1% charged in USD, with a minimum of 2 EUR (2.5 USD)
+            Fee::new(
+                "card fee",
+                FeeAmount::Percentage(Decimal::from_str("0.01").unwrap()),
+                FeeTiming::BeforeConversion,
+            )
+            .with_minimum(commodity("2 EUR")),
+            // 3% markup charged in EUR, capped at 2 EUR
+            Fee::markup(Decimal::from_str("0.03").unwrap()).with_maximum(commodity("2 EUR")),
+            // fixed fee declared in USD, charged in EUR
+            Fee::new(
+                "delivery",
+                FeeAmount::Fixed(commodity("1.25 USD")),
+                FeeTiming::AfterConversion,
+            ),
+        ];
+
+        let breakdown = exchange_rate
+            .convert_with_fees(commodity("100 USD"), eur, &fees)
+            .unwrap();
+
+        assert_eq!(commodity("2.5 USD"), breakdown.fees[0].amount);
+        assert_eq!(commodity("78 EUR"), breakdown.gross);
+        assert_eq!(commodity("2 EUR"), breakdown.fees[1].amount);
+        assert_eq!(commodity("1 EUR"), breakdown.fees[2].amount);
+        assert_eq!(commodity("1 EUR"), breakdown.fees[2].deducted);
+        assert_eq!(commodity("75 EUR"), breakdown.net);
+        assert_eq!(
+            Some(Decimal::from_str("0.75").unwrap()),
+            breakdown.effective_rate
+        );
+        assert_eq!(commodity("3 EUR"), breakdown.total_fees(eur).unwrap());
+        assert_eq!(commodity("2.5 USD"), breakdown.total_fees(usd).unwrap());
+
+        let breakdown = exchange_rate
+            .convert_with_fees(commodity("1000 USD"), eur, &fees)
+            .unwrap();
+        assert_eq!(commodity("10 USD"), breakdown.fees[0].amount);
+        assert_eq!(commodity("2 EUR"), breakdown.fees[1].amount);
+        assert_eq!(commodity("789 EUR"), breakdown.net);
+
+        // fees can't be larger than the amount
+        assert_eq!(
+            FeeError::FeesExceedAmount {
+                amount: commodity("1 USD"),
+                fees: commodity("2.5 USD"),
+            },
+            exchange_rate
+                .convert_with_fees(commodity("1 USD"), eur, &fees)
+                .unwrap_err()
+        );
+    }
+
+    /// Fees declared in a commodity type other than the source or the
+    /// target, converted through the exchange rate.
+    #[test]
+    fn fee_currency() {
+        let exchange_rate = test_exchange_rate();
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let jpy = CommodityTypeID::from_str("JPY").unwrap();
+
+        let fees = vec![
+            Fee::new(
+                "transfer fee",
+                FeeAmount::Fixed(commodity("2 USD")),
+                FeeTiming::BeforeConversion,
+            )
+            .with_currency(usd),
+            Fee::markup(Decimal::from_str("0.01").unwrap()).with_currency(usd),
+        ];
+
+        let breakdown = exchange_rate
+            .convert_with_fees(commodity("100 EUR"), jpy, &fees)
+            .unwrap();
+
+        assert_eq!(commodity("2 USD"), breakdown.fees[0].amount);
+        assert_eq!(commodity("1.6 EUR"), breakdown.fees[0].deducted);
+        assert_eq!(commodity("14760 JPY"), breakdown.gross);
+        assert_eq!(commodity("1.23 USD"), breakdown.fees[1].amount);
+        assert_eq!(commodity("147.6 JPY"), breakdown.fees[1].deducted);
+        assert_eq!(commodity("14612.4 JPY"), breakdown.net);
+        assert_eq!(commodity("3.23 USD"), breakdown.total_fees(usd).unwrap());
+    }
+
+    #[test]
+    fn total_fees_overflow() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let fee = AppliedFee {
+            name: "fee".to_string(),
+            timing: FeeTiming::AfterConversion,
+            amount: Commodity::new(Decimal::MAX, usd),
+            deducted: Commodity::new(Decimal::MAX, usd),
+        };
+        let breakdown = ConversionBreakdown {
+            original: Commodity::zero(usd),
+            gross: Commodity::zero(usd),
+            fees: vec![fee.clone(), fee],
+            net: Commodity::zero(usd),
+            effective_rate: None,
+        };
+
+        assert_eq!(
+            ExchangeRateError::AddOverflow(Decimal::MAX, Decimal::MAX),
+            breakdown.total_fees(usd).unwrap_err()
+        );
+    }
+
+    /// The effective rate of a conversion of nothing is undefined.
+    #[test]
+    fn zero_amount() {
+        let exchange_rate = test_exchange_rate();
+        let eur = CommodityTypeID::from_str("EUR").unwrap();
+        let fees = vec![Fee::markup(Decimal::from_str("0.03").unwrap())];
+
+        let breakdown = exchange_rate
+            .convert_with_fees(commodity("0 USD"), eur, &fees)
+            .unwrap();
+
+        assert_eq!(commodity("0 EUR"), breakdown.net);
+        assert_eq!(None, breakdown.effective_rate);
+    }
+}
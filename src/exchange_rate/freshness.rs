@@ -0,0 +1,271 @@
+//! Limiting the age of the [ExchangeRate](ExchangeRate)s used for a
+//! conversion with a [FreshnessPolicy](FreshnessPolicy).
+
+use super::{ExchangeRate, ExchangeRateError};
+use crate::{Commodity, CommodityTypeID};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use rust_decimal::Decimal;
+
+/// A policy which limits the age of the [ExchangeRate](ExchangeRate)s
+/// which can be used for a conversion, see
+/// [ExchangeRate::convert_fresh()](ExchangeRate::convert_fresh).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FreshnessPolicy {
+    /// The exchange rate needs to have been obtained no more than
+    /// `max_age` before `now`. The age is calculated from the
+    /// `obtained_datetime` of the exchange rate, or from the start of
+    /// its `date` if it has no `obtained_datetime`.
+    MaxAge {
+        now: DateTime<Utc>,
+        max_age: Duration,
+    },
+    /// The `date` of the exchange rate needs to be no more than
+    /// `max_age` before `date` (such as the date of the transaction
+    /// being converted).
+    MaxAgeFromDate { date: NaiveDate, max_age: Duration },
+}
+
+impl FreshnessPolicy {
+    /// Create a [FreshnessPolicy::MaxAge](FreshnessPolicy::MaxAge)
+    /// policy.
+    pub fn max_age(now: DateTime<Utc>, max_age: Duration) -> FreshnessPolicy {
+        FreshnessPolicy::MaxAge { now, max_age }
+    }
+
+    /// Create a
+    /// [FreshnessPolicy::MaxAgeFromDate](FreshnessPolicy::MaxAgeFromDate)
+    /// policy.
+    pub fn max_age_from_date(date: NaiveDate, max_age: Duration) -> FreshnessPolicy {
+        FreshnessPolicy::MaxAgeFromDate { date, max_age }
+    }
+}
+
+impl ExchangeRate {
+    /// The age of this exchange rate, according to the specified
+    /// [FreshnessPolicy](FreshnessPolicy). The age is negative if the
+    /// exchange rate is dated in the future.
+    pub fn age(&self, policy: &FreshnessPolicy) -> Result<Duration, ExchangeRateError> {
+        match policy {
+            FreshnessPolicy::MaxAge { now, .. } => {
+                let obtained = match (self.obtained_datetime, self.date) {
+                    (Some(obtained_datetime), _) => obtained_datetime,
+                    (None, Some(date)) => {
+                        DateTime::from_naive_utc_and_offset(date.and_time(NaiveTime::MIN), Utc)
+                    }
+                    (None, None) => return Err(ExchangeRateError::UnknownAge),
+                };
+                Ok(*now - obtained)
+            }
+            FreshnessPolicy::MaxAgeFromDate { date, .. } => {
+                let rate_date = self.date.ok_or(ExchangeRateError::MissingDate)?;
+                Ok(*date - rate_date)
+            }
+        }
+    }
+
+    /// Check that this exchange rate satisfies the specified
+    /// [FreshnessPolicy](FreshnessPolicy), returning an
+    /// [ExchangeRateError::StaleRate](ExchangeRateError::StaleRate)
+    /// if it is too old, or an
+    /// [ExchangeRateError::FutureRate](ExchangeRateError::FutureRate)
+    /// if it is dated in the future.
+    pub fn check_freshness(&self, policy: &FreshnessPolicy) -> Result<(), ExchangeRateError> {
+        let max_age = match policy {
+            FreshnessPolicy::MaxAge { max_age, .. } => *max_age,
+            FreshnessPolicy::MaxAgeFromDate { max_age, .. } => *max_age,
+        };
+
+        let age = self.age(policy)?;
+        if age < Duration::zero() {
+            return Err(ExchangeRateError::FutureRate(-age));
+        }
+        if age > max_age {
+            return Err(ExchangeRateError::StaleRate { age, max_age });
+        }
+
+        Ok(())
+    }
+
+    /// Convert the [CommodityType](crate::CommodityType) of a
+    /// [Commodity](Commodity) to another
+    /// [CommodityType](crate::CommodityType) using this
+    /// [ExchangeRate](ExchangeRate), if it satisfies the specified
+    /// [FreshnessPolicy](FreshnessPolicy).
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::exchange_rate::{ExchangeRate, ExchangeRateError, FreshnessPolicy};
+    /// # use commodity::{Commodity, CommodityTypeID};
+    /// use chrono::{Duration, TimeZone, Utc};
+    /// use rust_decimal::Decimal;
+    /// use std::collections::BTreeMap;
+    /// use std::str::FromStr;
+    ///
+    /// let usd = CommodityTypeID::from_str("USD").unwrap();
+    /// let aud = CommodityTypeID::from_str("AUD").unwrap();
+    /// let mut rates = BTreeMap::new();
+    /// rates.insert(aud, Decimal::from_str("1.5").unwrap());
+    /// let exchange_rate = ExchangeRate {
+    ///     date: None,
+    ///     obtained_datetime: Some(Utc.with_ymd_and_hms(2020, 2, 7, 9, 0, 0).unwrap()),
+    ///     base: Some(usd),
+    ///     rates,
+    /// };
+    ///
+    /// let usd_10 = Commodity::from_str("10 USD").unwrap();
+    /// let now = Utc.with_ymd_and_hms(2020, 2, 7, 10, 0, 0).unwrap();
+    /// let policy = FreshnessPolicy::max_age(now, Duration::minutes(90));
+    /// assert_eq!(
+    ///     Commodity::from_str("15 AUD").unwrap(),
+    ///     exchange_rate.convert_fresh(usd_10, aud, &policy).unwrap()
+    /// );
+    ///
+    /// let policy = FreshnessPolicy::max_age(now, Duration::minutes(30));
+    /// assert_eq!(
+    ///     ExchangeRateError::StaleRate {
+    ///         age: Duration::hours(1),
+    ///         max_age: Duration::minutes(30)
+    ///     },
+    ///     exchange_rate.convert_fresh(usd_10, aud, &policy).unwrap_err()
+    /// );
+    /// ```
+    pub fn convert_fresh(
+        &self,
+        commodity: Commodity,
+        target_commodity_type: CommodityTypeID,
+        policy: &FreshnessPolicy,
+    ) -> Result<Commodity, ExchangeRateError> {
+        self.check_freshness(policy)?;
+        self.convert(commodity, target_commodity_type)
+    }
+
+    /// Get the rate between two commodity types using this
+    /// [ExchangeRate](ExchangeRate) (see
+    /// [rate_between()](ExchangeRate::rate_between)), if it satisfies
+    /// the specified [FreshnessPolicy](FreshnessPolicy).
+    pub fn rate_between_fresh(
+        &self,
+        from: &CommodityTypeID,
+        to: &CommodityTypeID,
+        policy: &FreshnessPolicy,
+    ) -> Result<Option<Decimal>, ExchangeRateError> {
+        self.check_freshness(policy)?;
+        self.rate_between(from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FreshnessPolicy;
+    use crate::exchange_rate::{ExchangeRate, ExchangeRateError};
+    use crate::{Commodity, CommodityTypeID};
+    use chrono::{Duration, NaiveDate, TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    fn test_exchange_rate() -> ExchangeRate {
+        let mut rates = BTreeMap::new();
+        rates.insert(
+            CommodityTypeID::from_str("AUD").unwrap(),
+            Decimal::from_str("1.5").unwrap(),
+        );
+        ExchangeRate {
+            date: Some(NaiveDate::from_ymd_opt(2020, 2, 7).unwrap()),
+            obtained_datetime: None,
+            base: Some(CommodityTypeID::from_str("USD").unwrap()),
+            rates,
+        }
+    }
+
+    #[test]
+    fn max_age() {
+        let mut exchange_rate = test_exchange_rate();
+        let now = Utc.with_ymd_and_hms(2020, 2, 8, 6, 0, 0).unwrap();
+
+        // uses the start of the date
+        let policy = FreshnessPolicy::max_age(now, Duration::days(1));
+        assert_eq!(Duration::hours(30), exchange_rate.age(&policy).unwrap());
+        assert_eq!(
+            ExchangeRateError::StaleRate {
+                age: Duration::hours(30),
+                max_age: Duration::days(1),
+            },
+            exchange_rate.check_freshness(&policy).unwrap_err()
+        );
+
+        exchange_rate.obtained_datetime = Some(Utc.with_ymd_and_hms(2020, 2, 7, 16, 0, 0).unwrap());
+        assert_eq!(Duration::hours(14), exchange_rate.age(&policy).unwrap());
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        assert_eq!(
+            Commodity::from_str("15 AUD").unwrap(),
+            exchange_rate
+                .convert_fresh(Commodity::from_str("10 USD").unwrap(), aud, &policy)
+                .unwrap()
+        );
+
+        // obtained after now
+        exchange_rate.obtained_datetime = Some(Utc.with_ymd_and_hms(2020, 2, 8, 7, 0, 0).unwrap());
+        assert_eq!(Duration::hours(-1), exchange_rate.age(&policy).unwrap());
+        assert_eq!(
+            ExchangeRateError::FutureRate(Duration::hours(1)),
+            exchange_rate.check_freshness(&policy).unwrap_err()
+        );
+
+        exchange_rate.obtained_datetime = None;
+        exchange_rate.date = None;
+        assert_eq!(
+            ExchangeRateError::UnknownAge,
+            exchange_rate.check_freshness(&policy).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn max_age_from_date() {
+        let mut exchange_rate = test_exchange_rate();
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+
+        let policy = FreshnessPolicy::max_age_from_date(
+            NaiveDate::from_ymd_opt(2020, 2, 10).unwrap(),
+            Duration::days(3),
+        );
+        assert_eq!(
+            Some(Decimal::from_str("1.5").unwrap()),
+            exchange_rate
+                .rate_between_fresh(&usd, &aud, &policy)
+                .unwrap()
+        );
+
+        let policy = FreshnessPolicy::max_age_from_date(
+            NaiveDate::from_ymd_opt(2020, 2, 11).unwrap(),
+            Duration::days(3),
+        );
+        assert_eq!(
+            ExchangeRateError::StaleRate {
+                age: Duration::days(4),
+                max_age: Duration::days(3),
+            },
+            exchange_rate
+                .rate_between_fresh(&usd, &aud, &policy)
+                .unwrap_err()
+        );
+
+        let policy = FreshnessPolicy::max_age_from_date(
+            NaiveDate::from_ymd_opt(2020, 2, 6).unwrap(),
+            Duration::days(3),
+        );
+        assert_eq!(
+            ExchangeRateError::FutureRate(Duration::days(1)),
+            exchange_rate
+                .rate_between_fresh(&usd, &aud, &policy)
+                .unwrap_err()
+        );
+
+        exchange_rate.date = None;
+        assert_eq!(
+            ExchangeRateError::MissingDate,
+            exchange_rate.check_freshness(&policy).unwrap_err()
+        );
+    }
+}
@@ -0,0 +1,423 @@
+//! Conversions between commodity types which do not appear together
+//! in a single [ExchangeRate](ExchangeRate), by finding a path through
+//! a collection of exchange rates with an
+//! [ExchangeRateGraph](ExchangeRateGraph).
+
+use super::{ExchangeRate, ExchangeRateError};
+use crate::{Commodity, CommodityTypeID};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet};
+use thiserror::Error;
+
+/// An error associated with an [ExchangeRateGraph](ExchangeRateGraph).
+#[derive(Error, Debug, PartialEq)]
+pub enum GraphError {
+    #[error("There is no path of exchange rates to convert from the commodity type {0} to {1}.")]
+    NoConversionPath(CommodityTypeID, CommodityTypeID),
+    #[error("The commodity {0} is not of the commodity type {1} which the path converts from.")]
+    IncompatibleCommodityType(Commodity, CommodityTypeID),
+    #[error("There was an error with an exchange rate in the graph.")]
+    ExchangeRate(#[from] ExchangeRateError),
+}
+
+/// How recent an [ExchangeRate](ExchangeRate) is, used to choose
+/// between exchange rates. Exchange rates without a `date` or
+/// `obtained_datetime` are considered the oldest.
+type Recency = (Option<NaiveDate>, Option<DateTime<Utc>>);
+
+fn recency(exchange_rate: &ExchangeRate) -> Recency {
+    (exchange_rate.date, exchange_rate.obtained_datetime)
+}
+
+/// A single conversion within a [ConversionPath](ConversionPath).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionStep {
+    /// The commodity type being converted from.
+    pub from: CommodityTypeID,
+    /// The commodity type being converted to.
+    pub to: CommodityTypeID,
+    /// The number of units of `to` per unit of `from`.
+    pub rate: Decimal,
+    /// The index of the [ExchangeRate](ExchangeRate) used for this
+    /// step in [ExchangeRateGraph::exchange_rates](ExchangeRateGraph::exchange_rates).
+    pub exchange_rate_index: usize,
+}
+
+/// A path of conversions between two commodity types, found using an
+/// [ExchangeRateGraph](ExchangeRateGraph).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionPath {
+    /// The commodity type being converted from.
+    pub from: CommodityTypeID,
+    /// The commodity type being converted to.
+    pub to: CommodityTypeID,
+    /// The composed rate for the whole path, the number of units of
+    /// `to` per unit of `from`.
+    pub rate: Decimal,
+    /// The conversions which make up this path, in order. Empty if
+    /// `from` and `to` are the same commodity type.
+    pub steps: Vec<ConversionStep>,
+}
+
+impl ConversionPath {
+    /// The commodity types visited by this path, including `from` and
+    /// `to`.
+    pub fn commodity_types(&self) -> Vec<CommodityTypeID> {
+        std::iter::once(self.from)
+            .chain(self.steps.iter().map(|step| step.to))
+            .collect()
+    }
+
+    /// Convert the specified `commodity` (which needs to be of the
+    /// `from` commodity type) using the composed `rate` of this path.
+    pub fn convert(&self, commodity: Commodity) -> Result<Commodity, GraphError> {
+        if commodity.type_id != self.from {
+            return Err(GraphError::IncompatibleCommodityType(commodity, self.from));
+        }
+
+        let value =
+            commodity
+                .value
+                .checked_mul(self.rate)
+                .ok_or(ExchangeRateError::MultiplyOverflow(
+                    commodity.value,
+                    self.rate,
+                ))?;
+
+        Ok(Commodity::new(value, self.to))
+    }
+}
+
+/// A collection of [ExchangeRate](ExchangeRate)s (for example EUR
+/// based reference rates, and USD based cryptocurrency rates), which
+/// can be used to convert between commodity types which do not appear
+/// together in a single [ExchangeRate](ExchangeRate), by finding a
+/// path of conversions between them.
+///
+/// # Example
+/// ```
+/// # use commodity::exchange_rate::{ExchangeRate, ExchangeRateGraph};
+/// # use commodity::{Commodity, CommodityTypeID};
+/// use rust_decimal::Decimal;
+/// use std::collections::BTreeMap;
+/// use std::str::FromStr;
+///
+/// let eur = CommodityTypeID::from_str("EUR").unwrap();
+/// let usd = CommodityTypeID::from_str("USD").unwrap();
+/// let btc = CommodityTypeID::from_str("BTC").unwrap();
+///
+/// let mut ecb_rates = BTreeMap::new();
+/// ecb_rates.insert(usd, Decimal::from_str("1.1").unwrap());
+/// let ecb = ExchangeRate {
+///     date: None,
+///     obtained_datetime: None,
+///     base: Some(eur),
+///     rates: ecb_rates,
+/// };
+///
+/// let mut crypto_rates = BTreeMap::new();
+/// crypto_rates.insert(btc, Decimal::from_str("0.0001").unwrap());
+/// let crypto = ExchangeRate {
+///     date: None,
+///     obtained_datetime: None,
+///     base: Some(usd),
+///     rates: crypto_rates,
+/// };
+///
+/// let graph = ExchangeRateGraph::from(vec![ecb, crypto]);
+/// let path = graph.find_path(&eur, &btc).unwrap();
+/// assert_eq!(vec![eur, usd, btc], path.commodity_types());
+/// assert_eq!(Decimal::from_str("0.00011").unwrap(), path.rate);
+///
+/// let converted = graph.convert(Commodity::from_str("100 EUR").unwrap(), btc).unwrap();
+/// assert_eq!(Commodity::from_str("0.011 BTC").unwrap(), converted);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExchangeRateGraph {
+    /// The exchange rates in this graph.
+    pub exchange_rates: Vec<ExchangeRate>,
+}
+
+impl ExchangeRateGraph {
+    /// Create a new empty [ExchangeRateGraph](ExchangeRateGraph).
+    pub fn new() -> ExchangeRateGraph {
+        ExchangeRateGraph::default()
+    }
+
+    /// Add an [ExchangeRate](ExchangeRate) to this graph.
+    pub fn add(&mut self, exchange_rate: ExchangeRate) {
+        self.exchange_rates.push(exchange_rate);
+    }
+
+    /// The commodity types present in the specified exchange rate
+    /// (including its `base`).
+    fn commodity_types(exchange_rate: &ExchangeRate) -> impl Iterator<Item = &CommodityTypeID> {
+        exchange_rate.base.iter().chain(exchange_rate.rates.keys())
+    }
+
+    /// Maps each commodity type to the indices of the exchange rates
+    /// which it appears in.
+    fn index(&self) -> BTreeMap<CommodityTypeID, Vec<usize>> {
+        let mut index: BTreeMap<CommodityTypeID, Vec<usize>> = BTreeMap::new();
+        for (i, exchange_rate) in self.exchange_rates.iter().enumerate() {
+            for id in Self::commodity_types(exchange_rate) {
+                let indices = index.entry(*id).or_default();
+                if indices.last() != Some(&i) {
+                    indices.push(i);
+                }
+            }
+        }
+        index
+    }
+
+    /// Find a path of conversions from the commodity type `from` to
+    /// the commodity type `to`. The path with the fewest conversions
+    /// is chosen, and between equally short paths (or exchange rates
+    /// for the same pair of commodity types) the one using the most
+    /// recent exchange rates (by `date` and then `obtained_datetime`)
+    /// is preferred.
+    pub fn find_path(
+        &self,
+        from: &CommodityTypeID,
+        to: &CommodityTypeID,
+    ) -> Result<ConversionPath, GraphError> {
+        if from == to {
+            return Ok(ConversionPath {
+                from: *from,
+                to: *to,
+                rate: Decimal::new(1, 0),
+                steps: Vec::new(),
+            });
+        }
+
+        let index = self.index();
+
+        // For each visited commodity type: the previous commodity type
+        // and exchange rate used to reach it, and the recency of the
+        // oldest exchange rate on the path to it.
+        let mut previous: BTreeMap<CommodityTypeID, (CommodityTypeID, usize, Recency)> =
+            BTreeMap::new();
+        let mut visited: BTreeSet<CommodityTypeID> = BTreeSet::new();
+        visited.insert(*from);
+        let mut frontier: Vec<CommodityTypeID> = vec![*from];
+
+        while !frontier.is_empty() && !visited.contains(to) {
+            let mut next: BTreeMap<CommodityTypeID, (CommodityTypeID, usize, Recency)> =
+                BTreeMap::new();
+
+            for node in &frontier {
+                let path_recency = previous.get(node).map(|(_, _, recency)| *recency);
+
+                for i in index.get(node).into_iter().flatten() {
+                    let exchange_rate = &self.exchange_rates[*i];
+                    let edge_recency = recency(exchange_rate);
+                    let candidate_recency = match path_recency {
+                        Some(path_recency) => std::cmp::min(path_recency, edge_recency),
+                        None => edge_recency,
+                    };
+
+                    for neighbour in Self::commodity_types(exchange_rate) {
+                        if visited.contains(neighbour) {
+                            continue;
+                        }
+
+                        let better = match next.get(neighbour) {
+                            Some((_, _, existing)) => candidate_recency > *existing,
+                            None => true,
+                        };
+
+                        if better {
+                            next.insert(*neighbour, (*node, *i, candidate_recency));
+                        }
+                    }
+                }
+            }
+
+            frontier = next.keys().cloned().collect();
+            visited.extend(frontier.iter().cloned());
+            previous.extend(next);
+        }
+
+        if !previous.contains_key(to) {
+            return Err(GraphError::NoConversionPath(*from, *to));
+        }
+
+        let mut steps = Vec::new();
+        let mut current = *to;
+        while let Some((prev, i, _)) = previous.get(&current) {
+            let rate = self.exchange_rates[*i]
+                .rate_between(prev, &current)?
+                .ok_or(ExchangeRateError::CommodityTypeNotPresent(current))?;
+            steps.push(ConversionStep {
+                from: *prev,
+                to: current,
+                rate,
+                exchange_rate_index: *i,
+            });
+            current = *prev;
+        }
+        steps.reverse();
+
+        let mut rate = Decimal::new(1, 0);
+        for step in &steps {
+            rate = rate
+                .checked_mul(step.rate)
+                .ok_or(ExchangeRateError::MultiplyOverflow(rate, step.rate))?;
+        }
+
+        Ok(ConversionPath {
+            from: *from,
+            to: *to,
+            rate,
+            steps,
+        })
+    }
+
+    /// Get the composed rate between two commodity types, the number
+    /// of units of `to` per unit of `from`. See
+    /// [find_path()](ExchangeRateGraph::find_path).
+    pub fn rate_between(
+        &self,
+        from: &CommodityTypeID,
+        to: &CommodityTypeID,
+    ) -> Result<Decimal, GraphError> {
+        Ok(self.find_path(from, to)?.rate)
+    }
+
+    /// Convert the [CommodityType](crate::CommodityType) of a
+    /// [Commodity](Commodity) to another
+    /// [CommodityType](crate::CommodityType) using a path through this
+    /// graph. See [find_path()](ExchangeRateGraph::find_path).
+    pub fn convert(
+        &self,
+        commodity: Commodity,
+        target_commodity_type: CommodityTypeID,
+    ) -> Result<Commodity, GraphError> {
+        self.find_path(&commodity.type_id, &target_commodity_type)?
+            .convert(commodity)
+    }
+}
+
+impl From<Vec<ExchangeRate>> for ExchangeRateGraph {
+    fn from(exchange_rates: Vec<ExchangeRate>) -> ExchangeRateGraph {
+        ExchangeRateGraph { exchange_rates }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExchangeRateGraph, GraphError};
+    use crate::exchange_rate::ExchangeRate;
+    use crate::{Commodity, CommodityTypeID};
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    fn id(id: &str) -> CommodityTypeID {
+        CommodityTypeID::from_str(id).unwrap()
+    }
+
+    fn exchange_rate(
+        date: Option<(i32, u32, u32)>,
+        base: &str,
+        rates: &[(&str, &str)],
+    ) -> ExchangeRate {
+        ExchangeRate {
+            date: date.map(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d).unwrap()),
+            obtained_datetime: None,
+            base: Some(id(base)),
+            rates: rates
+                .iter()
+                .map(|(type_id, rate)| (id(type_id), Decimal::from_str(rate).unwrap()))
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    /// Convert across several exchange rates, choosing the shortest path.
+    #[test]
+    fn multi_hop() {
+        let graph = ExchangeRateGraph::from(vec![
+            exchange_rate(None, "EUR", &[("USD", "1.1"), ("AUD", "1.6")]),
+            exchange_rate(None, "USD", &[("BTC", "0.0001"), ("ETH", "0.002")]),
+            exchange_rate(None, "ETH", &[("DOGE", "1000")]),
+        ]);
+
+        let path = graph.find_path(&id("AUD"), &id("DOGE")).unwrap();
+        assert_eq!(
+            vec![id("AUD"), id("USD"), id("ETH"), id("DOGE")],
+            path.commodity_types()
+        );
+        assert_eq!(
+            vec![0, 1, 2],
+            path.steps
+                .iter()
+                .map(|step| step.exchange_rate_index)
+                .collect::<Vec<_>>()
+        );
+
+        // reverse direction
+        let converted = graph
+            .convert(Commodity::from_str("1 DOGE").unwrap(), id("EUR"))
+            .unwrap();
+        assert_eq!(
+            Decimal::from_str("0.4545454545").unwrap(),
+            converted.value.round_dp(10)
+        );
+
+        let usd = Commodity::from_str("1 USD").unwrap();
+        assert_eq!(
+            GraphError::IncompatibleCommodityType(usd, id("AUD")),
+            path.convert(usd).unwrap_err()
+        );
+
+        // no conversion is required between the same commodity type
+        let path = graph.find_path(&id("BTC"), &id("BTC")).unwrap();
+        assert_eq!(Decimal::new(1, 0), path.rate);
+        assert!(path.steps.is_empty());
+    }
+
+    /// When several paths are equally short, use the most recent rates.
+    #[test]
+    fn prefer_most_recent() {
+        let graph = ExchangeRateGraph::from(vec![
+            exchange_rate(Some((2020, 1, 1)), "EUR", &[("USD", "1.1")]),
+            exchange_rate(Some((2020, 2, 1)), "EUR", &[("USD", "1.2")]),
+            exchange_rate(Some((2019, 1, 1)), "USD", &[("BTC", "0.0001")]),
+            exchange_rate(Some((2020, 1, 1)), "EUR", &[("GBP", "0.8")]),
+            exchange_rate(Some((2020, 1, 1)), "GBP", &[("BTC", "0.0002")]),
+        ]);
+
+        assert_eq!(
+            Decimal::from_str("1.2").unwrap(),
+            graph.rate_between(&id("EUR"), &id("USD")).unwrap()
+        );
+
+        // the path through GBP is the same length, but more recent.
+        let path = graph.find_path(&id("EUR"), &id("BTC")).unwrap();
+        assert_eq!(
+            vec![id("EUR"), id("GBP"), id("BTC")],
+            path.commodity_types()
+        );
+        assert_eq!(Decimal::from_str("0.00016").unwrap(), path.rate);
+    }
+
+    /// An error is returned when there is no path between the commodity types.
+    #[test]
+    fn no_path() {
+        let graph = ExchangeRateGraph::from(vec![
+            exchange_rate(None, "EUR", &[("USD", "1.1")]),
+            exchange_rate(None, "BTC", &[("ETH", "30")]),
+        ]);
+
+        assert_eq!(
+            GraphError::NoConversionPath(id("EUR"), id("ETH")),
+            graph.find_path(&id("EUR"), &id("ETH")).unwrap_err()
+        );
+        assert_eq!(
+            GraphError::NoConversionPath(id("EUR"), id("JPY")),
+            graph.find_path(&id("EUR"), &id("JPY")).unwrap_err()
+        );
+    }
+}
@@ -0,0 +1,510 @@
+//! A history of dated [ExchangeRate](ExchangeRate) snapshots, with
+//! [ExchangeRateHistory](ExchangeRateHistory), for looking up rates on
+//! a given date using a [LookupStrategy](LookupStrategy).
+
+use super::{ExchangeRate, ExchangeRateError};
+use crate::{Commodity, CommodityTypeID};
+use chrono::{Duration, NaiveDate};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+#[cfg(feature = "serde-support")]
+use serde::{Deserialize, Serialize};
+
+/// An error associated with an [ExchangeRateHistory](ExchangeRateHistory).
+#[derive(Error, Debug, PartialEq)]
+pub enum HistoryError {
+    #[error("The exchange rate does not have a date.")]
+    MissingDate,
+    #[error("There is no rate from the commodity type {1} to {2} available on the date {0}.")]
+    NoRateOnDate(NaiveDate, CommodityTypeID, CommodityTypeID),
+    #[error("The date {date} is outside the range of the exchange rate history (from {first:?} until {last:?}).")]
+    DateOutOfRange {
+        date: NaiveDate,
+        first: Option<NaiveDate>,
+        last: Option<NaiveDate>,
+    },
+    #[error("There was an error with an exchange rate in the history.")]
+    ExchangeRate(#[from] ExchangeRateError),
+}
+
+/// The strategy used by [ExchangeRateHistory](ExchangeRateHistory) to
+/// find the rate on a given date.
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LookupStrategy {
+    /// Only use an exchange rate from exactly the requested date.
+    Exact,
+    /// Use the most recent exchange rate on or before the requested
+    /// date.
+    OnOrBefore,
+    /// Use the exchange rate nearest to the requested date, either
+    /// before or after it. The earlier exchange rate is used when two
+    /// are equally near.
+    Nearest,
+    /// Linearly interpolate between the exchange rates immediately
+    /// before and after the requested date, or use the exchange rate
+    /// from exactly the requested date if there is one.
+    Interpolate,
+}
+
+/// A history of dated [ExchangeRate](ExchangeRate) snapshots (such as
+/// years of daily reference rates), which can be used to look up the
+/// rate between two commodity types on a given date.
+///
+/// # Example
+/// ```
+/// # use commodity::exchange_rate::{ExchangeRate, ExchangeRateHistory, LookupStrategy};
+/// # use commodity::CommodityTypeID;
+/// use chrono::NaiveDate;
+/// use rust_decimal::Decimal;
+/// use std::collections::BTreeMap;
+/// use std::str::FromStr;
+///
+/// let eur = CommodityTypeID::from_str("EUR").unwrap();
+/// let usd = CommodityTypeID::from_str("USD").unwrap();
+///
+/// let mut history = ExchangeRateHistory::new();
+/// for (day, rate) in &[(1, "1.10"), (3, "1.20")] {
+///     let mut rates = BTreeMap::new();
+///     rates.insert(usd, Decimal::from_str(rate).unwrap());
+///     history.insert(ExchangeRate {
+///         date: Some(NaiveDate::from_ymd_opt(2020, 1, *day).unwrap()),
+///         obtained_datetime: None,
+///         base: Some(eur),
+///         rates,
+///     }).unwrap();
+/// }
+///
+/// let date = NaiveDate::from_ymd_opt(2020, 1, 2).unwrap();
+/// assert!(history.rate_at(date, &eur, &usd, LookupStrategy::Exact).is_err());
+/// assert_eq!(
+///     Decimal::from_str("1.10").unwrap(),
+///     history.rate_at(date, &eur, &usd, LookupStrategy::OnOrBefore).unwrap()
+/// );
+/// assert_eq!(
+///     Decimal::from_str("1.15").unwrap(),
+///     history.rate_at(date, &eur, &usd, LookupStrategy::Interpolate).unwrap()
+/// );
+/// ```
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExchangeRateHistory {
+    /// The exchange rate snapshots, keyed by their date.
+    snapshots: BTreeMap<NaiveDate, ExchangeRate>,
+    /// The maximum number of seconds between the requested date and
+    /// the date of an exchange rate used to find the rate for it.
+    max_look_back_seconds: Option<i64>,
+}
+
+impl ExchangeRateHistory {
+    /// Create a new empty [ExchangeRateHistory](ExchangeRateHistory),
+    /// without a maximum look-back window.
+    pub fn new() -> ExchangeRateHistory {
+        ExchangeRateHistory::default()
+    }
+
+    /// Set the maximum look-back window. Exchange rates further than
+    /// `max_look_back` from the requested date will not be used, so a
+    /// window shorter than a day only allows the exact date.
+    pub fn with_max_look_back(mut self, max_look_back: Duration) -> ExchangeRateHistory {
+        self.max_look_back_seconds = Some(max_look_back.num_seconds());
+        self
+    }
+
+    /// The maximum look-back window, if there is one.
+    pub fn max_look_back(&self) -> Option<Duration> {
+        self.max_look_back_seconds.map(Duration::seconds)
+    }
+
+    /// Insert an [ExchangeRate](ExchangeRate) snapshot, keyed by its
+    /// `date`, returning the snapshot it replaced (if there was one on
+    /// the same date). The exchange rate is required to have a `date`.
+    pub fn insert(
+        &mut self,
+        exchange_rate: ExchangeRate,
+    ) -> Result<Option<ExchangeRate>, HistoryError> {
+        let date = exchange_rate.date.ok_or(HistoryError::MissingDate)?;
+        Ok(self.snapshots.insert(date, exchange_rate))
+    }
+
+    /// Remove the snapshot on the specified `date`.
+    pub fn remove(&mut self, date: &NaiveDate) -> Option<ExchangeRate> {
+        self.snapshots.remove(date)
+    }
+
+    /// Get the snapshot from exactly the specified `date`.
+    pub fn get(&self, date: &NaiveDate) -> Option<&ExchangeRate> {
+        self.snapshots.get(date)
+    }
+
+    /// Iterate over the snapshots in this history, ordered by date.
+    pub fn iter(&self) -> impl Iterator<Item = (&NaiveDate, &ExchangeRate)> {
+        self.snapshots.iter()
+    }
+
+    /// The number of snapshots in this history.
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Returns true if there are no snapshots in this history.
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// The date of the earliest snapshot.
+    pub fn first_date(&self) -> Option<NaiveDate> {
+        self.snapshots.keys().next().cloned()
+    }
+
+    /// The date of the latest snapshot.
+    pub fn last_date(&self) -> Option<NaiveDate> {
+        self.snapshots.keys().next_back().cloned()
+    }
+
+    fn within_look_back(&self, date: NaiveDate, snapshot_date: NaiveDate) -> bool {
+        match self.max_look_back_seconds {
+            Some(seconds) => (date - snapshot_date).num_seconds().abs() <= seconds,
+            None => true,
+        }
+    }
+
+    /// The nearest snapshot (containing a rate between `from` and `to`)
+    /// in the specified direction from `date` (excluding `date`
+    /// itself), and within the look-back window.
+    fn neighbour(
+        &self,
+        date: NaiveDate,
+        from: &CommodityTypeID,
+        to: &CommodityTypeID,
+        before: bool,
+    ) -> Result<Option<(NaiveDate, Decimal)>, ExchangeRateError> {
+        let snapshots: Box<dyn Iterator<Item = (&NaiveDate, &ExchangeRate)>> = if before {
+            Box::new(self.snapshots.range(..date).rev())
+        } else {
+            Box::new(self.snapshots.range(date.succ_opt().unwrap_or(date)..))
+        };
+
+        for (snapshot_date, exchange_rate) in snapshots {
+            if !self.within_look_back(date, *snapshot_date) {
+                break;
+            }
+
+            if let Some(rate) = exchange_rate.rate_between(from, to)? {
+                return Ok(Some((*snapshot_date, rate)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Get the rate between two commodity types on the specified
+    /// `date` (the number of units of `to` per unit of `from`), using
+    /// the specified [LookupStrategy](LookupStrategy).
+    pub fn rate_at(
+        &self,
+        date: NaiveDate,
+        from: &CommodityTypeID,
+        to: &CommodityTypeID,
+        strategy: LookupStrategy,
+    ) -> Result<Decimal, HistoryError> {
+        let exact = match self.snapshots.get(&date) {
+            Some(exchange_rate) => exchange_rate.rate_between(from, to)?,
+            None => None,
+        };
+
+        let rate = match (exact, strategy) {
+            (Some(rate), _) => Some(rate),
+            (None, LookupStrategy::Exact) => None,
+            (None, LookupStrategy::OnOrBefore) => {
+                self.neighbour(date, from, to, true)?.map(|(_, rate)| rate)
+            }
+            (None, LookupStrategy::Nearest) => {
+                match (
+                    self.neighbour(date, from, to, true)?,
+                    self.neighbour(date, from, to, false)?,
+                ) {
+                    (Some((before_date, before)), Some((after_date, after))) => {
+                        if date - before_date <= after_date - date {
+                            Some(before)
+                        } else {
+                            Some(after)
+                        }
+                    }
+                    (before, after) => before.or(after).map(|(_, rate)| rate),
+                }
+            }
+            (None, LookupStrategy::Interpolate) => {
+                match (
+                    self.neighbour(date, from, to, true)?,
+                    self.neighbour(date, from, to, false)?,
+                ) {
+                    (Some(before), Some(after)) => Some(interpolate(date, before, after)?),
+                    _ => None,
+                }
+            }
+        };
+
+        rate.ok_or_else(|| self.not_found(date, from, to))
+    }
+
+    /// Convert the [CommodityType](crate::CommodityType) of a
+    /// [Commodity](Commodity) to another
+    /// [CommodityType](crate::CommodityType) using the rate on the
+    /// specified `date`. See [rate_at()](ExchangeRateHistory::rate_at).
+    pub fn convert_at(
+        &self,
+        date: NaiveDate,
+        commodity: Commodity,
+        target_commodity_type: CommodityTypeID,
+        strategy: LookupStrategy,
+    ) -> Result<Commodity, HistoryError> {
+        let rate = self.rate_at(date, &commodity.type_id, &target_commodity_type, strategy)?;
+        let value = commodity
+            .value
+            .checked_mul(rate)
+            .ok_or(ExchangeRateError::MultiplyOverflow(commodity.value, rate))?;
+        Ok(Commodity::new(value, target_commodity_type))
+    }
+
+    /// The error for when no rate could be found.
+    fn not_found(
+        &self,
+        date: NaiveDate,
+        from: &CommodityTypeID,
+        to: &CommodityTypeID,
+    ) -> HistoryError {
+        match (self.first_date(), self.last_date()) {
+            (Some(first), Some(last)) if first <= date && date <= last => {
+                HistoryError::NoRateOnDate(date, *from, *to)
+            }
+            (first, last) => HistoryError::DateOutOfRange { date, first, last },
+        }
+    }
+}
+
+/// Linearly interpolate the rate on `date` between the rates
+/// `before` and `after`.
+fn interpolate(
+    date: NaiveDate,
+    before: (NaiveDate, Decimal),
+    after: (NaiveDate, Decimal),
+) -> Result<Decimal, ExchangeRateError> {
+    let (before_date, before_rate) = before;
+    let (after_date, after_rate) = after;
+
+    let elapsed = Decimal::from((date - before_date).num_days());
+    let total = Decimal::from((after_date - before_date).num_days());
+    let difference = after_rate
+        .checked_sub(before_rate)
+        .ok_or(ExchangeRateError::SubtractOverflow(after_rate, before_rate))?;
+
+    let scaled = difference
+        .checked_mul(elapsed)
+        .ok_or(ExchangeRateError::MultiplyOverflow(difference, elapsed))?;
+    let step = scaled
+        .checked_div(total)
+        .ok_or(ExchangeRateError::DivideOverflow(scaled, total))?;
+
+    before_rate
+        .checked_add(step)
+        .ok_or(ExchangeRateError::AddOverflow(before_rate, step))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{interpolate, ExchangeRateHistory, HistoryError, LookupStrategy};
+    use crate::exchange_rate::{ExchangeRate, ExchangeRateError};
+    use crate::{Commodity, CommodityTypeID};
+    use chrono::{Duration, NaiveDate};
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2020, 1, day).unwrap()
+    }
+
+    fn id(id: &str) -> CommodityTypeID {
+        CommodityTypeID::from_str(id).unwrap()
+    }
+
+    /// A history of EUR based rates, with USD rates on the 1st, 3rd
+    /// and 10th, and a GBP rate only on the 3rd.
+    fn test_history() -> ExchangeRateHistory {
+        let mut history = ExchangeRateHistory::new();
+        for (day, rates) in &[
+            (1, vec![("USD", "1.10")]),
+            (3, vec![("USD", "1.20"), ("GBP", "0.85")]),
+            (10, vec![("USD", "1.06")]),
+        ] {
+            history
+                .insert(ExchangeRate {
+                    date: Some(date(*day)),
+                    obtained_datetime: None,
+                    base: Some(id("EUR")),
+                    rates: rates
+                        .iter()
+                        .map(|(type_id, rate)| (id(type_id), Decimal::from_str(rate).unwrap()))
+                        .collect::<BTreeMap<_, _>>(),
+                })
+                .unwrap();
+        }
+        history
+    }
+
+    #[test]
+    fn lookup_strategies() {
+        let history = test_history();
+        let (eur, usd, gbp) = (id("EUR"), id("USD"), id("GBP"));
+        let rate = |day, from, to, strategy| history.rate_at(date(day), from, to, strategy);
+
+        assert_eq!(
+            Decimal::from_str("1.20").unwrap(),
+            rate(3, &eur, &usd, LookupStrategy::Exact).unwrap()
+        );
+        assert_eq!(
+            HistoryError::NoRateOnDate(date(4), eur, usd),
+            rate(4, &eur, &usd, LookupStrategy::Exact).unwrap_err()
+        );
+
+        assert_eq!(
+            Decimal::from_str("1.20").unwrap(),
+            rate(9, &eur, &usd, LookupStrategy::OnOrBefore).unwrap()
+        );
+        // skips the snapshot on the 10th which doesn't contain GBP
+        assert_eq!(
+            Decimal::from_str("0.85").unwrap(),
+            rate(12, &eur, &gbp, LookupStrategy::OnOrBefore).unwrap()
+        );
+
+        assert_eq!(
+            Decimal::from_str("1.06").unwrap(),
+            rate(7, &eur, &usd, LookupStrategy::Nearest).unwrap()
+        );
+        assert_eq!(
+            Decimal::from_str("1.10").unwrap(),
+            rate(2, &eur, &usd, LookupStrategy::Nearest).unwrap()
+        );
+
+        assert_eq!(
+            Decimal::from_str("1.16").unwrap(),
+            rate(5, &eur, &usd, LookupStrategy::Interpolate).unwrap()
+        );
+        assert_eq!(
+            HistoryError::NoRateOnDate(date(5), eur, gbp),
+            rate(5, &eur, &gbp, LookupStrategy::Interpolate).unwrap_err()
+        );
+
+        let converted = history
+            .convert_at(
+                date(2),
+                Commodity::from_str("100 EUR").unwrap(),
+                usd,
+                LookupStrategy::Interpolate,
+            )
+            .unwrap();
+        assert_eq!(Commodity::from_str("115 USD").unwrap(), converted);
+    }
+
+    #[test]
+    fn look_back_window() {
+        let history = test_history().with_max_look_back(Duration::days(3));
+        let (eur, usd) = (id("EUR"), id("USD"));
+
+        assert_eq!(
+            Decimal::from_str("1.20").unwrap(),
+            history
+                .rate_at(date(6), &eur, &usd, LookupStrategy::OnOrBefore)
+                .unwrap()
+        );
+        assert_eq!(
+            HistoryError::NoRateOnDate(date(7), eur, usd),
+            history
+                .rate_at(date(7), &eur, &usd, LookupStrategy::OnOrBefore)
+                .unwrap_err()
+        );
+        assert_eq!(
+            HistoryError::NoRateOnDate(date(7), eur, usd),
+            history
+                .rate_at(date(7), &eur, &usd, LookupStrategy::Interpolate)
+                .unwrap_err()
+        );
+        assert_eq!(
+            Decimal::from_str("1.06").unwrap(),
+            history
+                .rate_at(date(13), &eur, &usd, LookupStrategy::OnOrBefore)
+                .unwrap()
+        );
+        assert_eq!(
+            HistoryError::DateOutOfRange {
+                date: date(14),
+                first: Some(date(1)),
+                last: Some(date(10)),
+            },
+            history
+                .rate_at(date(14), &eur, &usd, LookupStrategy::OnOrBefore)
+                .unwrap_err()
+        );
+    }
+
+    /// A look-back window shorter than a day only allows the exact date.
+    #[test]
+    fn look_back_window_less_than_a_day() {
+        let history = test_history().with_max_look_back(Duration::hours(12));
+        let (eur, usd) = (id("EUR"), id("USD"));
+
+        assert_eq!(Some(Duration::hours(12)), history.max_look_back());
+        assert_eq!(
+            Decimal::from_str("1.20").unwrap(),
+            history
+                .rate_at(date(3), &eur, &usd, LookupStrategy::OnOrBefore)
+                .unwrap()
+        );
+        assert_eq!(
+            HistoryError::NoRateOnDate(date(4), eur, usd),
+            history
+                .rate_at(date(4), &eur, &usd, LookupStrategy::OnOrBefore)
+                .unwrap_err()
+        );
+    }
+
+    #[test]
+    fn interpolate_overflow() {
+        assert_eq!(
+            ExchangeRateError::SubtractOverflow(Decimal::MAX, Decimal::MIN),
+            interpolate(date(2), (date(1), Decimal::MIN), (date(3), Decimal::MAX)).unwrap_err()
+        );
+        assert_eq!(
+            Decimal::MAX,
+            interpolate(date(2), (date(1), Decimal::MAX), (date(3), Decimal::MAX)).unwrap()
+        );
+    }
+
+    #[test]
+    fn insert_requires_date() {
+        let mut history = ExchangeRateHistory::new();
+        let exchange_rate = ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: None,
+            rates: BTreeMap::new(),
+        };
+        assert_eq!(
+            HistoryError::MissingDate,
+            history.insert(exchange_rate).unwrap_err()
+        );
+        assert_eq!(
+            HistoryError::DateOutOfRange {
+                date: date(1),
+                first: None,
+                last: None,
+            },
+            history
+                .rate_at(date(1), &id("EUR"), &id("USD"), LookupStrategy::Nearest)
+                .unwrap_err()
+        );
+    }
+}
@@ -0,0 +1,380 @@
+//! A [CrossRateMatrix](CrossRateMatrix) of the precomputed rates
+//! between every pair of commodity types in an
+//! [ExchangeRate](ExchangeRate).
+
+use super::{ExchangeRate, ExchangeRateError};
+use crate::CommodityTypeID;
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// A precomputed table of the rates between every pair of commodity
+/// types in an [ExchangeRate](ExchangeRate), created using
+/// [ExchangeRate::cross_matrix()](ExchangeRate::cross_matrix).
+///
+/// Each row is the commodity type being converted from, and each
+/// column the commodity type being converted to, so the cell in row
+/// `from` and column `to` is the number of `to` units per one `from`
+/// unit (the same as
+/// [ExchangeRate::rate_between()](ExchangeRate::rate_between)).
+///
+/// The [Display](std::fmt::Display) implementation renders the matrix
+/// as an aligned text table, and
+/// [to_csv_string()](CrossRateMatrix::to_csv_string) renders it as
+/// csv.
+///
+/// # Example
+/// ```
+/// # use commodity::exchange_rate::ExchangeRate;
+/// # use commodity::CommodityTypeID;
+/// use rust_decimal::{Decimal, RoundingStrategy};
+/// use std::collections::BTreeMap;
+/// use std::str::FromStr;
+///
+/// let usd = CommodityTypeID::from_str("USD").unwrap();
+/// let aud = CommodityTypeID::from_str("AUD").unwrap();
+/// let nzd = CommodityTypeID::from_str("NZD").unwrap();
+/// let mut rates = BTreeMap::new();
+/// rates.insert(aud, Decimal::from_str("1.5").unwrap());
+/// rates.insert(nzd, Decimal::from_str("1.6").unwrap());
+/// let exchange_rate = ExchangeRate {
+///     date: None,
+///     obtained_datetime: None,
+///     base: Some(usd),
+///     rates,
+/// };
+///
+/// let matrix = exchange_rate
+///     .cross_matrix()
+///     .unwrap()
+///     .round(4, RoundingStrategy::MidpointNearestEven);
+/// assert_eq!(Some(Decimal::from_str("1.0667").unwrap()), matrix.rate(&aud, &nzd));
+/// assert_eq!(Some(Decimal::from_str("0.6667").unwrap()), matrix.rate(&aud, &usd));
+/// assert_eq!(Some(Decimal::ONE), matrix.rate(&usd, &usd));
+///
+/// assert_eq!(
+///     ",AUD,NZD,USD\n\
+///      AUD,1,1.0667,0.6667\n\
+///      NZD,0.9375,1,0.625\n\
+///      USD,1.5,1.6,1\n",
+///     matrix.to_csv_string(',')
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossRateMatrix {
+    commodity_types: Vec<CommodityTypeID>,
+    index: HashMap<CommodityTypeID, usize>,
+    /// The cells of the matrix, stored row by row.
+    rates: Vec<Decimal>,
+}
+
+impl CrossRateMatrix {
+    /// The commodity types in the matrix, in the order of its rows and
+    /// columns.
+    pub fn commodity_types(&self) -> &[CommodityTypeID] {
+        &self.commodity_types
+    }
+
+    /// The number of commodity types in the matrix (the number of rows
+    /// and of columns).
+    pub fn len(&self) -> usize {
+        self.commodity_types.len()
+    }
+
+    /// Whether the matrix has no commodity types.
+    pub fn is_empty(&self) -> bool {
+        self.commodity_types.is_empty()
+    }
+
+    /// The index of the row and column for the specified commodity
+    /// type.
+    pub fn index_of(&self, commodity_type_id: &CommodityTypeID) -> Option<usize> {
+        self.index.get(commodity_type_id).copied()
+    }
+
+    /// The rate in the cell at the `from` row and `to` column index.
+    ///
+    /// # Panics
+    ///
+    /// If either index is not less than [len()](CrossRateMatrix::len).
+    pub fn get(&self, from: usize, to: usize) -> Decimal {
+        assert!(from < self.len() && to < self.len());
+        self.rates[from * self.len() + to]
+    }
+
+    /// The number of `to` units per one `from` unit, or `None` if
+    /// either commodity type is not present in the matrix.
+    pub fn rate(&self, from: &CommodityTypeID, to: &CommodityTypeID) -> Option<Decimal> {
+        Some(self.get(self.index_of(from)?, self.index_of(to)?))
+    }
+
+    /// The rates in the row for the `from` commodity type, in the
+    /// order of [commodity_types()](CrossRateMatrix::commodity_types).
+    pub fn row(&self, from: &CommodityTypeID) -> Option<&[Decimal]> {
+        let start = self.index_of(from)? * self.len();
+        Some(&self.rates[start..start + self.len()])
+    }
+
+    /// Round every cell of the matrix to `dp` decimal places using the
+    /// specified rounding `strategy`.
+    pub fn round(mut self, dp: u32, strategy: RoundingStrategy) -> CrossRateMatrix {
+        for rate in &mut self.rates {
+            *rate = rate.round_dp_with_strategy(dp, strategy);
+        }
+        self
+    }
+
+    /// Render the matrix as csv using the specified `delimiter`, with a
+    /// header row and a first column containing the commodity types.
+    /// Fields containing the `delimiter`, a quote or a line break are
+    /// quoted.
+    pub fn to_csv_string(&self, delimiter: char) -> String {
+        let mut csv = String::new();
+        for id in &self.commodity_types {
+            csv.push(delimiter);
+            csv.push_str(&csv_field(&id.to_string(), delimiter));
+        }
+        csv.push('\n');
+
+        for (from, id) in self.commodity_types.iter().enumerate() {
+            csv.push_str(&csv_field(&id.to_string(), delimiter));
+            for to in 0..self.len() {
+                csv.push(delimiter);
+                csv.push_str(&csv_field(&self.get(from, to).to_string(), delimiter));
+            }
+            csv.push('\n');
+        }
+
+        csv
+    }
+}
+
+/// Quote `field` if it contains the `delimiter`, a quote or a line
+/// break, doubling any quotes inside it.
+fn csv_field(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl fmt::Display for CrossRateMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids: Vec<String> = self
+            .commodity_types
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        let cells: Vec<String> = self.rates.iter().map(|rate| rate.to_string()).collect();
+        let width = ids
+            .iter()
+            .chain(cells.iter())
+            .map(|cell| cell.chars().count())
+            .max()
+            .unwrap_or(0);
+        let id_width = ids.iter().map(|id| id.chars().count()).max().unwrap_or(0);
+
+        write!(f, "{:id_width$}", "", id_width = id_width)?;
+        for id in &ids {
+            write!(f, " {:>width$}", id, width = width)?;
+        }
+        writeln!(f)?;
+
+        for (from, id) in ids.iter().enumerate() {
+            write!(f, "{:id_width$}", id, id_width = id_width)?;
+            for cell in &cells[from * self.len()..(from + 1) * self.len()] {
+                write!(f, " {:>width$}", cell, width = width)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ExchangeRate {
+    /// Compute the rates between every pair of commodity types in this
+    /// exchange rate (including the base), as a
+    /// [CrossRateMatrix](CrossRateMatrix) supporting constant time
+    /// lookups.
+    pub fn cross_matrix(&self) -> Result<CrossRateMatrix, ExchangeRateError> {
+        let commodity_types: Vec<CommodityTypeID> = self
+            .base
+            .iter()
+            .chain(self.rates.keys())
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        let index = commodity_types
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect();
+
+        let mut rates = Vec::with_capacity(commodity_types.len() * commodity_types.len());
+        for from in &commodity_types {
+            for to in &commodity_types {
+                let rate = if from == to {
+                    Decimal::ONE
+                } else {
+                    self.rate_between(from, to)?
+                        .ok_or(ExchangeRateError::CommodityTypeNotPresent(*to))?
+                };
+                rates.push(rate);
+            }
+        }
+
+        Ok(CrossRateMatrix {
+            commodity_types,
+            index,
+            rates,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::exchange_rate::{ExchangeRate, ExchangeRateError};
+    use crate::CommodityTypeID;
+    use rust_decimal::{Decimal, RoundingStrategy};
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn cross_matrix() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let eur = CommodityTypeID::from_str("EUR").unwrap();
+        let nzd = CommodityTypeID::from_str("NZD").unwrap();
+
+        let mut rates = BTreeMap::new();
+        rates.insert(aud, Decimal::from_str("1.5").unwrap());
+        rates.insert(eur, Decimal::from_str("0.9").unwrap());
+        let exchange_rate = ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(usd),
+            rates,
+        };
+
+        let matrix = exchange_rate.cross_matrix().unwrap();
+        assert_eq!(3, matrix.len());
+        assert_eq!(&[aud, eur, usd], matrix.commodity_types());
+        assert_eq!(None, matrix.rate(&aud, &nzd));
+
+        // every cell matches rate_between
+        for from in matrix.commodity_types() {
+            for to in matrix.commodity_types() {
+                if from != to {
+                    assert_eq!(
+                        exchange_rate.rate_between(from, to).unwrap(),
+                        matrix.rate(from, to)
+                    );
+                }
+            }
+        }
+
+        assert_eq!(
+            &[
+                Decimal::from_str("1.6666666666666666666666666667").unwrap(),
+                Decimal::ONE,
+                Decimal::from_str("1.1111111111111111111111111111").unwrap(),
+            ],
+            matrix.row(&eur).unwrap()
+        );
+
+        let rounded = matrix.round(2, RoundingStrategy::ToZero);
+        assert_eq!(
+            Some(Decimal::from_str("1.11").unwrap()),
+            rounded.rate(&eur, &usd)
+        );
+        assert_eq!(
+            "     AUD  EUR  USD\n\
+             AUD    1 0.60 0.66\n\
+             EUR 1.66    1 1.11\n\
+             USD  1.5  0.9    1\n",
+            rounded.to_string()
+        );
+        assert_eq!(
+            ";AUD;EUR;USD\nAUD;1;0.60;0.66\nEUR;1.66;1;1.11\nUSD;1.5;0.9;1\n",
+            rounded.to_csv_string(';')
+        );
+    }
+
+    #[test]
+    fn round_keeps_trailing_zeros() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+
+        let mut rates = BTreeMap::new();
+        rates.insert(aud, Decimal::from_str("1.5000").unwrap());
+        let exchange_rate = ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(usd),
+            rates,
+        };
+
+        let rounded = exchange_rate
+            .cross_matrix()
+            .unwrap()
+            .round(2, RoundingStrategy::MidpointNearestEven);
+        assert_eq!("1.50", rounded.rate(&usd, &aud).unwrap().to_string());
+    }
+
+    #[test]
+    fn to_csv_string_quotes_fields() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let odd = CommodityTypeID::from_str("A,\"B").unwrap();
+
+        let mut rates = BTreeMap::new();
+        rates.insert(odd, Decimal::from_str("2.5").unwrap());
+        let exchange_rate = ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(usd),
+            rates,
+        };
+
+        let matrix = exchange_rate.cross_matrix().unwrap();
+        assert_eq!(
+            ",\"A,\"\"B\",USD\n\"A,\"\"B\",1,0.4\nUSD,2.5,1\n",
+            matrix.to_csv_string(',')
+        );
+        assert_eq!(
+            ".\"A,\"\"B\".USD\n\"A,\"\"B\".1.\"0.4\"\nUSD.\"2.5\".1\n",
+            matrix.to_csv_string('.')
+        );
+    }
+
+    #[test]
+    fn cross_matrix_errors() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+
+        let mut rates = BTreeMap::new();
+        rates.insert(aud, Decimal::ZERO);
+        let exchange_rate = ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(usd),
+            rates,
+        };
+
+        assert_eq!(
+            ExchangeRateError::DivideOverflow(Decimal::ONE, Decimal::ZERO),
+            exchange_rate.cross_matrix().unwrap_err()
+        );
+
+        let empty = ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: None,
+            rates: BTreeMap::new(),
+        };
+        assert!(empty.cross_matrix().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,317 @@
+//! Merging two [ExchangeRate](ExchangeRate)s with
+//! [ExchangeRate::merge()](ExchangeRate::merge), resolving conflicting
+//! rates with a [MergePolicy](MergePolicy).
+
+use super::{ExchangeRate, ExchangeRateError};
+use crate::CommodityTypeID;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+#[cfg(feature = "serde-support")]
+use serde::{Deserialize, Serialize};
+
+/// An error associated with [ExchangeRate::merge()](ExchangeRate::merge).
+#[derive(Error, Debug, PartialEq)]
+pub enum MergeError {
+    #[error(
+        "The exchange rates have conflicting rates (commodity type, left rate, right rate): {0:?}"
+    )]
+    Conflict(Vec<(CommodityTypeID, Decimal, Decimal)>),
+    #[error("There was an error aligning the exchange rates being merged.")]
+    ExchangeRate(#[from] ExchangeRateError),
+}
+
+/// How to resolve conflicting rates for the same commodity type when
+/// using [ExchangeRate::merge()](ExchangeRate::merge).
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MergePolicy {
+    /// Use the rate from the exchange rate with the most recent
+    /// `obtained_datetime` (and then `date`), or the left one if they
+    /// are equally recent.
+    PreferNewer,
+    /// Use the rate from the left exchange rate (`self`).
+    PreferLeft,
+    /// Use the rate from the right exchange rate (`other`).
+    PreferRight,
+    /// Use the average of the two rates.
+    Average,
+    /// Return a [MergeError::Conflict](MergeError::Conflict).
+    Error,
+}
+
+/// Whether two rates are equal, allowing for the rounding introduced
+/// by [ExchangeRate::rebase()](ExchangeRate::rebase), within one part
+/// in 10<sup>20</sup> of the larger rate.
+fn rates_agree(left: Decimal, right: Decimal) -> bool {
+    let difference = match left.checked_sub(right) {
+        Some(difference) => difference.abs(),
+        None => return false,
+    };
+    let tolerance = left.abs().max(right.abs()) * Decimal::new(1, 20);
+    difference <= tolerance
+}
+
+impl ExchangeRate {
+    /// Express the rates of `other` (with the base `other_base`)
+    /// relative to `base` (the base of this exchange rate), either by
+    /// rebasing `other`, or if `base` is not present in `other`, using
+    /// the rate between `base` and `other_base` from this exchange rate.
+    fn align(
+        &self,
+        other: &ExchangeRate,
+        base: CommodityTypeID,
+        other_base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ExchangeRateError> {
+        if other.rates.contains_key(&base) {
+            return other.rebase(base);
+        }
+
+        let factor = self
+            .rate_between(&base, &other_base)?
+            .ok_or(ExchangeRateError::CommodityTypeNotPresent(other_base))?;
+
+        let mut rates = BTreeMap::new();
+        rates.insert(other_base, factor);
+        for (id, rate) in &other.rates {
+            let value = rate
+                .checked_mul(factor)
+                .ok_or(ExchangeRateError::MultiplyOverflow(*rate, factor))?;
+            rates.insert(*id, value);
+        }
+
+        Ok(ExchangeRate {
+            date: other.date,
+            obtained_datetime: other.obtained_datetime,
+            base: Some(base),
+            rates,
+        })
+    }
+
+    /// Merge the rates of this exchange rate with the rates of `other`,
+    /// resolving any conflicting rates with the specified
+    /// [MergePolicy](MergePolicy). If the two exchange rates have a
+    /// different `base`, `other` is
+    /// [rebased](ExchangeRate::rebase) to the `base` of this exchange
+    /// rate, or scaled using the rate between the two bases in this
+    /// exchange rate (if this one has no `base`, it is rebased to the
+    /// `base` of `other` instead). The merged exchange rate takes the most
+    /// recent `date` and `obtained_datetime` of the two.
+    ///
+    /// Rebasing introduces rounding in the last significant digits, so
+    /// two rates only conflict if they differ by more than one part in
+    /// 10<sup>20</sup>; otherwise the rate from this exchange rate is
+    /// kept.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::exchange_rate::{ExchangeRate, MergePolicy};
+    /// # use commodity::CommodityTypeID;
+    /// use rust_decimal::Decimal;
+    /// use std::collections::BTreeMap;
+    /// use std::str::FromStr;
+    ///
+    /// let usd = CommodityTypeID::from_str("USD").unwrap();
+    /// let aud = CommodityTypeID::from_str("AUD").unwrap();
+    /// let nzd = CommodityTypeID::from_str("NZD").unwrap();
+    ///
+    /// let mut rates = BTreeMap::new();
+    /// rates.insert(aud, Decimal::from_str("1.5").unwrap());
+    /// let left = ExchangeRate { date: None, obtained_datetime: None, base: Some(usd), rates };
+    ///
+    /// let mut rates = BTreeMap::new();
+    /// rates.insert(aud, Decimal::from_str("1.7").unwrap());
+    /// rates.insert(nzd, Decimal::from_str("1.6").unwrap());
+    /// let right = ExchangeRate { date: None, obtained_datetime: None, base: Some(usd), rates };
+    ///
+    /// let merged = left.merge(&right, MergePolicy::Average).unwrap();
+    /// assert_eq!(Some(&Decimal::from_str("1.6").unwrap()), merged.get_rate(&aud));
+    /// assert_eq!(Some(&Decimal::from_str("1.6").unwrap()), merged.get_rate(&nzd));
+    ///
+    /// assert!(left.merge(&right, MergePolicy::Error).is_err());
+    /// ```
+    pub fn merge(
+        &self,
+        other: &ExchangeRate,
+        policy: MergePolicy,
+    ) -> Result<ExchangeRate, MergeError> {
+        let (left, right) = match (self.base, other.base) {
+            (Some(base), Some(other_base)) if base != other_base => {
+                (self.clone(), self.align(other, base, other_base)?)
+            }
+            (None, Some(other_base)) => (self.rebase(other_base)?, other.clone()),
+            (Some(base), None) => (self.clone(), other.rebase(base)?),
+            _ => (self.clone(), other.clone()),
+        };
+
+        let prefer_right =
+            (right.obtained_datetime, right.date) > (left.obtained_datetime, left.date);
+
+        let mut rates = left.rates.clone();
+        let mut conflicts = Vec::new();
+
+        for (id, right_rate) in &right.rates {
+            let left_rate = match rates.get(id) {
+                Some(left_rate) if rates_agree(*left_rate, *right_rate) => continue,
+                Some(left_rate) => *left_rate,
+                None => {
+                    rates.insert(*id, *right_rate);
+                    continue;
+                }
+            };
+
+            let rate = match policy {
+                MergePolicy::PreferNewer if prefer_right => *right_rate,
+                MergePolicy::PreferNewer | MergePolicy::PreferLeft => left_rate,
+                MergePolicy::PreferRight => *right_rate,
+                MergePolicy::Average => {
+                    left_rate
+                        .checked_add(*right_rate)
+                        .ok_or(ExchangeRateError::AddOverflow(left_rate, *right_rate))?
+                        / Decimal::new(2, 0)
+                }
+                MergePolicy::Error => {
+                    conflicts.push((*id, left_rate, *right_rate));
+                    left_rate
+                }
+            };
+            rates.insert(*id, rate);
+        }
+
+        if !conflicts.is_empty() {
+            return Err(MergeError::Conflict(conflicts));
+        }
+
+        Ok(ExchangeRate {
+            date: left.date.max(right.date),
+            obtained_datetime: left.obtained_datetime.max(right.obtained_datetime),
+            base: left.base,
+            rates,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MergeError, MergePolicy};
+    use crate::exchange_rate::{ExchangeRate, ExchangeRateError};
+    use crate::CommodityTypeID;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    /// Merge exchange rates with different bases and conflicting rates.
+    #[test]
+    fn merge() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let eur = CommodityTypeID::from_str("EUR").unwrap();
+        let nzd = CommodityTypeID::from_str("NZD").unwrap();
+
+        let mut rates: BTreeMap<CommodityTypeID, Decimal> = BTreeMap::new();
+        rates.insert(aud, Decimal::from_str("1.5").unwrap());
+        rates.insert(eur, Decimal::from_str("0.9").unwrap());
+        let left = ExchangeRate {
+            date: Some(NaiveDate::from_ymd_opt(2020, 2, 7).unwrap()),
+            obtained_datetime: Some(Utc.with_ymd_and_hms(2020, 2, 7, 10, 0, 0).unwrap()),
+            base: Some(usd),
+            rates,
+        };
+
+        // EUR based, with 1 EUR = 1.8 AUD, which is 1.62 AUD per USD
+        let mut rates: BTreeMap<CommodityTypeID, Decimal> = BTreeMap::new();
+        rates.insert(aud, Decimal::from_str("1.8").unwrap());
+        rates.insert(nzd, Decimal::from_str("1.9").unwrap());
+        let right = ExchangeRate {
+            date: Some(NaiveDate::from_ymd_opt(2020, 2, 7).unwrap()),
+            obtained_datetime: Some(Utc.with_ymd_and_hms(2020, 2, 7, 12, 0, 0).unwrap()),
+            base: Some(eur),
+            rates,
+        };
+
+        let merged = left.merge(&right, MergePolicy::PreferLeft).unwrap();
+        assert_eq!(Some(usd), merged.base);
+        assert_eq!(right.obtained_datetime, merged.obtained_datetime);
+        assert_eq!(Decimal::from_str("1.5").unwrap(), merged.rates[&aud]);
+        assert_eq!(Decimal::from_str("0.9").unwrap(), merged.rates[&eur]);
+        assert_eq!(Decimal::from_str("1.71").unwrap(), merged.rates[&nzd]);
+
+        let merged = left.merge(&right, MergePolicy::PreferNewer).unwrap();
+        assert_eq!(Decimal::from_str("1.62").unwrap(), merged.rates[&aud]);
+        let merged = right.merge(&left, MergePolicy::PreferNewer).unwrap();
+        assert_eq!(Some(eur), merged.base);
+        assert_eq!(Decimal::from_str("1.8").unwrap(), merged.rates[&aud]);
+
+        let merged = left.merge(&right, MergePolicy::PreferRight).unwrap();
+        assert_eq!(Decimal::from_str("1.62").unwrap(), merged.rates[&aud]);
+        let merged = left.merge(&right, MergePolicy::Average).unwrap();
+        assert_eq!(Decimal::from_str("1.56").unwrap(), merged.rates[&aud]);
+
+        assert_eq!(
+            MergeError::Conflict(vec![(
+                aud,
+                Decimal::from_str("1.5").unwrap(),
+                Decimal::from_str("1.62").unwrap()
+            )]),
+            left.merge(&right, MergePolicy::Error).unwrap_err()
+        );
+    }
+
+    /// Merging an exchange rate with a rebased copy of itself does not
+    /// conflict, despite the rounding introduced by rebasing.
+    #[test]
+    fn merge_rebased_copy() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let nok = CommodityTypeID::from_str("NOK").unwrap();
+        let gel = CommodityTypeID::from_str("GEL").unwrap();
+
+        let mut rates: BTreeMap<CommodityTypeID, Decimal> = BTreeMap::new();
+        rates.insert(nok, Decimal::from_str("9.2691220713").unwrap());
+        rates.insert(gel, Decimal::from_str("3.08").unwrap());
+        let exchange_rate = ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(usd),
+            rates,
+        };
+
+        let rebased = exchange_rate.rebase(gel).unwrap();
+        assert_ne!(
+            exchange_rate.rates[&nok],
+            rebased.rebase(usd).unwrap().rates[&nok]
+        );
+
+        let merged = exchange_rate.merge(&rebased, MergePolicy::Error).unwrap();
+        assert_eq!(exchange_rate, merged);
+        let merged = rebased.merge(&exchange_rate, MergePolicy::Error).unwrap();
+        assert_eq!(Some(gel), merged.base);
+        assert_eq!(rebased.rates, merged.rates);
+    }
+
+    #[test]
+    fn merge_average_overflow() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+
+        let mut rates: BTreeMap<CommodityTypeID, Decimal> = BTreeMap::new();
+        rates.insert(aud, Decimal::MAX);
+        let left = ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(usd),
+            rates,
+        };
+        let mut right = left.clone();
+        right.rates.insert(aud, Decimal::MAX / Decimal::new(2, 0));
+
+        assert_eq!(
+            MergeError::ExchangeRate(ExchangeRateError::AddOverflow(
+                Decimal::MAX,
+                Decimal::MAX / Decimal::new(2, 0)
+            )),
+            left.merge(&right, MergePolicy::Average).unwrap_err()
+        );
+    }
+}
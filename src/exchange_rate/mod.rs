@@ -2,7 +2,7 @@
 //! between different types of commodities.
 
 use crate::{Commodity, CommodityTypeID};
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use rust_decimal::Decimal;
 
 #[cfg(feature = "serde-support")]
@@ -11,13 +11,51 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use thiserror::Error;
 
+#[cfg(feature = "csv")]
+pub mod csv;
+mod fees;
+mod freshness;
+mod graph;
+mod history;
+mod matrix;
+mod merge;
+mod pair;
+mod quote;
+mod rounding;
+mod validate;
+
+pub use fees::{AppliedFee, ConversionBreakdown, Fee, FeeAmount, FeeError, FeeTiming};
+pub use freshness::FreshnessPolicy;
+pub use graph::{ConversionPath, ConversionStep, ExchangeRateGraph, GraphError};
+pub use history::{ExchangeRateHistory, HistoryError, LookupStrategy};
+pub use matrix::CrossRateMatrix;
+pub use merge::{MergeError, MergePolicy};
+pub use pair::{CurrencyPair, CurrencyPairError};
+pub use quote::{Quote, QuoteError, QuotedExchangeRate, Side};
+pub use rounding::{ConversionRounding, RoundedConversion, RoundingError};
+pub use validate::{ArbitrageChecker, ArbitrageCycle, ValidationIssue};
+
 /// An error associated with functionality in the [exchange_rate](crate::exchange_rate) module.
-#[derive(Error, Debug)]
+#[derive(Error, Debug, PartialEq)]
 pub enum ExchangeRateError {
     #[error("The commodity type with id {0} is not present in the exchange rate.")]
     CommodityTypeNotPresent(CommodityTypeID),
     #[error("There was a divide overflow while computing the exchange rate, performing the division {0}/{1}.")]
     DivideOverflow(Decimal, Decimal),
+    #[error("There was a multiply overflow while computing the exchange rate, performing the multiplication {0}*{1}.")]
+    MultiplyOverflow(Decimal, Decimal),
+    #[error("There was an add overflow while computing the exchange rate, performing the addition {0}+{1}.")]
+    AddOverflow(Decimal, Decimal),
+    #[error("There was a subtract overflow while computing the exchange rate, performing the subtraction {0}-{1}.")]
+    SubtractOverflow(Decimal, Decimal),
+    #[error("The exchange rate is {age} old, which is older than the maximum age of {max_age}.")]
+    StaleRate { age: Duration, max_age: Duration },
+    #[error("The exchange rate is dated {0} in the future.")]
+    FutureRate(Duration),
+    #[error("The age of the exchange rate is unknown, it has no date or obtained datetime.")]
+    UnknownAge,
+    #[error("The exchange rate does not have a date.")]
+    MissingDate,
 }
 
 /// Represents the exchange rate between [Commodity](Commodity)s
@@ -66,7 +104,7 @@ impl ExchangeRate {
                     let div = commodity
                         .value
                         .checked_div(*rate)
-                        .ok_or_else(|| ExchangeRateError::DivideOverflow(commodity.value, *rate))?;
+                        .ok_or(ExchangeRateError::DivideOverflow(commodity.value, *rate))?;
                     return Ok(Commodity::new(div, target_commodity_type));
                 };
             }
@@ -93,10 +131,9 @@ impl ExchangeRate {
             }
         };
 
-        let div = commodity
-            .value
-            .checked_div(*commodity_rate)
-            .ok_or_else(|| ExchangeRateError::DivideOverflow(commodity.value, *commodity_rate))?;
+        let div = commodity.value.checked_div(*commodity_rate).ok_or(
+            ExchangeRateError::DivideOverflow(commodity.value, *commodity_rate),
+        )?;
         let value = div * target_rate;
 
         Ok(Commodity::new(value, target_commodity_type))
@@ -111,13 +148,13 @@ impl ExchangeRate {
     ) -> Result<Option<Decimal>, ExchangeRateError> {
         if let Some(base) = &self.base {
             if from == base {
-                if let Some(rate) = self.get_rate(&to) {
+                if let Some(rate) = self.get_rate(to) {
                     return Ok(Some(*rate));
                 };
             }
 
             if to == base {
-                if let Some(rate) = self.get_rate(&from) {
+                if let Some(rate) = self.get_rate(from) {
                     let one = Decimal::new(1, 0);
                     return match one.checked_div(*rate) {
                         Some(value) => Ok(Some(value)),
@@ -130,12 +167,12 @@ impl ExchangeRate {
         // handle the situation where there is no base commodity type, or neither the from
         // or the to commodity types are the base commodity type.
 
-        let from_rate = match self.get_rate(&from) {
+        let from_rate = match self.get_rate(from) {
             Some(rate) => rate,
             None => return Ok(None),
         };
 
-        let to_rate = match self.get_rate(&to) {
+        let to_rate = match self.get_rate(to) {
             Some(rate) => rate,
             None => return Ok(None),
         };
@@ -145,11 +182,69 @@ impl ExchangeRate {
             None => Err(ExchangeRateError::DivideOverflow(*to_rate, *from_rate)),
         }
     }
+
+    /// Create a new [ExchangeRate](ExchangeRate) with the same rates,
+    /// but relative to a different `new_base` commodity type. The old
+    /// `base` (if there was one) is added to the `rates`, and the
+    /// `new_base` is removed from them.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::exchange_rate::ExchangeRate;
+    /// # use commodity::CommodityTypeID;
+    /// use rust_decimal::Decimal;
+    /// use std::collections::BTreeMap;
+    /// use std::str::FromStr;
+    ///
+    /// let usd = CommodityTypeID::from_str("USD").unwrap();
+    /// let aud = CommodityTypeID::from_str("AUD").unwrap();
+    /// let mut rates = BTreeMap::new();
+    /// rates.insert(aud, Decimal::from_str("1.6").unwrap());
+    /// let exchange_rate = ExchangeRate {
+    ///     date: None,
+    ///     obtained_datetime: None,
+    ///     base: Some(usd),
+    ///     rates,
+    /// };
+    ///
+    /// let rebased = exchange_rate.rebase(aud).unwrap();
+    /// assert_eq!(Some(aud), rebased.base);
+    /// assert_eq!(Some(&Decimal::from_str("0.625").unwrap()), rebased.get_rate(&usd));
+    /// assert_eq!(None, rebased.get_rate(&aud));
+    /// ```
+    pub fn rebase(&self, new_base: CommodityTypeID) -> Result<ExchangeRate, ExchangeRateError> {
+        if self.base == Some(new_base) {
+            return Ok(self.clone());
+        }
+
+        if !self.rates.contains_key(&new_base) {
+            return Err(ExchangeRateError::CommodityTypeNotPresent(new_base));
+        }
+
+        let mut rates = BTreeMap::new();
+        for id in self.base.iter().chain(self.rates.keys()) {
+            if *id == new_base {
+                continue;
+            }
+
+            let rate = self
+                .rate_between(&new_base, id)?
+                .ok_or(ExchangeRateError::CommodityTypeNotPresent(*id))?;
+            rates.insert(*id, rate);
+        }
+
+        Ok(ExchangeRate {
+            date: self.date,
+            obtained_datetime: self.obtained_datetime,
+            base: Some(new_base),
+            rates,
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Commodity, CommodityTypeID, ExchangeRate};
+    use super::{Commodity, CommodityTypeID, ExchangeRate, ExchangeRateError};
     use chrono::NaiveDate;
     use rust_decimal::Decimal;
     use std::collections::BTreeMap;
@@ -175,7 +270,7 @@ mod tests {
         let eu = CommodityTypeID::from_str("EU").unwrap();
 
         assert_eq!(
-            NaiveDate::from_ymd(2020, 02, 07),
+            NaiveDate::from_ymd_opt(2020, 2, 7).unwrap(),
             exchange_rate.date.unwrap()
         );
         assert_eq!("AUD", exchange_rate.base.unwrap());
@@ -212,7 +307,7 @@ mod tests {
         rates.insert(nzd, Decimal::from_str("1.7094").unwrap());
 
         let exchange_rate = ExchangeRate {
-            date: Some(NaiveDate::from_ymd(2020, 02, 07)),
+            date: Some(NaiveDate::from_ymd_opt(2020, 2, 7).unwrap()),
             base: None,
             obtained_datetime: None,
             rates,
@@ -227,7 +322,7 @@ mod tests {
             );
             assert_eq!(
                 exchange_rate.rate_between(&aud, &nzd).unwrap(),
-                Some(Decimal::from_str("1.0412377413656575501005055734").unwrap())
+                Some(Decimal::from_str("1.0412377413656575501005055735").unwrap())
             );
         }
 
@@ -240,11 +335,66 @@ mod tests {
             );
             assert_eq!(
                 exchange_rate.rate_between(&nzd, &aud).unwrap(),
-                Some(Decimal::from_str("0.9603954603954603954603954603").unwrap())
+                Some(Decimal::from_str("0.9603954603954603954603954604").unwrap())
             );
         }
     }
 
+    /// Rebase an exchange rate to a different base commodity type.
+    #[test]
+    fn rebase() {
+        let mut rates: BTreeMap<CommodityTypeID, Decimal> = BTreeMap::new();
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let eur = CommodityTypeID::from_str("EUR").unwrap();
+        rates.insert(aud, Decimal::from_str("1.5").unwrap());
+        rates.insert(eur, Decimal::from_str("0.9").unwrap());
+
+        let exchange_rate = ExchangeRate {
+            date: Some(NaiveDate::from_ymd_opt(2020, 2, 7).unwrap()),
+            base: Some(usd),
+            obtained_datetime: None,
+            rates,
+        };
+
+        let rebased = exchange_rate.rebase(aud).unwrap();
+        assert_eq!(exchange_rate.date, rebased.date);
+        assert_eq!(Some(aud), rebased.base);
+        assert_eq!(2, rebased.rates.len());
+        assert_eq!(
+            Decimal::from_str("0.6").unwrap(),
+            *rebased.get_rate(&eur).unwrap()
+        );
+        assert_eq!(
+            Decimal::from_str("0.6666666666666666666666666667").unwrap(),
+            *rebased.get_rate(&usd).unwrap()
+        );
+
+        // rebasing back gives the original rates (to within rounding)
+        let original = rebased.rebase(usd).unwrap();
+        assert_eq!(
+            Decimal::from_str("1.5").unwrap(),
+            original.get_rate(&aud).unwrap().round_dp(20)
+        );
+        assert_eq!(
+            Decimal::from_str("0.9").unwrap(),
+            original.get_rate(&eur).unwrap().round_dp(20)
+        );
+
+        let nzd = CommodityTypeID::from_str("NZD").unwrap();
+        assert_eq!(
+            ExchangeRateError::CommodityTypeNotPresent(nzd),
+            exchange_rate.rebase(nzd).unwrap_err()
+        );
+
+        let mut zero_rates = exchange_rate.clone();
+        zero_rates.rates.insert(nzd, Decimal::ZERO);
+        assert_eq!(
+            ExchangeRateError::DivideOverflow(Decimal::new(1, 0), Decimal::ZERO),
+            zero_rates.rebase(nzd).unwrap_err()
+        );
+    }
+
     /// Convert between commodities using an exchange rate with a base rate.
     #[test]
     fn convert_base_rate() {
@@ -257,7 +407,7 @@ mod tests {
         rates.insert(gel, Decimal::from_str("3.08").unwrap());
 
         let exchange_rate = ExchangeRate {
-            date: Some(NaiveDate::from_ymd(2020, 02, 07)),
+            date: Some(NaiveDate::from_ymd_opt(2020, 2, 7).unwrap()),
             base: Some(usd),
             obtained_datetime: None,
             rates,
@@ -280,7 +430,7 @@ mod tests {
             let start_commodity = Commodity::new(Decimal::from_str("100.0").unwrap(), nok);
             let converted_commodity = exchange_rate.convert(start_commodity, usd);
             assert_eq!(
-                Decimal::from_str("10.788508256853169187585300627").unwrap(),
+                Decimal::from_str("10.788508256853169187585300628").unwrap(),
                 converted_commodity.unwrap().value
             );
             assert_eq!(
@@ -293,7 +443,7 @@ mod tests {
             let start_commodity = Commodity::new(Decimal::from_str("100.0").unwrap(), nok);
             let converted_commodity = exchange_rate.convert(start_commodity, gel);
             assert_eq!(
-                Decimal::from_str("33.228605431107761097762725931").unwrap(),
+                Decimal::from_str("33.228605431107761097762725934").unwrap(),
                 converted_commodity.unwrap().value
             );
             assert_eq!(
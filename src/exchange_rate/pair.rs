@@ -0,0 +1,475 @@
+//! [CurrencyPair](CurrencyPair)s such as `EUR/USD`, their market
+//! quoting conventions, and converting between pair quotes and
+//! [ExchangeRate](ExchangeRate) tables.
+
+use super::{ExchangeRate, ExchangeRateError};
+use crate::CommodityTypeID;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[cfg(feature = "serde-support")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// An error associated with a [CurrencyPair](CurrencyPair).
+#[derive(Error, Debug, PartialEq)]
+pub enum CurrencyPairError {
+    #[error("The currency pair {0:?} is invalid, it should be in the form \"EUR/USD\" with two different commodity types.")]
+    InvalidCurrencyPair(String),
+    #[error("The rate {rate} for the currency pair {pair} is invalid, it needs to be positive.")]
+    InvalidRate { pair: CurrencyPair, rate: Decimal },
+    #[error("There was an error with the exchange rate.")]
+    ExchangeRate(#[from] ExchangeRateError),
+}
+
+/// The commodity types which are conventionally quoted as the base of
+/// a currency pair against any other commodity type, from the highest
+/// priority to the lowest.
+const MARKET_CONVENTION_BASE_PRIORITY: [&str; 6] = ["EUR", "GBP", "AUD", "NZD", "USD", "CAD"];
+
+/// The commodity types which are conventionally quoted as the quote of
+/// a currency pair against any commodity type not in
+/// [MARKET_CONVENTION_BASE_PRIORITY](MARKET_CONVENTION_BASE_PRIORITY)
+/// (e.g. `SEK/JPY`), from the highest priority to the lowest.
+const MARKET_CONVENTION_QUOTE_PRIORITY: [&str; 2] = ["CHF", "JPY"];
+
+/// A currency pair such as `EUR/USD`, the quote for which is the
+/// number of `quote` units per one `base` unit (e.g. `1.0951` USD per
+/// EUR).
+///
+/// Unlike an [ExchangeRate](ExchangeRate) table (where each rate is
+/// the number of units of a commodity type per one unit of the
+/// table's base), the direction of the quote is explicit in the pair.
+/// [CurrencyPair::market_convention()](CurrencyPair::market_convention)
+/// gives the direction which the pair is conventionally quoted in.
+///
+/// # Example
+/// ```
+/// # use commodity::exchange_rate::{CurrencyPair, ExchangeRate};
+/// # use commodity::CommodityTypeID;
+/// use rust_decimal::Decimal;
+/// use std::collections::BTreeMap;
+/// use std::str::FromStr;
+///
+/// let pair = CurrencyPair::from_str("USD/EUR").unwrap();
+/// let eur_usd = pair.market_convention();
+/// assert_eq!("EUR/USD", eur_usd.to_string());
+///
+/// // a usd based table, with 0.9 EUR per USD
+/// let usd = CommodityTypeID::from_str("USD").unwrap();
+/// let eur = CommodityTypeID::from_str("EUR").unwrap();
+/// let mut rates = BTreeMap::new();
+/// rates.insert(eur, Decimal::from_str("0.9").unwrap());
+/// let exchange_rate = ExchangeRate {
+///     date: None,
+///     obtained_datetime: None,
+///     base: Some(usd),
+///     rates,
+/// };
+///
+/// assert_eq!(
+///     Some(Decimal::from_str("0.9").unwrap()),
+///     exchange_rate.pair_rate(&pair).unwrap()
+/// );
+/// assert_eq!(
+///     Some(Decimal::from_str("1.1111111111111111111111111111").unwrap()),
+///     exchange_rate.pair_rate(&eur_usd).unwrap()
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CurrencyPair {
+    /// The commodity type being priced.
+    pub base: CommodityTypeID,
+    /// The commodity type which the price is in.
+    pub quote: CommodityTypeID,
+}
+
+impl CurrencyPair {
+    /// Create a new [CurrencyPair](CurrencyPair).
+    pub fn new(base: CommodityTypeID, quote: CommodityTypeID) -> CurrencyPair {
+        CurrencyPair { base, quote }
+    }
+
+    /// The same pair in the opposite direction (e.g. `USD/EUR` for
+    /// `EUR/USD`).
+    pub fn inverse(&self) -> CurrencyPair {
+        CurrencyPair::new(self.quote, self.base)
+    }
+
+    /// The market convention priority of the commodity type, where
+    /// `0` is the highest priority, or `None` if it is not one of the
+    /// commodity types with a conventional priority
+    /// (`EUR > GBP > AUD > NZD > USD > CAD > CHF > JPY`).
+    pub fn priority(commodity_type_id: &CommodityTypeID) -> Option<usize> {
+        let id = commodity_type_id.to_string();
+        MARKET_CONVENTION_BASE_PRIORITY
+            .iter()
+            .chain(MARKET_CONVENTION_QUOTE_PRIORITY.iter())
+            .position(|priority_id| *priority_id == id)
+    }
+
+    /// The position of the commodity type in the market convention
+    /// ordering, where commodity types without a
+    /// [priority()](CurrencyPair::priority) rank below `CAD` but above
+    /// `CHF` and `JPY`, and are ordered alphabetically.
+    fn rank(commodity_type_id: &CommodityTypeID) -> (usize, String) {
+        let unlisted = MARKET_CONVENTION_BASE_PRIORITY.len();
+        match CurrencyPair::priority(commodity_type_id) {
+            Some(priority) if priority < unlisted => (priority, String::new()),
+            Some(priority) => (priority + 1, String::new()),
+            None => (unlisted, commodity_type_id.to_string()),
+        }
+    }
+
+    /// Whether this pair is in the direction it is conventionally
+    /// quoted in. The commodity type with the higher
+    /// [priority()](CurrencyPair::priority) is the base, except that
+    /// `CHF` and `JPY` are the quote against commodity types without a
+    /// priority (e.g. `SEK/JPY`), and commodity types without a
+    /// priority are ordered alphabetically.
+    pub fn is_market_convention(&self) -> bool {
+        CurrencyPair::rank(&self.base) <= CurrencyPair::rank(&self.quote)
+    }
+
+    /// This pair in the direction it is conventionally quoted in, see
+    /// [is_market_convention()](CurrencyPair::is_market_convention).
+    pub fn market_convention(&self) -> CurrencyPair {
+        if self.is_market_convention() {
+            *self
+        } else {
+            self.inverse()
+        }
+    }
+
+    /// Create an [ExchangeRate](ExchangeRate) table containing the
+    /// `rate` quoted for this pair, with the pair's `base` as its base.
+    pub fn to_exchange_rate(&self, rate: Decimal) -> ExchangeRate {
+        let mut rates = BTreeMap::new();
+        rates.insert(self.quote, rate);
+        ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(self.base),
+            rates,
+        }
+    }
+}
+
+impl fmt::Display for CurrencyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.base, self.quote)
+    }
+}
+
+impl FromStr for CurrencyPair {
+    type Err = CurrencyPairError;
+
+    /// Parse a pair in the form `EUR/USD`, or `EURUSD` for pairs of
+    /// three character ids. Ids containing `/` or whitespace are
+    /// rejected.
+    fn from_str(pair: &str) -> Result<CurrencyPair, CurrencyPairError> {
+        let invalid = || CurrencyPairError::InvalidCurrencyPair(pair.to_string());
+        let trimmed = pair.trim();
+
+        let (base, quote) = match trimmed.split_once('/') {
+            Some(ids) => ids,
+            None if trimmed.len() == 6 && trimmed.is_ascii() => trimmed.split_at(3),
+            None => return Err(invalid()),
+        };
+
+        let (base, quote) = (base.trim(), quote.trim());
+        let is_invalid_id = |id: &str| id.contains('/') || id.contains(char::is_whitespace);
+        if is_invalid_id(base) || is_invalid_id(quote) {
+            return Err(invalid());
+        }
+
+        let base = CommodityTypeID::from_str(base).map_err(|_| invalid())?;
+        let quote = CommodityTypeID::from_str(quote).map_err(|_| invalid())?;
+        if base == quote || base.to_string().is_empty() || quote.to_string().is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(CurrencyPair::new(base, quote))
+    }
+}
+
+#[cfg(feature = "serde-support")]
+impl Serialize for CurrencyPair {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(feature = "serde-support")]
+impl<'de> Deserialize<'de> for CurrencyPair {
+    fn deserialize<D>(deserializer: D) -> Result<CurrencyPair, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pair = String::deserialize(deserializer)?;
+        CurrencyPair::from_str(&pair).map_err(serde::de::Error::custom)
+    }
+}
+
+impl ExchangeRate {
+    /// The quote for the currency `pair` (the number of `pair.quote`
+    /// units per one `pair.base` unit), calculated from this table. See
+    /// [rate_between()](ExchangeRate::rate_between).
+    pub fn pair_rate(&self, pair: &CurrencyPair) -> Result<Option<Decimal>, ExchangeRateError> {
+        self.rate_between(&pair.base, &pair.quote)
+    }
+
+    /// Insert the `rate` quoted for the currency `pair` into this
+    /// table, converting it to be relative to the table's base. One
+    /// of the pair's commodity types needs to be the base of the
+    /// table, or already be present in it; an empty table without a
+    /// base takes the pair's base as its base. The `rate` needs to be
+    /// positive, and the pair needs to have two different commodity
+    /// types.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::exchange_rate::{CurrencyPair, ExchangeRate};
+    /// # use commodity::CommodityTypeID;
+    /// use rust_decimal::Decimal;
+    /// use std::collections::BTreeMap;
+    /// use std::str::FromStr;
+    ///
+    /// let usd = CommodityTypeID::from_str("USD").unwrap();
+    /// let eur = CommodityTypeID::from_str("EUR").unwrap();
+    /// let mut exchange_rate = ExchangeRate {
+    ///     date: None,
+    ///     obtained_datetime: None,
+    ///     base: Some(usd),
+    ///     rates: BTreeMap::new(),
+    /// };
+    ///
+    /// let eur_usd = CurrencyPair::from_str("EUR/USD").unwrap();
+    /// exchange_rate.insert_pair_rate(&eur_usd, Decimal::from_str("1.25").unwrap()).unwrap();
+    /// assert_eq!(Some(&Decimal::from_str("0.8").unwrap()), exchange_rate.get_rate(&eur));
+    /// ```
+    pub fn insert_pair_rate(
+        &mut self,
+        pair: &CurrencyPair,
+        rate: Decimal,
+    ) -> Result<(), CurrencyPairError> {
+        if pair.base == pair.quote {
+            return Err(CurrencyPairError::InvalidCurrencyPair(pair.to_string()));
+        }
+        if rate <= Decimal::ZERO {
+            return Err(CurrencyPairError::InvalidRate { pair: *pair, rate });
+        }
+
+        if self.base.is_none() && self.rates.is_empty() {
+            self.base = Some(pair.base);
+        }
+
+        let (id, value) = if self.base == Some(pair.base) {
+            (pair.quote, rate)
+        } else if self.base == Some(pair.quote) {
+            let one = Decimal::ONE;
+            let value = one
+                .checked_div(rate)
+                .ok_or(ExchangeRateError::DivideOverflow(one, rate))?;
+            (pair.base, value)
+        } else if let Some(base_rate) = self.get_rate(&pair.base) {
+            let value = base_rate
+                .checked_mul(rate)
+                .ok_or(ExchangeRateError::MultiplyOverflow(*base_rate, rate))?;
+            (pair.quote, value)
+        } else if let Some(quote_rate) = self.get_rate(&pair.quote) {
+            let value = quote_rate
+                .checked_div(rate)
+                .ok_or(ExchangeRateError::DivideOverflow(*quote_rate, rate))?;
+            (pair.base, value)
+        } else {
+            return Err(ExchangeRateError::CommodityTypeNotPresent(pair.base).into());
+        };
+
+        self.rates.insert(id, value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CurrencyPair, CurrencyPairError};
+    use crate::exchange_rate::{ExchangeRate, ExchangeRateError};
+    use crate::CommodityTypeID;
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn parse_display() {
+        let pair = CurrencyPair::from_str("EUR/USD").unwrap();
+        assert_eq!(CommodityTypeID::from_str("EUR").unwrap(), pair.base);
+        assert_eq!(CommodityTypeID::from_str("USD").unwrap(), pair.quote);
+        assert_eq!("EUR/USD", pair.to_string());
+        assert_eq!(pair, CurrencyPair::from_str("EURUSD").unwrap());
+        assert_eq!(pair, CurrencyPair::from_str(" EUR / USD ").unwrap());
+        assert_eq!("USD/EUR", pair.inverse().to_string());
+        assert_eq!(
+            "BTC/USDT",
+            CurrencyPair::from_str("BTC/USDT").unwrap().to_string()
+        );
+
+        for invalid in &[
+            "EUR",
+            "EUR/",
+            "/USD",
+            "EUR/EUR",
+            "EUR-USD",
+            "EURUSDX",
+            "EUR/USD/GBP",
+            "EUR/US D",
+            "EU SD",
+        ] {
+            assert_eq!(
+                CurrencyPairError::InvalidCurrencyPair(invalid.to_string()),
+                CurrencyPair::from_str(invalid).unwrap_err()
+            );
+        }
+    }
+
+    #[test]
+    fn market_convention() {
+        let convention = |pair: &str| {
+            CurrencyPair::from_str(pair)
+                .unwrap()
+                .market_convention()
+                .to_string()
+        };
+
+        assert_eq!("EUR/USD", convention("USD/EUR"));
+        assert_eq!("EUR/GBP", convention("GBP/EUR"));
+        assert_eq!("GBP/USD", convention("GBP/USD"));
+        assert_eq!("AUD/USD", convention("USD/AUD"));
+        assert_eq!("NZD/USD", convention("USD/NZD"));
+        assert_eq!("USD/JPY", convention("JPY/USD"));
+        assert_eq!("USD/CAD", convention("CAD/USD"));
+        assert_eq!("CHF/JPY", convention("JPY/CHF"));
+        assert_eq!("USD/CHF", convention("CHF/USD"));
+        assert_eq!("EUR/JPY", convention("JPY/EUR"));
+        // commodity types without a priority are quoted against those with one
+        assert_eq!("USD/MXN", convention("MXN/USD"));
+        assert_eq!("CAD/SEK", convention("SEK/CAD"));
+        // except for CHF and JPY, which are the quote
+        assert_eq!("SEK/JPY", convention("JPY/SEK"));
+        assert_eq!("MXN/JPY", convention("JPY/MXN"));
+        assert_eq!("ZAR/JPY", convention("JPY/ZAR"));
+        assert_eq!("TRY/JPY", convention("TRY/JPY"));
+        assert_eq!("SEK/CHF", convention("CHF/SEK"));
+        // and alphabetically against each other
+        assert_eq!("MXN/SEK", convention("SEK/MXN"));
+
+        assert!(CurrencyPair::from_str("EUR/USD")
+            .unwrap()
+            .is_market_convention());
+        assert!(!CurrencyPair::from_str("USD/EUR")
+            .unwrap()
+            .is_market_convention());
+    }
+
+    #[test]
+    fn pair_rates() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let eur = CommodityTypeID::from_str("EUR").unwrap();
+        let gbp = CommodityTypeID::from_str("GBP").unwrap();
+        let jpy = CommodityTypeID::from_str("JPY").unwrap();
+
+        let mut exchange_rate = ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: None,
+            rates: BTreeMap::new(),
+        };
+
+        let eur_usd = CurrencyPair::new(eur, usd);
+        let rate = Decimal::from_str("1.25").unwrap();
+        exchange_rate.insert_pair_rate(&eur_usd, rate).unwrap();
+        assert_eq!(Some(eur), exchange_rate.base);
+        assert_eq!(Some(rate), exchange_rate.pair_rate(&eur_usd).unwrap());
+
+        // relative to a commodity type already in the table
+        let usd_jpy = CurrencyPair::new(usd, jpy);
+        exchange_rate
+            .insert_pair_rate(&usd_jpy, Decimal::from_str("110").unwrap())
+            .unwrap();
+        assert_eq!(
+            Some(&Decimal::from_str("137.5").unwrap()),
+            exchange_rate.get_rate(&jpy)
+        );
+        assert_eq!(
+            Some(Decimal::from_str("110").unwrap()),
+            exchange_rate.pair_rate(&usd_jpy).unwrap()
+        );
+
+        // quoted with the table's base as the quote
+        let gbp_eur = CurrencyPair::new(gbp, eur);
+        exchange_rate
+            .insert_pair_rate(&gbp_eur, Decimal::from_str("1.6").unwrap())
+            .unwrap();
+        assert_eq!(
+            Some(&Decimal::from_str("0.625").unwrap()),
+            exchange_rate.get_rate(&gbp)
+        );
+        assert_eq!(
+            Some(Decimal::from_str("2").unwrap()),
+            exchange_rate
+                .pair_rate(&CurrencyPair::new(gbp, usd))
+                .unwrap()
+        );
+
+        // the round trip through a pair's exchange rate
+        assert_eq!(
+            Some(rate),
+            eur_usd.to_exchange_rate(rate).pair_rate(&eur_usd).unwrap()
+        );
+
+        let chf = CommodityTypeID::from_str("CHF").unwrap();
+        let nzd = CommodityTypeID::from_str("NZD").unwrap();
+        assert_eq!(
+            CurrencyPairError::ExchangeRate(ExchangeRateError::CommodityTypeNotPresent(chf)),
+            exchange_rate
+                .insert_pair_rate(&CurrencyPair::new(chf, nzd), Decimal::ONE)
+                .unwrap_err()
+        );
+
+        // invalid rates and pairs are rejected, leaving the table unchanged
+        let before = exchange_rate.clone();
+        for invalid in &[Decimal::ZERO, Decimal::from_str("-1.25").unwrap()] {
+            assert_eq!(
+                CurrencyPairError::InvalidRate {
+                    pair: eur_usd,
+                    rate: *invalid
+                },
+                exchange_rate
+                    .insert_pair_rate(&eur_usd, *invalid)
+                    .unwrap_err()
+            );
+        }
+        assert_eq!(
+            CurrencyPairError::InvalidCurrencyPair("USD/USD".to_string()),
+            exchange_rate
+                .insert_pair_rate(&CurrencyPair::new(usd, usd), Decimal::ONE)
+                .unwrap_err()
+        );
+        assert_eq!(before, exchange_rate);
+    }
+
+    #[cfg(feature = "serde-support")]
+    #[test]
+    fn test_json_serialization() {
+        let pair = CurrencyPair::from_str("EUR/USD").unwrap();
+        let json = serde_json::to_string(&pair).unwrap();
+        assert_eq!("\"EUR/USD\"", json);
+        assert_eq!(pair, serde_json::from_str(&json).unwrap());
+        assert!(serde_json::from_str::<CurrencyPair>("\"EUR\"").is_err());
+    }
+}
@@ -0,0 +1,451 @@
+//! Two-sided [Quote](Quote)s with a bid and an ask, and the
+//! [QuotedExchangeRate](QuotedExchangeRate) table for converting
+//! commodities on a chosen [Side](Side).
+
+use super::{ExchangeRate, ExchangeRateError};
+use crate::{Commodity, CommodityTypeID};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+#[cfg(feature = "serde-support")]
+use std::convert::TryFrom;
+
+#[cfg(feature = "serde-support")]
+use serde::{Deserialize, Serialize};
+
+/// An error associated with a [Quote](Quote).
+#[derive(Error, Debug, PartialEq)]
+pub enum QuoteError {
+    #[error("The quote with a bid of {0} and an ask of {1} is invalid, the bid needs to be positive and not greater than the ask.")]
+    InvalidQuote(Decimal, Decimal),
+}
+
+/// Which side of a [Quote](Quote) to use when converting a
+/// [Commodity](Commodity) with
+/// [QuotedExchangeRate::convert_with_side()](QuotedExchangeRate::convert_with_side).
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    /// Buy the commodity, paying with the target commodity type (at
+    /// the ask rate).
+    Buy,
+    /// Sell the commodity, receiving the target commodity type (at the
+    /// bid rate).
+    Sell,
+    /// Convert at the mid rate.
+    Mid,
+}
+
+/// A two-sided quote for the price of one commodity type in another.
+/// The `bid` is the rate at which the quoted commodity type can be
+/// sold, and the `ask` is the rate at which it can be bought. The `bid`
+/// is always positive, and not greater than the `ask`.
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-support", serde(try_from = "RawQuote"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Quote {
+    bid: Decimal,
+    ask: Decimal,
+}
+
+/// The serialized form of a [Quote](Quote), which is checked with
+/// [Quote::new()](Quote::new) when it is deserialized.
+#[cfg(feature = "serde-support")]
+#[derive(Deserialize)]
+struct RawQuote {
+    bid: Decimal,
+    ask: Decimal,
+}
+
+#[cfg(feature = "serde-support")]
+impl TryFrom<RawQuote> for Quote {
+    type Error = QuoteError;
+
+    fn try_from(raw: RawQuote) -> Result<Quote, QuoteError> {
+        Quote::new(raw.bid, raw.ask)
+    }
+}
+
+impl Quote {
+    /// Create a new [Quote](Quote). The `bid` needs to be positive,
+    /// and not greater than the `ask`.
+    pub fn new(bid: Decimal, ask: Decimal) -> Result<Quote, QuoteError> {
+        if bid <= Decimal::ZERO || bid > ask {
+            return Err(QuoteError::InvalidQuote(bid, ask));
+        }
+        Ok(Quote { bid, ask })
+    }
+
+    /// The rate at which the quoted commodity type can be sold.
+    pub fn bid(&self) -> Decimal {
+        self.bid
+    }
+
+    /// The rate at which the quoted commodity type can be bought.
+    pub fn ask(&self) -> Decimal {
+        self.ask
+    }
+
+    /// The mid rate, halfway between the `bid` and the `ask`.
+    pub fn mid(&self) -> Decimal {
+        // the bid and ask are both positive, so neither the spread nor
+        // the result can overflow.
+        self.bid + self.spread() / Decimal::new(2, 0)
+    }
+
+    /// The difference between the `ask` and the `bid`.
+    pub fn spread(&self) -> Decimal {
+        self.ask - self.bid
+    }
+
+    /// The rate to use for the specified [Side](Side).
+    pub fn rate(&self, side: Side) -> Decimal {
+        match side {
+            Side::Buy => self.ask,
+            Side::Sell => self.bid,
+            Side::Mid => self.mid(),
+        }
+    }
+
+    /// The inverse of this quote, for the price of the other commodity
+    /// type. Selling the other commodity type is buying this one, so
+    /// the inverse `bid` is `1/ask`, and the inverse `ask` is `1/bid`.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::exchange_rate::Quote;
+    /// use rust_decimal::Decimal;
+    ///
+    /// let quote = Quote::new(Decimal::new(125, 2), Decimal::new(160, 2)).unwrap();
+    /// let inverse = quote.inverse().unwrap();
+    /// assert_eq!(Decimal::new(625, 3), inverse.bid());
+    /// assert_eq!(Decimal::new(8, 1), inverse.ask());
+    /// ```
+    pub fn inverse(&self) -> Result<Quote, ExchangeRateError> {
+        let one = Decimal::new(1, 0);
+        Ok(Quote {
+            bid: one
+                .checked_div(self.ask)
+                .ok_or(ExchangeRateError::DivideOverflow(one, self.ask))?,
+            ask: one
+                .checked_div(self.bid)
+                .ok_or(ExchangeRateError::DivideOverflow(one, self.bid))?,
+        })
+    }
+
+    /// Compose this quote (from commodity type `A` to `B`) with the
+    /// quote `other` (from `B` to `C`) to create a quote from `A` to
+    /// `C`.
+    pub fn compose(&self, other: &Quote) -> Result<Quote, ExchangeRateError> {
+        Ok(Quote {
+            bid: self
+                .bid
+                .checked_mul(other.bid)
+                .ok_or(ExchangeRateError::MultiplyOverflow(self.bid, other.bid))?,
+            ask: self
+                .ask
+                .checked_mul(other.ask)
+                .ok_or(ExchangeRateError::MultiplyOverflow(self.ask, other.ask))?,
+        })
+    }
+}
+
+/// An exchange rate table of two-sided [Quote](Quote)s, the equivalent
+/// of an [ExchangeRate](ExchangeRate) for a dealer who buys and sells
+/// at different rates.
+///
+/// # Example
+/// ```
+/// # use commodity::exchange_rate::{Quote, QuotedExchangeRate, Side};
+/// # use commodity::{Commodity, CommodityTypeID};
+/// use rust_decimal::Decimal;
+/// use std::collections::BTreeMap;
+/// use std::str::FromStr;
+///
+/// let usd = CommodityTypeID::from_str("USD").unwrap();
+/// let jpy = CommodityTypeID::from_str("JPY").unwrap();
+///
+/// let mut quotes = BTreeMap::new();
+/// quotes.insert(jpy, Quote::new(Decimal::new(100, 0), Decimal::new(125, 0)).unwrap());
+/// let exchange_rate = QuotedExchangeRate {
+///     date: None,
+///     obtained_datetime: None,
+///     base: Some(usd),
+///     quotes,
+/// };
+///
+/// // selling 10 USD receives 1000 JPY at the bid rate
+/// let usd_10 = Commodity::from_str("10 USD").unwrap();
+/// assert_eq!(
+///     Commodity::from_str("1000 JPY").unwrap(),
+///     exchange_rate.convert_with_side(usd_10, jpy, Side::Sell).unwrap()
+/// );
+///
+/// // selling 1000 JPY receives 8 USD, at the inverse of the ask rate
+/// let jpy_1000 = Commodity::from_str("1000 JPY").unwrap();
+/// assert_eq!(
+///     Commodity::from_str("8 USD").unwrap(),
+///     exchange_rate.convert_with_side(jpy_1000, usd, Side::Sell).unwrap()
+/// );
+/// ```
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotedExchangeRate {
+    /// The datetime that this exchange rate represents
+    pub date: Option<NaiveDate>,
+    /// The datetime that this exchange rate was obtained.
+    pub obtained_datetime: Option<DateTime<Utc>>,
+    /// The id of the base commodity type for the exchange rate
+    pub base: Option<CommodityTypeID>,
+    /// Maps commodity type ids, to the [Quote](Quote) for the number
+    /// of units of that [CommodityType](crate::CommodityType) per unit
+    /// of the `base` [CommodityType](crate::CommodityType).
+    pub quotes: BTreeMap<CommodityTypeID, Quote>,
+}
+
+impl QuotedExchangeRate {
+    pub fn get_quote(&self, commodity_type_id: &CommodityTypeID) -> Option<&Quote> {
+        self.quotes.get(commodity_type_id)
+    }
+
+    /// Get the [Quote](Quote) for the number of units of `to` per unit
+    /// of `from`. Returns `None` if one of the commodity types is not
+    /// present. When `from` and `to` are the same commodity type the
+    /// quote is exactly one on both sides, no spread is charged.
+    pub fn quote_between(
+        &self,
+        from: &CommodityTypeID,
+        to: &CommodityTypeID,
+    ) -> Result<Option<Quote>, ExchangeRateError> {
+        let one = Decimal::new(1, 0);
+        // the quote for the number of units of the specified commodity
+        // type per unit of the base (or the reference commodity type).
+        let base_quote = |id: &CommodityTypeID| {
+            if Some(*id) == self.base {
+                Some(Quote { bid: one, ask: one })
+            } else {
+                self.get_quote(id).cloned()
+            }
+        };
+
+        match (base_quote(from), base_quote(to)) {
+            (Some(_), Some(_)) if from == to => Ok(Some(Quote { bid: one, ask: one })),
+            (Some(from_quote), Some(to_quote)) => {
+                Ok(Some(from_quote.inverse()?.compose(&to_quote)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Convert the [CommodityType](crate::CommodityType) of a
+    /// [Commodity](Commodity) to another
+    /// [CommodityType](crate::CommodityType), using the rate for the
+    /// specified [Side](Side).
+    ///
+    /// When converting between two commodity types which are not the
+    /// `base`, the conversion goes through the `base`, selling the
+    /// `commodity` for the `base`, and then selling the `base` for the
+    /// target commodity type (or buying, for [Side::Buy](Side::Buy)).
+    pub fn convert_with_side(
+        &self,
+        commodity: Commodity,
+        target_commodity_type: CommodityTypeID,
+        side: Side,
+    ) -> Result<Commodity, ExchangeRateError> {
+        let rate = match side {
+            Side::Mid => self
+                .mid_exchange_rate()
+                .rate_between(&commodity.type_id, &target_commodity_type)?,
+            _ => self
+                .quote_between(&commodity.type_id, &target_commodity_type)?
+                .map(|quote| quote.rate(side)),
+        };
+
+        let rate = rate.ok_or_else(|| {
+            if self.get_quote(&commodity.type_id).is_none() && self.base != Some(commodity.type_id)
+            {
+                ExchangeRateError::CommodityTypeNotPresent(commodity.type_id)
+            } else {
+                ExchangeRateError::CommodityTypeNotPresent(target_commodity_type)
+            }
+        })?;
+
+        let value = commodity
+            .value
+            .checked_mul(rate)
+            .ok_or(ExchangeRateError::MultiplyOverflow(commodity.value, rate))?;
+
+        Ok(Commodity::new(value, target_commodity_type))
+    }
+
+    /// Create an [ExchangeRate](ExchangeRate) using the mid rate of
+    /// each quote.
+    pub fn mid_exchange_rate(&self) -> ExchangeRate {
+        ExchangeRate {
+            date: self.date,
+            obtained_datetime: self.obtained_datetime,
+            base: self.base,
+            rates: self
+                .quotes
+                .iter()
+                .map(|(id, quote)| (*id, quote.mid()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Quote, QuoteError, QuotedExchangeRate, Side};
+    use crate::exchange_rate::ExchangeRateError;
+    use crate::{Commodity, CommodityTypeID};
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    fn quote(bid: &str, ask: &str) -> Quote {
+        Quote::new(
+            Decimal::from_str(bid).unwrap(),
+            Decimal::from_str(ask).unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn test_exchange_rate() -> QuotedExchangeRate {
+        let mut quotes = BTreeMap::new();
+        quotes.insert(
+            CommodityTypeID::from_str("AUD").unwrap(),
+            quote("1.5", "1.6"),
+        );
+        quotes.insert(
+            CommodityTypeID::from_str("NZD").unwrap(),
+            quote("1.6", "1.7"),
+        );
+
+        QuotedExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(CommodityTypeID::from_str("USD").unwrap()),
+            quotes,
+        }
+    }
+
+    #[test]
+    fn invalid_quote() {
+        assert_eq!(
+            QuoteError::InvalidQuote(Decimal::new(2, 0), Decimal::new(1, 0)),
+            Quote::new(Decimal::new(2, 0), Decimal::new(1, 0)).unwrap_err()
+        );
+        assert!(Quote::new(Decimal::ZERO, Decimal::new(1, 0)).is_err());
+        assert_eq!(Decimal::new(155, 2), quote("1.5", "1.6").mid());
+        assert_eq!(Decimal::new(15, 1), quote("1.5", "1.6").bid());
+        assert_eq!(Decimal::new(16, 1), quote("1.5", "1.6").ask());
+
+        // the mid of a quote near the maximum does not overflow
+        let max = Quote::new(Decimal::MAX - Decimal::new(2, 0), Decimal::MAX).unwrap();
+        assert_eq!(Decimal::MAX - Decimal::ONE, max.mid());
+    }
+
+    /// Converting always uses the side which is less favourable to
+    /// the customer.
+    #[test]
+    fn convert_sides() {
+        let exchange_rate = test_exchange_rate();
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let nzd = CommodityTypeID::from_str("NZD").unwrap();
+        let convert = |commodity: &str, target, side| {
+            exchange_rate
+                .convert_with_side(Commodity::from_str(commodity).unwrap(), target, side)
+                .unwrap()
+        };
+
+        // from the base
+        assert_eq!(
+            Commodity::from_str("150 AUD").unwrap(),
+            convert("100 USD", aud, Side::Sell)
+        );
+        assert_eq!(
+            Commodity::from_str("160 AUD").unwrap(),
+            convert("100 USD", aud, Side::Buy)
+        );
+        assert_eq!(
+            Commodity::from_str("155 AUD").unwrap(),
+            convert("100 USD", aud, Side::Mid)
+        );
+
+        // to the base
+        assert_eq!(
+            Commodity::from_str("100 USD").unwrap(),
+            convert("160 AUD", usd, Side::Sell)
+        );
+        assert_eq!(
+            Commodity::from_str("100 USD").unwrap(),
+            convert("150 AUD", usd, Side::Buy)
+        );
+
+        // through the base
+        let sell = convert("160 AUD", nzd, Side::Sell);
+        assert_eq!(Decimal::new(160, 0), sell.value.round_dp(10));
+        let buy = convert("150 AUD", nzd, Side::Buy);
+        assert_eq!(Decimal::new(170, 0), buy.value.round_dp(10));
+        assert!(sell.value / Decimal::new(160, 0) < buy.value / Decimal::new(150, 0));
+
+        let eur = CommodityTypeID::from_str("EUR").unwrap();
+        assert_eq!(
+            ExchangeRateError::CommodityTypeNotPresent(eur),
+            exchange_rate
+                .convert_with_side(Commodity::from_str("1 AUD").unwrap(), eur, Side::Sell)
+                .unwrap_err()
+        );
+    }
+
+    /// Converting to the same commodity type does not charge the
+    /// spread, even when it is not the base.
+    #[test]
+    fn convert_same_commodity_type() {
+        let exchange_rate = test_exchange_rate();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let unit = quote("1", "1");
+
+        assert_eq!(Some(unit), exchange_rate.quote_between(&aud, &aud).unwrap());
+        assert_eq!(
+            None,
+            exchange_rate
+                .quote_between(
+                    &CommodityTypeID::from_str("EUR").unwrap(),
+                    &CommodityTypeID::from_str("EUR").unwrap()
+                )
+                .unwrap()
+        );
+
+        for side in &[Side::Buy, Side::Sell, Side::Mid] {
+            assert_eq!(
+                Commodity::from_str("100 AUD").unwrap(),
+                exchange_rate
+                    .convert_with_side(Commodity::from_str("100 AUD").unwrap(), aud, *side)
+                    .unwrap()
+            );
+        }
+    }
+
+    #[cfg(feature = "serde-support")]
+    #[test]
+    fn test_json_serialization() {
+        use serde_json;
+
+        let exchange_rate = test_exchange_rate();
+        let serialized = serde_json::to_string(&exchange_rate).unwrap();
+        assert_eq!(
+            r#"{"date":null,"obtained_datetime":null,"base":"USD","quotes":{"AUD":{"bid":"1.5","ask":"1.6"},"NZD":{"bid":"1.6","ask":"1.7"}}}"#,
+            serialized
+        );
+        assert_eq!(exchange_rate, serde_json::from_str(&serialized).unwrap());
+
+        // invalid quotes are rejected
+        assert!(serde_json::from_str::<Quote>(r#"{"bid":"1.7","ask":"1.6"}"#).is_err());
+        assert!(serde_json::from_str::<Quote>(r#"{"bid":"0","ask":"1.6"}"#).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+//! Rounding the result of a conversion to the precision of the target
+//! commodity type with
+//! [ExchangeRate::convert_rounded()](ExchangeRate::convert_rounded).
+
+use super::{ExchangeRate, ExchangeRateError};
+use crate::{Commodity, CommodityType, CommodityTypeID};
+use rust_decimal::RoundingStrategy;
+use thiserror::Error;
+
+/// An error associated with
+/// [ExchangeRate::convert_rounded()](ExchangeRate::convert_rounded).
+#[derive(Error, Debug, PartialEq)]
+pub enum RoundingError {
+    #[error(
+        "The commodity type {0} has no minor units, a precision needs to be supplied to round to."
+    )]
+    UnknownPrecision(CommodityTypeID),
+    #[error("There was an error converting the commodity with the exchange rate.")]
+    ExchangeRate(#[from] ExchangeRateError),
+}
+
+/// How to round the result of a conversion, see
+/// [ExchangeRate::convert_rounded()](ExchangeRate::convert_rounded).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConversionRounding {
+    /// The rounding strategy.
+    pub strategy: RoundingStrategy,
+    /// The number of decimal places to round to, or `None` to use the
+    /// `minor_units` of the target commodity type.
+    pub decimal_places: Option<u32>,
+}
+
+impl ConversionRounding {
+    /// Round to the `minor_units` of the target commodity type using
+    /// the specified `strategy`.
+    pub fn new(strategy: RoundingStrategy) -> ConversionRounding {
+        ConversionRounding {
+            strategy,
+            decimal_places: None,
+        }
+    }
+
+    /// Round to the specified number of `decimal_places`, instead of
+    /// the `minor_units` of the target commodity type.
+    pub fn with_decimal_places(mut self, decimal_places: u32) -> Self {
+        self.decimal_places = Some(decimal_places);
+        self
+    }
+}
+
+impl Default for ConversionRounding {
+    /// Round to the `minor_units` of the target commodity type, rounding
+    /// half way values to the nearest even number (banker's rounding).
+    fn default() -> Self {
+        ConversionRounding::new(RoundingStrategy::MidpointNearestEven)
+    }
+}
+
+/// The result of
+/// [ExchangeRate::convert_rounded()](ExchangeRate::convert_rounded).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundedConversion {
+    /// The converted value, rounded.
+    pub value: Commodity,
+    /// The converted value, before it was rounded.
+    pub unrounded: Commodity,
+    /// The amount removed by rounding (`unrounded - value`), which can
+    /// be negative if the value was rounded up. `value + residual`
+    /// always equals `unrounded`.
+    pub residual: Commodity,
+}
+
+impl ExchangeRate {
+    /// Convert the [CommodityType](CommodityType) of a
+    /// [Commodity](Commodity) to the `target` commodity type using this
+    /// [ExchangeRate](ExchangeRate), and round the result according to
+    /// `rounding`, returning the rounded value along with the rounding
+    /// residual.
+    ///
+    /// Returns
+    /// [RoundingError::UnknownPrecision](RoundingError::UnknownPrecision)
+    /// if no `decimal_places` were supplied and the `target` commodity
+    /// type has no `minor_units`.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::exchange_rate::{ConversionRounding, ExchangeRate};
+    /// # use commodity::{Commodity, CommodityType, CommodityTypeID};
+    /// use rust_decimal::{Decimal, RoundingStrategy};
+    /// use std::collections::BTreeMap;
+    /// use std::str::FromStr;
+    ///
+    /// let usd = CommodityTypeID::from_str("USD").unwrap();
+    /// let jpy = CommodityType::from_str("JPY", "Japanese yen")
+    ///     .unwrap()
+    ///     .with_minor_units(0);
+    /// let mut rates = BTreeMap::new();
+    /// rates.insert(jpy.id, Decimal::from_str("109.815").unwrap());
+    /// let exchange_rate = ExchangeRate {
+    ///     date: None,
+    ///     obtained_datetime: None,
+    ///     base: Some(usd),
+    ///     rates,
+    /// };
+    ///
+    /// let conversion = exchange_rate
+    ///     .convert_rounded(
+    ///         Commodity::from_str("10.25 USD").unwrap(),
+    ///         &jpy,
+    ///         &ConversionRounding::default(),
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(Commodity::from_str("1126 JPY").unwrap(), conversion.value);
+    /// assert_eq!(Commodity::from_str("1125.60375 JPY").unwrap(), conversion.unrounded);
+    /// assert_eq!(Commodity::from_str("-0.39625 JPY").unwrap(), conversion.residual);
+    /// ```
+    pub fn convert_rounded(
+        &self,
+        commodity: Commodity,
+        target: &CommodityType,
+        rounding: &ConversionRounding,
+    ) -> Result<RoundedConversion, RoundingError> {
+        let decimal_places = rounding
+            .decimal_places
+            .or(target.minor_units)
+            .ok_or(RoundingError::UnknownPrecision(target.id))?;
+
+        let unrounded = self.convert(commodity, target.id)?;
+        let value = Commodity::new(
+            unrounded
+                .value
+                .round_dp_with_strategy(decimal_places, rounding.strategy),
+            target.id,
+        );
+        let residual = Commodity::new(unrounded.value - value.value, target.id);
+
+        Ok(RoundedConversion {
+            value,
+            unrounded,
+            residual,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConversionRounding, RoundingError};
+    use crate::exchange_rate::ExchangeRate;
+    use crate::{Commodity, CommodityType, CommodityTypeID};
+    use rust_decimal::{Decimal, RoundingStrategy};
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn convert_rounded() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityType::from_str("AUD", "Australian dollar")
+            .unwrap()
+            .with_minor_units(2);
+        let mut rates = BTreeMap::new();
+        rates.insert(aud.id, Decimal::from_str("1.4923").unwrap());
+        let exchange_rate = ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(usd),
+            rates,
+        };
+
+        // converting to the base produces many significant digits
+        let usd_type = CommodityType::from_str("USD", "United States dollar")
+            .unwrap()
+            .with_minor_units(2);
+        let aud_15_54 = Commodity::from_str("15.54 AUD").unwrap();
+        let conversion = exchange_rate
+            .convert_rounded(aud_15_54, &usd_type, &ConversionRounding::default())
+            .unwrap();
+        assert_eq!(Commodity::from_str("10.41 USD").unwrap(), conversion.value);
+        assert_eq!(
+            exchange_rate.convert(aud_15_54, usd).unwrap(),
+            conversion.unrounded
+        );
+        assert_eq!(
+            conversion.unrounded,
+            conversion.value.add(&conversion.residual).unwrap()
+        );
+
+        let conversion = exchange_rate
+            .convert_rounded(
+                aud_15_54,
+                &usd_type,
+                &ConversionRounding::new(RoundingStrategy::AwayFromZero).with_decimal_places(4),
+            )
+            .unwrap();
+        assert_eq!(
+            Commodity::from_str("10.4135 USD").unwrap(),
+            conversion.value
+        );
+        assert!(conversion.residual.value.is_sign_negative());
+
+        // the rounding strategy is used for half way values (0.0074615 AUD)
+        let usd_0_005 = Commodity::from_str("0.005 USD").unwrap();
+        let rounding =
+            ConversionRounding::new(RoundingStrategy::MidpointNearestEven).with_decimal_places(6);
+        assert_eq!(
+            Commodity::from_str("0.007462 AUD").unwrap(),
+            exchange_rate
+                .convert_rounded(usd_0_005, &aud, &rounding)
+                .unwrap()
+                .value
+        );
+        let rounding =
+            ConversionRounding::new(RoundingStrategy::MidpointTowardZero).with_decimal_places(6);
+        assert_eq!(
+            Commodity::from_str("0.007461 AUD").unwrap(),
+            exchange_rate
+                .convert_rounded(usd_0_005, &aud, &rounding)
+                .unwrap()
+                .value
+        );
+
+        // without minor units a precision is required
+        let usd_no_minor_units = CommodityType::from_str("USD", "United States dollar").unwrap();
+        assert_eq!(
+            RoundingError::UnknownPrecision(usd),
+            exchange_rate
+                .convert_rounded(
+                    aud_15_54,
+                    &usd_no_minor_units,
+                    &ConversionRounding::default()
+                )
+                .unwrap_err()
+        );
+    }
+}
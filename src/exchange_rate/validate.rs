@@ -0,0 +1,392 @@
+//! Sanity checks for [ExchangeRate](ExchangeRate)s with
+//! [ExchangeRate::validate()](ExchangeRate::validate), and finding
+//! arbitrage opportunities between exchange rates with the
+//! [ArbitrageChecker](ArbitrageChecker).
+
+use super::{ExchangeRate, ExchangeRateError, QuotedExchangeRate};
+use crate::CommodityTypeID;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// A problem with an [ExchangeRate](ExchangeRate), found using
+/// [ExchangeRate::validate()](ExchangeRate::validate).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    /// The rate for the commodity type is zero or negative.
+    NonPositiveRate(CommodityTypeID, Decimal),
+    /// The `base` commodity type also appears in the `rates`, with a
+    /// rate other than `1`.
+    BaseSelfRate(CommodityTypeID, Decimal),
+    /// The exchange rate does not have a `base`.
+    MissingBase,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::NonPositiveRate(id, rate) => {
+                write!(f, "The rate {} for {} is not positive.", rate, id)
+            }
+            ValidationIssue::BaseSelfRate(id, rate) => write!(
+                f,
+                "The base {} has a rate of {} relative to itself, instead of 1.",
+                id, rate
+            ),
+            ValidationIssue::MissingBase => write!(f, "The exchange rate has no base."),
+        }
+    }
+}
+
+impl ExchangeRate {
+    /// Check this exchange rate for problems, such as zero or negative
+    /// rates. Returns an empty list if no problems were found.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::exchange_rate::{ExchangeRate, ValidationIssue};
+    /// # use commodity::CommodityTypeID;
+    /// use rust_decimal::Decimal;
+    /// use std::collections::BTreeMap;
+    /// use std::str::FromStr;
+    ///
+    /// let aud = CommodityTypeID::from_str("AUD").unwrap();
+    /// let mut rates = BTreeMap::new();
+    /// rates.insert(aud, Decimal::ZERO);
+    /// let exchange_rate = ExchangeRate {
+    ///     date: None,
+    ///     obtained_datetime: None,
+    ///     base: None,
+    ///     rates,
+    /// };
+    ///
+    /// assert_eq!(
+    ///     vec![ValidationIssue::MissingBase, ValidationIssue::NonPositiveRate(aud, Decimal::ZERO)],
+    ///     exchange_rate.validate()
+    /// );
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        match self.base {
+            Some(base) => {
+                if let Some(rate) = self.get_rate(&base) {
+                    if *rate != Decimal::new(1, 0) {
+                        issues.push(ValidationIssue::BaseSelfRate(base, *rate));
+                    }
+                }
+            }
+            None => issues.push(ValidationIssue::MissingBase),
+        }
+
+        for (id, rate) in &self.rates {
+            if *rate <= Decimal::ZERO {
+                issues.push(ValidationIssue::NonPositiveRate(*id, *rate));
+            }
+        }
+
+        issues
+    }
+}
+
+/// A cycle of conversions which ends with more than it started with,
+/// found using [ArbitrageChecker](ArbitrageChecker).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArbitrageCycle {
+    /// The commodity types in the cycle, in order. The cycle converts
+    /// from the last commodity type back to the first.
+    pub commodity_types: Vec<CommodityTypeID>,
+    /// The rate used for each conversion in the cycle.
+    pub rates: Vec<Decimal>,
+    /// The index of the source (in the order they were added to the
+    /// [ArbitrageChecker](ArbitrageChecker)) of each rate.
+    pub sources: Vec<usize>,
+    /// The product of the `rates`, the amount obtained after
+    /// converting one unit around the cycle.
+    pub product: Decimal,
+}
+
+/// Checks the consistency of the rates from several
+/// [ExchangeRate](ExchangeRate)s or
+/// [QuotedExchangeRate](QuotedExchangeRate)s, finding pairs and
+/// triangles of conversions which would result in an arbitrage profit.
+///
+/// # Example
+/// ```
+/// # use commodity::exchange_rate::{ArbitrageChecker, ExchangeRate};
+/// # use commodity::CommodityTypeID;
+/// use rust_decimal::Decimal;
+/// use std::collections::BTreeMap;
+/// use std::str::FromStr;
+///
+/// let usd = CommodityTypeID::from_str("USD").unwrap();
+/// let aud = CommodityTypeID::from_str("AUD").unwrap();
+/// let exchange_rate = |base, rate| {
+///     let mut rates = BTreeMap::new();
+///     rates.insert(aud, Decimal::from_str(rate).unwrap());
+///     ExchangeRate { date: None, obtained_datetime: None, base: Some(base), rates }
+/// };
+///
+/// let mut checker = ArbitrageChecker::new();
+/// checker.add_exchange_rate(&exchange_rate(usd, "1.5")).unwrap();
+/// checker.add_exchange_rate(&exchange_rate(usd, "1.6")).unwrap();
+///
+/// // sell AUD for USD at 1.5 AUD/USD, and buy them back at 1.6 AUD/USD
+/// let cycles = checker.find_cycles(Decimal::new(1, 3)).unwrap();
+/// assert_eq!(1, cycles.len());
+/// assert_eq!(vec![aud, usd], cycles[0].commodity_types);
+/// assert_eq!(vec![0, 1], cycles[0].sources);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ArbitrageChecker {
+    /// The best rate (and its source) for each ordered pair of
+    /// commodity types.
+    rates: BTreeMap<(CommodityTypeID, CommodityTypeID), (Decimal, usize)>,
+    /// The number of sources added.
+    sources: usize,
+}
+
+impl ArbitrageChecker {
+    /// Create a new empty [ArbitrageChecker](ArbitrageChecker).
+    pub fn new() -> ArbitrageChecker {
+        ArbitrageChecker::default()
+    }
+
+    fn add_rate(&mut self, from: CommodityTypeID, to: CommodityTypeID, rate: Decimal) {
+        let source = self.sources;
+        let entry = self.rates.entry((from, to)).or_insert((rate, source));
+        if rate > entry.0 {
+            *entry = (rate, source);
+        }
+    }
+
+    /// Add the rates between every pair of commodity types in an
+    /// [ExchangeRate](ExchangeRate).
+    pub fn add_exchange_rate(
+        &mut self,
+        exchange_rate: &ExchangeRate,
+    ) -> Result<(), ExchangeRateError> {
+        let ids: BTreeSet<CommodityTypeID> = exchange_rate
+            .base
+            .iter()
+            .chain(exchange_rate.rates.keys())
+            .cloned()
+            .collect();
+
+        for from in &ids {
+            for to in ids.iter().filter(|to| *to != from) {
+                if let Some(rate) = exchange_rate.rate_between(from, to)? {
+                    self.add_rate(*from, *to, rate);
+                }
+            }
+        }
+
+        self.sources += 1;
+        Ok(())
+    }
+
+    /// Add the rates between every pair of commodity types in a
+    /// [QuotedExchangeRate](QuotedExchangeRate), using the `bid` (the
+    /// rate actually received when selling).
+    pub fn add_quoted_exchange_rate(
+        &mut self,
+        exchange_rate: &QuotedExchangeRate,
+    ) -> Result<(), ExchangeRateError> {
+        let ids: BTreeSet<CommodityTypeID> = exchange_rate
+            .base
+            .iter()
+            .chain(exchange_rate.quotes.keys())
+            .cloned()
+            .collect();
+
+        for from in &ids {
+            for to in ids.iter().filter(|to| *to != from) {
+                if let Some(quote) = exchange_rate.quote_between(from, to)? {
+                    self.add_rate(*from, *to, quote.bid());
+                }
+            }
+        }
+
+        self.sources += 1;
+        Ok(())
+    }
+
+    fn cycle(
+        &self,
+        commodity_types: &[CommodityTypeID],
+    ) -> Result<Option<ArbitrageCycle>, ExchangeRateError> {
+        let mut rates = Vec::new();
+        let mut sources = Vec::new();
+        let mut product = Decimal::new(1, 0);
+
+        for (i, from) in commodity_types.iter().enumerate() {
+            let to = commodity_types[(i + 1) % commodity_types.len()];
+            let (rate, source) = match self.rates.get(&(*from, to)) {
+                Some(rate) => *rate,
+                None => return Ok(None),
+            };
+            product = product
+                .checked_mul(rate)
+                .ok_or(ExchangeRateError::MultiplyOverflow(product, rate))?;
+            rates.push(rate);
+            sources.push(source);
+        }
+
+        Ok(Some(ArbitrageCycle {
+            commodity_types: commodity_types.to_vec(),
+            rates,
+            sources,
+            product,
+        }))
+    }
+
+    /// Find the cycles of two or three conversions (using the best
+    /// available rate for each conversion) where converting one unit
+    /// around the cycle results in more than `1 + tolerance` units.
+    pub fn find_cycles(
+        &self,
+        tolerance: Decimal,
+    ) -> Result<Vec<ArbitrageCycle>, ExchangeRateError> {
+        let threshold = Decimal::ONE
+            .checked_add(tolerance)
+            .ok_or(ExchangeRateError::AddOverflow(Decimal::ONE, tolerance))?;
+        let ids: BTreeSet<CommodityTypeID> = self.rates.keys().map(|(from, _)| *from).collect();
+        let mut cycles = Vec::new();
+
+        // each cycle starts with its smallest commodity type, so that
+        // it is only reported once.
+        for a in &ids {
+            for b in ids.range(a..).skip(1) {
+                if let Some(cycle) = self.cycle(&[*a, *b])? {
+                    cycles.push(cycle);
+                }
+
+                for c in ids.range(a..).skip(1).filter(|c| *c != b) {
+                    if let Some(cycle) = self.cycle(&[*a, *b, *c])? {
+                        cycles.push(cycle);
+                    }
+                }
+            }
+        }
+
+        cycles.retain(|cycle| cycle.product > threshold);
+        Ok(cycles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArbitrageChecker, ValidationIssue};
+    use crate::exchange_rate::{ExchangeRate, ExchangeRateError, Quote, QuotedExchangeRate};
+    use crate::CommodityTypeID;
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    fn id(id: &str) -> CommodityTypeID {
+        CommodityTypeID::from_str(id).unwrap()
+    }
+
+    fn exchange_rate(base: &str, rates: &[(&str, &str)]) -> ExchangeRate {
+        ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(id(base)),
+            rates: rates
+                .iter()
+                .map(|(type_id, rate)| (id(type_id), Decimal::from_str(rate).unwrap()))
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn validate() {
+        assert!(exchange_rate("USD", &[("AUD", "1.5"), ("USD", "1")])
+            .validate()
+            .is_empty());
+
+        assert_eq!(
+            vec![
+                ValidationIssue::BaseSelfRate(id("USD"), Decimal::new(2, 0)),
+                ValidationIssue::NonPositiveRate(id("AUD"), Decimal::new(-15, 1)),
+            ],
+            exchange_rate("USD", &[("AUD", "-1.5"), ("USD", "2")]).validate()
+        );
+    }
+
+    /// Consistent rates from a single table don't produce arbitrage
+    /// cycles, but a triangle across several tables does.
+    #[test]
+    fn triangular_arbitrage() {
+        let tolerance = Decimal::new(1, 6);
+        let mut checker = ArbitrageChecker::new();
+        checker
+            .add_exchange_rate(&exchange_rate(
+                "USD",
+                &[("AUD", "1.5"), ("EUR", "0.9"), ("JPY", "110")],
+            ))
+            .unwrap();
+        assert!(checker.find_cycles(tolerance).unwrap().is_empty());
+
+        // 1 EUR = 1.7 AUD instead of 1.6667 AUD
+        checker
+            .add_exchange_rate(&exchange_rate("EUR", &[("AUD", "1.7")]))
+            .unwrap();
+        let cycles = checker.find_cycles(tolerance).unwrap();
+
+        let expected: Vec<Vec<CommodityTypeID>> = vec![
+            vec![id("AUD"), id("EUR")],
+            vec![id("AUD"), id("JPY"), id("EUR")],
+            vec![id("AUD"), id("USD"), id("EUR")],
+        ];
+        assert_eq!(
+            expected,
+            cycles
+                .iter()
+                .map(|cycle| cycle.commodity_types.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec![0, 0, 1], cycles[2].sources);
+        assert_eq!(
+            Decimal::from_str("1.02").unwrap(),
+            cycles[2].product.round_dp(10)
+        );
+
+        // a large enough tolerance ignores the difference
+        assert!(checker.find_cycles(Decimal::new(3, 2)).unwrap().is_empty());
+        assert_eq!(
+            ExchangeRateError::AddOverflow(Decimal::ONE, Decimal::MAX),
+            checker.find_cycles(Decimal::MAX).unwrap_err()
+        );
+    }
+
+    /// The spread between the bid and the ask prevents arbitrage.
+    #[test]
+    fn quotes_arbitrage() {
+        let mut quotes = BTreeMap::new();
+        quotes.insert(
+            id("AUD"),
+            Quote::new(Decimal::new(15, 1), Decimal::new(16, 1)).unwrap(),
+        );
+        let quoted = QuotedExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(id("USD")),
+            quotes,
+        };
+
+        let mut checker = ArbitrageChecker::new();
+        checker.add_quoted_exchange_rate(&quoted).unwrap();
+        checker
+            .add_exchange_rate(&exchange_rate("USD", &[("AUD", "1.55")]))
+            .unwrap();
+        assert!(checker.find_cycles(Decimal::ZERO).unwrap().is_empty());
+
+        checker
+            .add_exchange_rate(&exchange_rate("USD", &[("AUD", "1.4")]))
+            .unwrap();
+        let cycles = checker.find_cycles(Decimal::ZERO).unwrap();
+        assert_eq!(1, cycles.len());
+        assert_eq!(vec![2, 1], cycles[0].sources);
+    }
+}
@@ -0,0 +1,380 @@
+//! Historic (withdrawn) currencies, the irrevocably fixed conversion
+//! rates of the legacy euro currencies, and redenominations between
+//! old and new units of the same currency.
+
+use crate::exchange_rate::ExchangeRate;
+use crate::{Commodity, CommodityKind, CommodityType, CommodityTypeID};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An error associated with functionality in the [historic](crate::historic) module.
+#[derive(Error, Debug, PartialEq)]
+pub enum HistoricError {
+    #[error("There is no known redenomination between {0} and {1}.")]
+    NoRedenomination(CommodityTypeID, CommodityTypeID),
+    #[error("There was a divide overflow while redenominating, performing the division {0}/{1}.")]
+    DivideOverflow(Decimal, Decimal),
+    #[error("There was a multiply overflow while redenominating, performing the multiplication {0}*{1}.")]
+    MultiplyOverflow(Decimal, Decimal),
+}
+
+/// A date as `(year, month, day)`.
+type Ymd = (i32, u32, u32);
+
+/// `(alpha3, numeric code, minor units, name, valid from, valid until)`
+type HistoricCurrencyData = (&'static str, u16, u32, &'static str, Option<Ymd>, Ymd);
+
+/// Withdrawn `ISO4217` currencies. For the legacy euro currencies,
+/// the end of validity is the end of the month before the code was
+/// withdrawn from `ISO4217`. For redenominated currencies it is the
+/// day before the new units were introduced.
+#[rustfmt::skip]
+const HISTORIC_CURRENCIES: &[HistoricCurrencyData] = &[
+    ("ATS", 40, 2, "Austrian schilling", None, (2002, 2, 28)),
+    ("AZM", 31, 2, "Azerbaijani manat (1992–2006)", None, (2005, 12, 31)),
+    ("BEF", 56, 2, "Belgian franc", None, (2002, 2, 28)),
+    ("BYR", 974, 0, "Belarusian ruble (2000–2016)", None, (2016, 6, 30)),
+    ("CYP", 196, 2, "Cypriot pound", None, (2007, 12, 31)),
+    ("DEM", 276, 2, "Deutsche Mark", None, (2002, 2, 28)),
+    ("EEK", 233, 2, "Estonian kroon", None, (2010, 12, 31)),
+    ("ESP", 724, 0, "Spanish peseta", None, (2002, 2, 28)),
+    ("FIM", 246, 2, "Finnish markka", None, (2002, 2, 28)),
+    ("FRF", 250, 2, "French franc", None, (2002, 2, 28)),
+    ("GHC", 288, 2, "Ghanaian cedi (1967–2007)", None, (2007, 6, 30)),
+    ("GRD", 300, 2, "Greek drachma", None, (2002, 2, 28)),
+    ("HRK", 191, 2, "Croatian kuna", None, (2022, 12, 31)),
+    ("IEP", 372, 2, "Irish pound", None, (2002, 2, 28)),
+    ("ITL", 380, 0, "Italian lira", None, (2002, 2, 28)),
+    ("LTL", 440, 2, "Lithuanian litas", None, (2014, 12, 31)),
+    ("LUF", 442, 2, "Luxembourg franc", None, (2002, 2, 28)),
+    ("LVL", 428, 2, "Latvian lats", None, (2013, 12, 31)),
+    ("MRO", 478, 2, "Mauritanian ouguiya (1973–2017)", None, (2017, 12, 31)),
+    ("MTL", 470, 2, "Maltese lira", None, (2007, 12, 31)),
+    ("MZM", 508, 2, "Mozambican metical (1980–2006)", None, (2006, 6, 30)),
+    ("NLG", 528, 2, "Dutch guilder", None, (2002, 2, 28)),
+    ("PLZ", 616, 2, "Polish złoty (1950–1994)", None, (1994, 12, 31)),
+    ("PTE", 620, 0, "Portuguese escudo", None, (2002, 2, 28)),
+    ("ROL", 642, 2, "Romanian leu (1952–2005)", None, (2005, 6, 30)),
+    ("RUR", 810, 2, "Russian ruble (1992–1997)", None, (1997, 12, 31)),
+    ("SIT", 705, 2, "Slovenian tolar", None, (2006, 12, 31)),
+    ("SKK", 703, 2, "Slovak koruna", None, (2008, 12, 31)),
+    ("STD", 678, 2, "São Tomé and Príncipe dobra (1977–2017)", None, (2017, 12, 31)),
+    ("TMM", 795, 2, "Turkmenistan manat (1993–2008)", None, (2008, 12, 31)),
+    ("TRL", 792, 0, "Turkish lira (1922–2004)", None, (2004, 12, 31)),
+    ("VEB", 862, 2, "Venezuelan bolívar (1879–2007)", None, (2007, 12, 31)),
+    ("VEF", 937, 2, "Venezuelan bolívar fuerte (2008–2018)", Some((2008, 1, 1)), (2018, 8, 19)),
+];
+
+/// `(alpha3, fixed rate of the currency per euro, date the rate came into effect)`
+const EURO_LEGACY_RATES: &[(&str, &str, Ymd)] = &[
+    ("ATS", "13.7603", (1999, 1, 1)),
+    ("BEF", "40.3399", (1999, 1, 1)),
+    ("CYP", "0.585274", (2008, 1, 1)),
+    ("DEM", "1.95583", (1999, 1, 1)),
+    ("EEK", "15.6466", (2011, 1, 1)),
+    ("ESP", "166.386", (1999, 1, 1)),
+    ("FIM", "5.94573", (1999, 1, 1)),
+    ("FRF", "6.55957", (1999, 1, 1)),
+    ("GRD", "340.750", (2001, 1, 1)),
+    ("HRK", "7.53450", (2023, 1, 1)),
+    ("IEP", "0.787564", (1999, 1, 1)),
+    ("ITL", "1936.27", (1999, 1, 1)),
+    ("LTL", "3.45280", (2015, 1, 1)),
+    ("LUF", "40.3399", (1999, 1, 1)),
+    ("LVL", "0.702804", (2014, 1, 1)),
+    ("MTL", "0.429300", (2008, 1, 1)),
+    ("NLG", "2.20371", (1999, 1, 1)),
+    ("PTE", "200.482", (1999, 1, 1)),
+    ("SIT", "239.640", (2007, 1, 1)),
+    ("SKK", "30.1260", (2009, 1, 1)),
+];
+
+/// `(old alpha3, new alpha3, old units per new unit, date the new units were introduced)`
+const REDENOMINATIONS: &[(&str, &str, i64, Ymd)] = &[
+    ("AZM", "AZN", 5_000, (2006, 1, 1)),
+    ("BYR", "BYN", 10_000, (2016, 7, 1)),
+    ("GHC", "GHS", 10_000, (2007, 7, 1)),
+    ("MRO", "MRU", 10, (2018, 1, 1)),
+    ("MZM", "MZN", 1_000, (2006, 7, 1)),
+    ("PLZ", "PLN", 10_000, (1995, 1, 1)),
+    ("ROL", "RON", 10_000, (2005, 7, 1)),
+    ("RUR", "RUB", 1_000, (1998, 1, 1)),
+    ("STD", "STN", 1_000, (2018, 1, 1)),
+    ("TMM", "TMT", 5_000, (2009, 1, 1)),
+    ("TRL", "TRY", 1_000_000, (2005, 1, 1)),
+    ("VEB", "VEF", 1_000, (2008, 1, 1)),
+    ("VEF", "VES", 100_000, (2018, 8, 20)),
+];
+
+fn ymd((year, month, day): Ymd) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).expect("historic dates should be valid")
+}
+
+fn type_id(alpha3: &str) -> CommodityTypeID {
+    CommodityTypeID::from_str(alpha3).expect("historic alpha3 codes should be valid ids")
+}
+
+fn historic_commodity_type(data: &HistoricCurrencyData) -> CommodityType {
+    let (alpha3, numeric_code, minor_units, name, valid_from, valid_until) = *data;
+    let mut commodity_type = CommodityType::new(type_id(alpha3), Some(String::from(name)))
+        .with_minor_units(minor_units)
+        .with_kind(CommodityKind::Fiat)
+        .with_validity(valid_from.map(ymd), Some(ymd(valid_until)));
+    commodity_type.numeric_code = Some(numeric_code);
+    commodity_type
+}
+
+/// Return a vector of all the withdrawn `ISO4217` currencies known to
+/// this library, with their `valid_until` dates set.
+pub fn historic_currencies() -> Vec<CommodityType> {
+    HISTORIC_CURRENCIES
+        .iter()
+        .map(historic_commodity_type)
+        .collect()
+}
+
+/// Look up a withdrawn `ISO4217` currency by its alpha3 code.
+///
+/// # Example
+/// ```
+/// # use commodity::historic::historic_currency;
+/// use chrono::NaiveDate;
+///
+/// let dem = historic_currency("DEM").unwrap();
+/// assert_eq!(Some(String::from("Deutsche Mark")), dem.name);
+/// assert_eq!(Some(276), dem.numeric_code);
+/// assert_eq!(NaiveDate::from_ymd_opt(2002, 2, 28), dem.valid_until);
+/// ```
+pub fn historic_currency<S: AsRef<str>>(alpha3: S) -> Option<CommodityType> {
+    HISTORIC_CURRENCIES
+        .iter()
+        .find(|data| data.0 == alpha3.as_ref())
+        .map(historic_commodity_type)
+}
+
+/// The irrevocably fixed conversion rates between the euro and the
+/// legacy currencies it replaced, as an [ExchangeRate](ExchangeRate)
+/// with `EUR` as the base.
+///
+/// # Example
+/// ```
+/// # use commodity::historic::euro_legacy_rates;
+/// use commodity::{Commodity, CommodityTypeID};
+/// use std::str::FromStr;
+///
+/// let rates = euro_legacy_rates();
+/// let dem = Commodity::from_str("1955.83 DEM").unwrap();
+/// let frf = CommodityTypeID::from_str("FRF").unwrap();
+///
+/// let converted = rates.convert(dem, frf).unwrap();
+/// assert_eq!(Commodity::from_str("6559.57 FRF").unwrap(), converted);
+/// ```
+pub fn euro_legacy_rates() -> ExchangeRate {
+    ExchangeRate {
+        date: None,
+        obtained_datetime: None,
+        base: Some(type_id("EUR")),
+        rates: EURO_LEGACY_RATES
+            .iter()
+            .map(|(alpha3, rate, _)| {
+                (
+                    type_id(alpha3),
+                    Decimal::from_str(rate).expect("euro legacy rates should be valid decimals"),
+                )
+            })
+            .collect(),
+    }
+}
+
+/// The date on which the fixed conversion rate between the euro and
+/// the specified legacy currency came into effect.
+pub fn euro_adoption_date(legacy_currency: &CommodityTypeID) -> Option<NaiveDate> {
+    EURO_LEGACY_RATES
+        .iter()
+        .find(|(alpha3, _, _)| type_id(alpha3) == *legacy_currency)
+        .map(|(_, _, date)| ymd(*date))
+}
+
+/// A change in the units of a currency, where `factor` units of the
+/// `old` currency are equal to one unit of the `new` currency.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Redenomination {
+    /// The id of the currency in the old units.
+    pub old: CommodityTypeID,
+    /// The id of the currency in the new units.
+    pub new: CommodityTypeID,
+    /// The number of `old` units equal to one `new` unit.
+    pub factor: Decimal,
+    /// The date that the `new` units were introduced.
+    pub date: NaiveDate,
+}
+
+impl Redenomination {
+    /// Convert a [Commodity](Commodity) in either the `old` or the
+    /// `new` units to the other units.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::historic::find_redenomination;
+    /// use commodity::{Commodity, CommodityTypeID};
+    /// use std::str::FromStr;
+    ///
+    /// let trl = CommodityTypeID::from_str("TRL").unwrap();
+    /// let try_id = CommodityTypeID::from_str("TRY").unwrap();
+    /// let redenomination = find_redenomination(&trl, &try_id).unwrap();
+    ///
+    /// let old = Commodity::from_str("2500000 TRL").unwrap();
+    /// let new = redenomination.convert(old).unwrap();
+    /// assert_eq!(Commodity::from_str("2.5 TRY").unwrap(), new);
+    /// assert_eq!(old, redenomination.convert(new).unwrap());
+    /// ```
+    pub fn convert(&self, commodity: Commodity) -> Result<Commodity, HistoricError> {
+        if commodity.type_id == self.old {
+            let value = commodity
+                .value
+                .checked_div(self.factor)
+                .ok_or(HistoricError::DivideOverflow(commodity.value, self.factor))?;
+            Ok(Commodity::new(value.normalize(), self.new))
+        } else if commodity.type_id == self.new {
+            let value =
+                commodity
+                    .value
+                    .checked_mul(self.factor)
+                    .ok_or(HistoricError::MultiplyOverflow(
+                        commodity.value,
+                        self.factor,
+                    ))?;
+            Ok(Commodity::new(value.normalize(), self.old))
+        } else {
+            Err(HistoricError::NoRedenomination(commodity.type_id, self.new))
+        }
+    }
+}
+
+/// Return a vector of all the currency redenominations known to this
+/// library.
+pub fn redenominations() -> Vec<Redenomination> {
+    REDENOMINATIONS
+        .iter()
+        .map(|(old, new, factor, date)| Redenomination {
+            old: type_id(old),
+            new: type_id(new),
+            factor: Decimal::from(*factor),
+            date: ymd(*date),
+        })
+        .collect()
+}
+
+/// Find the redenomination between the currencies `a` and `b` (in
+/// either direction).
+pub fn find_redenomination(a: &CommodityTypeID, b: &CommodityTypeID) -> Option<Redenomination> {
+    redenominations().into_iter().find(|redenomination| {
+        (redenomination.old == *a && redenomination.new == *b)
+            || (redenomination.old == *b && redenomination.new == *a)
+    })
+}
+
+/// Convert a [Commodity](Commodity) between the old and new units of
+/// a redenominated currency at the official factor.
+///
+/// # Example
+/// ```
+/// # use commodity::historic::redenominate;
+/// use commodity::{Commodity, CommodityTypeID};
+/// use std::str::FromStr;
+///
+/// let rol = Commodity::from_str("15000 ROL").unwrap();
+/// let ron = redenominate(rol, CommodityTypeID::from_str("RON").unwrap()).unwrap();
+/// assert_eq!(Commodity::from_str("1.5 RON").unwrap(), ron);
+/// ```
+pub fn redenominate(
+    commodity: Commodity,
+    target: CommodityTypeID,
+) -> Result<Commodity, HistoricError> {
+    match find_redenomination(&commodity.type_id, &target) {
+        Some(redenomination) => redenomination.convert(commodity),
+        None => Err(HistoricError::NoRedenomination(commodity.type_id, target)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        euro_adoption_date, euro_legacy_rates, historic_currencies, historic_currency,
+        redenominate, HistoricError,
+    };
+    use crate::{Commodity, CommodityTypeID};
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    #[test]
+    fn euro_legacy_conversion() {
+        let rates = euro_legacy_rates();
+        let eur = CommodityTypeID::from_str("EUR").unwrap();
+        let itl = CommodityTypeID::from_str("ITL").unwrap();
+
+        let converted = rates
+            .convert(Commodity::from_str("100 EUR").unwrap(), itl)
+            .unwrap();
+        assert_eq!(Decimal::from_str("193627").unwrap(), converted.value);
+
+        let converted = rates
+            .convert(Commodity::from_str("6.55957 FRF").unwrap(), eur)
+            .unwrap();
+        assert_eq!(Decimal::from(1), converted.value);
+
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2001, 1, 1),
+            euro_adoption_date(&CommodityTypeID::from_str("GRD").unwrap())
+        );
+
+        // every legacy currency is also a known historic currency.
+        for id in rates.rates.keys() {
+            assert!(historic_currency(id.to_string()).is_some(), "{}", id);
+        }
+    }
+
+    #[test]
+    fn historic_validity() {
+        let sit = historic_currency("SIT").unwrap();
+        assert!(sit.is_valid_on(NaiveDate::from_ymd_opt(2006, 12, 31).unwrap()));
+        assert!(!sit.is_valid_on(NaiveDate::from_ymd_opt(2007, 1, 1).unwrap()));
+
+        let vef = historic_currency("VEF").unwrap();
+        assert!(!vef.is_valid_on(NaiveDate::from_ymd_opt(2007, 12, 31).unwrap()));
+        assert!(vef.is_valid_on(NaiveDate::from_ymd_opt(2010, 1, 1).unwrap()));
+
+        assert!(historic_currencies()
+            .iter()
+            .all(|currency| currency.valid_until.is_some()));
+        assert!(historic_currency("USD").is_none());
+    }
+
+    #[test]
+    fn redenomination() {
+        let try_id = CommodityTypeID::from_str("TRY").unwrap();
+        let trl = CommodityTypeID::from_str("TRL").unwrap();
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+
+        let new = redenominate(Commodity::from_str("1234567 TRL").unwrap(), try_id).unwrap();
+        assert_eq!(Commodity::from_str("1.234567 TRY").unwrap(), new);
+        assert_eq!(
+            Commodity::from_str("1234567 TRL").unwrap(),
+            redenominate(new, trl).unwrap()
+        );
+
+        assert_eq!(
+            HistoricError::NoRedenomination(trl, usd),
+            redenominate(Commodity::from_str("1 TRL").unwrap(), usd).unwrap_err()
+        );
+
+        let huge = Commodity::new(Decimal::MAX, try_id);
+        match redenominate(huge, trl) {
+            Err(HistoricError::MultiplyOverflow(_, _)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
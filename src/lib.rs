@@ -7,6 +7,38 @@
 //! + `serde-support`
 //!   + Disabled by default
 //!   + Enables support for serialization/de-serialization via `serde`
+//! + `iso4217`
+//!   + Disabled by default
+//!   + Enables looking up currencies in the `ISO4217` database
+//! + `bigdecimal`
+//!   + Disabled by default
+//!   + Enables the [big](big) module, with high precision versions of
+//!     [Commodity](Commodity) and [ExchangeRate](exchange_rate::ExchangeRate)
+//!     backed by `bigdecimal`
+//! + `json`
+//!   + Disabled by default
+//!   + Enables loading/saving a [CommodityTypeRegistry](registry::CommodityTypeRegistry)
+//!     from/to json files, the [FileProvider](provider::FileProvider), and
+//!     the [json](provider::json) response adapters (implies `serde-support`)
+//! + `toml`
+//!   + Disabled by default
+//!   + Enables loading/saving a [CommodityTypeRegistry](registry::CommodityTypeRegistry)
+//!     from/to toml files (implies `serde-support`)
+//! + `http`
+//!   + Disabled by default
+//!   + Enables the [HttpProvider](provider::HttpProvider) for requesting
+//!     exchange rates from a http or https server (implies `json`)
+//! + `async`
+//!   + Disabled by default
+//!   + Enables the [AsyncRateProvider](provider::AsyncRateProvider) trait
+//! + `ecb`
+//!   + Disabled by default
+//!   + Enables the [ecb](provider::ecb) module, for parsing the European
+//!     Central Bank's reference exchange rates
+//! + `csv`
+//!   + Disabled by default
+//!   + Enables the [csv](exchange_rate::csv) module, for reading and writing
+//!     exchange rates as csv
 //!
 //! # Usage
 //!
@@ -33,10 +65,12 @@
 //! use rust_decimal::Decimal;
 //! use std::str::FromStr;
 //!
-//! // Create a currency from its iso4317 three character code.
+//! // Create a currency from its three character code.
 //! // The currency stores information associated with the currency,
-//! // such as the full name ("United States dollar" for this one).
-//! let usd = CommodityType::from_currency_alpha3("USD").unwrap();
+//! // such as the full name ("United States dollar" for this one). With
+//! // the `iso4217` feature enabled, this can be looked up using
+//! // `CommodityType::from_currency_alpha3("USD")`.
+//! let usd = CommodityType::from_str("USD", "United States dollar").unwrap();
 //!
 //! // Create a commodity with a value of "2.02 USD"
 //! let commodity1 = Commodity::new(Decimal::from_str("2.02").unwrap(), &usd);
@@ -66,12 +100,23 @@ extern crate rust_decimal;
 #[cfg(feature = "serde-support")]
 extern crate serde;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "json"))]
 #[cfg(feature = "serde-support")]
 extern crate serde_json;
 
+#[cfg(feature = "toml")]
+extern crate toml_crate as toml;
+
+#[cfg(feature = "bigdecimal")]
+pub mod big;
 mod commodity;
 pub mod exchange_rate;
+pub mod historic;
+pub mod price;
+pub mod provider;
+pub mod registry;
+pub mod security;
+pub mod unit;
 
 pub use crate::commodity::*;
 
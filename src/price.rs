@@ -0,0 +1,416 @@
+//! The [Price](Price) type, representing an amount of one commodity
+//! type per unit of another, e.g. `150.25 USD/AAPL`.
+
+use crate::exchange_rate::{ExchangeRate, ExchangeRateError};
+use crate::{Commodity, CommodityError, CommodityTypeID};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Div, Mul};
+use std::str::FromStr;
+use thiserror::Error;
+
+#[cfg(feature = "serde-support")]
+use serde::{Deserialize, Serialize};
+
+/// An error associated with functionality in the [price](crate::price) module.
+#[derive(Error, Debug, PartialEq)]
+pub enum PriceError {
+    #[error("The commodity {commodity} is incompatible with the price {price}, expected a commodity of type {expected}.")]
+    IncompatibleCommodityType {
+        price: Price,
+        commodity: Commodity,
+        expected: CommodityTypeID,
+    },
+    #[error("The provided string {0} is invalid, it should be a decimal followed by a quote and per commodity type. e.g. 150.25 USD/AAPL")]
+    InvalidPriceString(String),
+    #[error("There was a multiply overflow while performing the multiplication {0}*{1}.")]
+    MultiplyOverflow(Decimal, Decimal),
+    #[error("There was a divide overflow while performing the division {0}/{1}.")]
+    DivideOverflow(Decimal, Decimal),
+    #[error("The commodity type with id {0} is not present in the exchange rate.")]
+    CommodityTypeNotPresent(CommodityTypeID),
+    #[error("The exchange rate does not represent a single pair of commodity types, it has a base of {0:?} and {1} rates.")]
+    NotASinglePair(Option<CommodityTypeID>, usize),
+    #[error("Error in the exchange rate")]
+    ExchangeRate(#[from] ExchangeRateError),
+    #[error("Error in the commodity")]
+    Commodity(#[from] CommodityError),
+}
+
+/// The price of one unit of the `per`
+/// [CommodityType](crate::CommodityType), in units of the `quote`
+/// [CommodityType](crate::CommodityType). For example the price of a
+/// share of `AAPL` in `USD` is written as `150.25 USD/AAPL`.
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Price {
+    /// The number of `quote` units per one `per` unit.
+    pub value: Decimal,
+    /// The id of the commodity type that the price is expressed in.
+    pub quote: CommodityTypeID,
+    /// The id of the commodity type which is being priced.
+    pub per: CommodityTypeID,
+}
+
+impl Price {
+    /// Create a new [Price](Price).
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::price::Price;
+    /// # use commodity::CommodityTypeID;
+    /// use rust_decimal::Decimal;
+    /// use std::str::FromStr;
+    ///
+    /// let usd = CommodityTypeID::from_str("USD").unwrap();
+    /// let aapl = CommodityTypeID::from_str("AAPL").unwrap();
+    /// let price = Price::new(Decimal::new(15025, 2), usd, aapl);
+    ///
+    /// assert_eq!("150.25 USD/AAPL", price.to_string());
+    /// ```
+    pub fn new<Q: Into<CommodityTypeID>, P: Into<CommodityTypeID>>(
+        value: Decimal,
+        quote: Q,
+        per: P,
+    ) -> Price {
+        Price {
+            value,
+            quote: quote.into(),
+            per: per.into(),
+        }
+    }
+
+    /// Calculate the value (in the `quote` commodity type) of the
+    /// specified `commodity`, which needs to be of the `per` commodity
+    /// type. Also available using the `*` operator.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::price::Price;
+    /// # use commodity::Commodity;
+    /// use std::str::FromStr;
+    ///
+    /// let price = Price::from_str("150.25 USD/AAPL").unwrap();
+    /// let shares = Commodity::from_str("10 AAPL").unwrap();
+    ///
+    /// assert_eq!(Commodity::from_str("1502.50 USD").unwrap(), price.value_of(&shares).unwrap());
+    /// assert_eq!(Commodity::from_str("1502.50 USD").unwrap(), (price * shares).unwrap());
+    /// ```
+    pub fn value_of(&self, commodity: &Commodity) -> Result<Commodity, PriceError> {
+        self.check_commodity_type(commodity, self.per)?;
+
+        let value = commodity
+            .value
+            .checked_mul(self.value)
+            .ok_or(PriceError::MultiplyOverflow(commodity.value, self.value))?;
+
+        Ok(Commodity::new(value, self.quote))
+    }
+
+    /// Calculate the quantity (in the `per` commodity type) which can
+    /// be obtained for the specified `commodity`, which needs to be of
+    /// the `quote` commodity type. Also available using the `/`
+    /// operator.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::price::Price;
+    /// # use commodity::Commodity;
+    /// use std::str::FromStr;
+    ///
+    /// let price = Price::from_str("150.25 USD/AAPL").unwrap();
+    /// let cash = Commodity::from_str("3005 USD").unwrap();
+    ///
+    /// assert_eq!(Commodity::from_str("20 AAPL").unwrap(), price.quantity_for(&cash).unwrap());
+    /// assert_eq!(Commodity::from_str("20 AAPL").unwrap(), (cash / price).unwrap());
+    /// ```
+    pub fn quantity_for(&self, commodity: &Commodity) -> Result<Commodity, PriceError> {
+        self.check_commodity_type(commodity, self.quote)?;
+
+        let value = commodity
+            .value
+            .checked_div(self.value)
+            .ok_or(PriceError::DivideOverflow(commodity.value, self.value))?;
+
+        Ok(Commodity::new(value, self.per))
+    }
+
+    /// The inverse of this price, swapping the `quote` and `per`
+    /// commodity types, e.g. `2 AUD/USD` becomes `0.5 USD/AUD`.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::price::Price;
+    /// use std::str::FromStr;
+    ///
+    /// let price = Price::from_str("2 AUD/USD").unwrap();
+    /// assert_eq!(Price::from_str("0.5 USD/AUD").unwrap(), price.inverse().unwrap());
+    /// ```
+    pub fn inverse(&self) -> Result<Price, PriceError> {
+        let one = Decimal::new(1, 0);
+        let value = one
+            .checked_div(self.value)
+            .ok_or(PriceError::DivideOverflow(one, self.value))?;
+
+        Ok(Price::new(value, self.per, self.quote))
+    }
+
+    /// Obtain the price of the `per` commodity type in the `quote`
+    /// commodity type from an [ExchangeRate](ExchangeRate).
+    pub fn from_exchange_rate(
+        exchange_rate: &ExchangeRate,
+        quote: CommodityTypeID,
+        per: CommodityTypeID,
+    ) -> Result<Price, PriceError> {
+        match exchange_rate.rate_between(&per, &quote)? {
+            Some(value) => Ok(Price::new(value, quote, per)),
+            None => {
+                let missing =
+                    if exchange_rate.get_rate(&per).is_none() && exchange_rate.base != Some(per) {
+                        per
+                    } else {
+                        quote
+                    };
+                Err(PriceError::CommodityTypeNotPresent(missing))
+            }
+        }
+    }
+
+    /// Create an [ExchangeRate](ExchangeRate) for the single pair of
+    /// commodity types in this price, with the `per` commodity type as
+    /// the `base`.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::price::Price;
+    /// # use commodity::Commodity;
+    /// use commodity::CommodityTypeID;
+    /// use std::str::FromStr;
+    ///
+    /// let price = Price::from_str("1.5 AUD/USD").unwrap();
+    /// let exchange_rate = price.to_exchange_rate();
+    ///
+    /// let aud = CommodityTypeID::from_str("AUD").unwrap();
+    /// let usd = Commodity::from_str("10 USD").unwrap();
+    /// assert_eq!(Commodity::from_str("15 AUD").unwrap(), exchange_rate.convert(usd, aud).unwrap());
+    /// ```
+    pub fn to_exchange_rate(&self) -> ExchangeRate {
+        let mut rates = BTreeMap::new();
+        rates.insert(self.quote, self.value);
+
+        ExchangeRate {
+            date: None,
+            obtained_datetime: None,
+            base: Some(self.per),
+            rates,
+        }
+    }
+
+    fn check_commodity_type(
+        &self,
+        commodity: &Commodity,
+        expected: CommodityTypeID,
+    ) -> Result<(), PriceError> {
+        if commodity.type_id != expected {
+            return Err(PriceError::IncompatibleCommodityType {
+                price: *self,
+                commodity: *commodity,
+                expected,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Mul<Commodity> for Price {
+    type Output = Result<Commodity, PriceError>;
+
+    /// See [Price::value_of()](Price::value_of).
+    fn mul(self, commodity: Commodity) -> Result<Commodity, PriceError> {
+        self.value_of(&commodity)
+    }
+}
+
+impl Div<Price> for Commodity {
+    type Output = Result<Commodity, PriceError>;
+
+    /// See [Price::quantity_for()](Price::quantity_for).
+    fn div(self, price: Price) -> Result<Commodity, PriceError> {
+        price.quantity_for(&self)
+    }
+}
+
+impl From<Price> for ExchangeRate {
+    fn from(price: Price) -> ExchangeRate {
+        price.to_exchange_rate()
+    }
+}
+
+impl TryFrom<&ExchangeRate> for Price {
+    type Error = PriceError;
+
+    /// Convert an [ExchangeRate](ExchangeRate) with a `base`, and a
+    /// single rate for a commodity type other than the `base` into a
+    /// [Price](Price) of the `base` commodity type.
+    fn try_from(exchange_rate: &ExchangeRate) -> Result<Price, PriceError> {
+        let not_a_single_pair =
+            || PriceError::NotASinglePair(exchange_rate.base, exchange_rate.rates.len());
+
+        let base = exchange_rate.base.ok_or_else(not_a_single_pair)?;
+        let mut quotes = exchange_rate.rates.keys().filter(|id| **id != base);
+
+        match (quotes.next(), quotes.next()) {
+            (Some(quote), None) => Price::from_exchange_rate(exchange_rate, *quote, base),
+            _ => Err(not_a_single_pair()),
+        }
+    }
+}
+
+impl FromStr for Price {
+    type Err = PriceError;
+
+    /// Parse a [Price](Price) from a string, e.g. `"150.25 USD/AAPL"`.
+    fn from_str(price_string: &str) -> Result<Price, PriceError> {
+        let invalid = || PriceError::InvalidPriceString(String::from(price_string));
+        let elements: Vec<&str> = price_string.split_whitespace().collect();
+
+        if elements.len() != 2 {
+            return Err(invalid());
+        }
+
+        let value = Decimal::from_str(elements[0]).map_err(|_| invalid())?;
+        let mut ids = elements[1].split('/');
+
+        match (ids.next(), ids.next(), ids.next()) {
+            (Some(quote), Some(per), None) => Ok(Price::new(
+                value,
+                CommodityTypeID::from_str(quote)?,
+                CommodityTypeID::from_str(per)?,
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}/{}", self.value, self.quote, self.per)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Price, PriceError};
+    use crate::exchange_rate::ExchangeRate;
+    use crate::{Commodity, CommodityTypeID};
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::convert::TryFrom;
+    use std::str::FromStr;
+
+    /// Parse and display a price.
+    #[test]
+    fn parse_and_display() {
+        let price = Price::from_str("150.25 USD/AAPL").unwrap();
+        assert_eq!(Decimal::new(15025, 2), price.value);
+        assert_eq!("USD", price.quote);
+        assert_eq!("AAPL", price.per);
+        assert_eq!("150.25 USD/AAPL", price.to_string());
+
+        for invalid in &["150.25 USD", "150.25", "USD/AAPL 150.25", "1 USD/AAPL/EUR"] {
+            assert_eq!(
+                PriceError::InvalidPriceString(String::from(*invalid)),
+                Price::from_str(invalid).unwrap_err()
+            );
+        }
+    }
+
+    /// Multiplying and dividing with commodities requires matching commodity types.
+    #[test]
+    fn mul_div_incompatible() {
+        let price = Price::from_str("150.25 USD/AAPL").unwrap();
+        let msft = Commodity::from_str("1 MSFT").unwrap();
+
+        assert_eq!(
+            PriceError::IncompatibleCommodityType {
+                price,
+                commodity: msft,
+                expected: CommodityTypeID::from_str("AAPL").unwrap(),
+            },
+            (price * msft).unwrap_err()
+        );
+
+        // the quote commodity type is required for division
+        let aapl = Commodity::from_str("1 AAPL").unwrap();
+        assert!((aapl / price).is_err());
+
+        let zero = Price::from_str("0 USD/AAPL").unwrap();
+        assert!(zero.inverse().is_err());
+        assert!((Commodity::from_str("1 USD").unwrap() / zero).is_err());
+    }
+
+    /// Round trip between a price and an exchange rate.
+    #[test]
+    fn exchange_rate_round_trip() {
+        let price = Price::from_str("0.65 USD/AUD").unwrap();
+        let exchange_rate = ExchangeRate::from(price);
+        assert_eq!(Some(price.per), exchange_rate.base);
+        assert_eq!(price, Price::try_from(&exchange_rate).unwrap());
+
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let nzd = CommodityTypeID::from_str("NZD").unwrap();
+
+        let mut rates = BTreeMap::new();
+        rates.insert(aud, Decimal::from_str("1.6417").unwrap());
+        rates.insert(nzd, Decimal::from_str("1.7094").unwrap());
+        let exchange_rate = ExchangeRate {
+            date: Some(NaiveDate::from_ymd_opt(2020, 2, 7).unwrap()),
+            obtained_datetime: None,
+            base: Some(usd),
+            rates,
+        };
+
+        assert_eq!(
+            PriceError::NotASinglePair(Some(usd), 2),
+            Price::try_from(&exchange_rate).unwrap_err()
+        );
+
+        let price = Price::from_exchange_rate(&exchange_rate, aud, usd).unwrap();
+        assert_eq!(Price::from_str("1.6417 AUD/USD").unwrap(), price);
+
+        let price = Price::from_exchange_rate(&exchange_rate, usd, aud).unwrap();
+        assert_eq!(
+            price.inverse().unwrap().value.round_dp(4),
+            Decimal::from_str("1.6417").unwrap()
+        );
+
+        let eur = CommodityTypeID::from_str("EUR").unwrap();
+        assert_eq!(
+            PriceError::CommodityTypeNotPresent(eur),
+            Price::from_exchange_rate(&exchange_rate, aud, eur).unwrap_err()
+        );
+        assert_eq!(
+            PriceError::CommodityTypeNotPresent(eur),
+            Price::from_exchange_rate(&exchange_rate, eur, usd).unwrap_err()
+        );
+    }
+
+    #[cfg(feature = "serde-support")]
+    #[test]
+    fn test_json_serialization() {
+        use serde_json;
+
+        let price = Price::from_str("150.25 USD/AAPL").unwrap();
+        let serialized = serde_json::to_string(&price).unwrap();
+        assert_eq!(
+            r#"{"value":"150.25","quote":"USD","per":"AAPL"}"#,
+            serialized
+        );
+        assert_eq!(price, serde_json::from_str(&serialized).unwrap());
+    }
+}
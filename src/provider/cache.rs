@@ -0,0 +1,210 @@
+//! [CachedProvider](CachedProvider), which caches the exchange rates
+//! returned by another provider.
+
+use super::{ProviderError, RateProvider};
+use crate::exchange_rate::ExchangeRate;
+use crate::CommodityTypeID;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use super::AsyncRateProvider;
+#[cfg(feature = "async")]
+use async_trait::async_trait;
+
+/// A provider which caches the [ExchangeRate](ExchangeRate)s returned
+/// by another provider for a time-to-live (TTL), keyed by their date
+/// and base. Errors are not cached.
+#[derive(Debug)]
+pub struct CachedProvider<P> {
+    provider: P,
+    ttl: Duration,
+    cache: Mutex<HashMap<(NaiveDate, CommodityTypeID), (Instant, ExchangeRate)>>,
+}
+
+impl<P> CachedProvider<P> {
+    /// Create a new [CachedProvider](CachedProvider) wrapping
+    /// `provider`, where cached exchange rates expire after `ttl`.
+    pub fn new(provider: P, ttl: Duration) -> CachedProvider<P> {
+        CachedProvider {
+            provider,
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The provider being cached.
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// The time-to-live of the cached exchange rates.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Remove all of the cached exchange rates.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    /// Get the cached exchange rate, if it has not yet expired.
+    fn cached(&self, date: NaiveDate, base: CommodityTypeID) -> Option<ExchangeRate> {
+        let mut cache = self.cache.lock().unwrap();
+        match cache.get(&(date, base)) {
+            Some((inserted, exchange_rate)) if inserted.elapsed() < self.ttl => {
+                Some(exchange_rate.clone())
+            }
+            Some(_) => {
+                cache.remove(&(date, base));
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, date: NaiveDate, base: CommodityTypeID, exchange_rate: &ExchangeRate) {
+        self.cache
+            .lock()
+            .unwrap()
+            .insert((date, base), (Instant::now(), exchange_rate.clone()));
+    }
+}
+
+impl<P: RateProvider> RateProvider for CachedProvider<P> {
+    fn exchange_rate(
+        &self,
+        date: NaiveDate,
+        base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ProviderError> {
+        if let Some(exchange_rate) = self.cached(date, base) {
+            return Ok(exchange_rate);
+        }
+
+        let exchange_rate = self.provider.exchange_rate(date, base)?;
+        self.insert(date, base, &exchange_rate);
+        Ok(exchange_rate)
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<P: AsyncRateProvider> AsyncRateProvider for CachedProvider<P> {
+    async fn exchange_rate(
+        &self,
+        date: NaiveDate,
+        base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ProviderError> {
+        if let Some(exchange_rate) = self.cached(date, base) {
+            return Ok(exchange_rate);
+        }
+
+        let exchange_rate = self.provider.exchange_rate(date, base).await?;
+        self.insert(date, base, &exchange_rate);
+        Ok(exchange_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CachedProvider;
+    use crate::exchange_rate::ExchangeRate;
+    use crate::provider::{ProviderError, RateProvider};
+    use crate::CommodityTypeID;
+    use chrono::NaiveDate;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Provides an empty exchange rate for any date and base,
+    /// counting how many times it was called.
+    #[derive(Default)]
+    struct CountingProvider {
+        calls: AtomicUsize,
+    }
+
+    impl RateProvider for CountingProvider {
+        fn exchange_rate(
+            &self,
+            date: NaiveDate,
+            base: CommodityTypeID,
+        ) -> Result<ExchangeRate, ProviderError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(ExchangeRate {
+                date: Some(date),
+                obtained_datetime: None,
+                base: Some(base),
+                rates: BTreeMap::new(),
+            })
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[async_trait::async_trait]
+    impl crate::provider::AsyncRateProvider for CountingProvider {
+        async fn exchange_rate(
+            &self,
+            date: NaiveDate,
+            base: CommodityTypeID,
+        ) -> Result<ExchangeRate, ProviderError> {
+            RateProvider::exchange_rate(self, date, base)
+        }
+    }
+
+    #[test]
+    fn cache() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let date = NaiveDate::from_ymd_opt(2020, 2, 7).unwrap();
+
+        let provider = CachedProvider::new(CountingProvider::default(), Duration::from_secs(60));
+        provider.exchange_rate(date, usd).unwrap();
+        provider.exchange_rate(date, usd).unwrap();
+        assert_eq!(1, provider.provider().calls.load(Ordering::SeqCst));
+
+        // different keys are cached separately
+        provider.exchange_rate(date, aud).unwrap();
+        provider
+            .exchange_rate(date.succ_opt().unwrap(), usd)
+            .unwrap();
+        assert_eq!(3, provider.provider().calls.load(Ordering::SeqCst));
+
+        provider.clear();
+        provider.exchange_rate(date, usd).unwrap();
+        assert_eq!(4, provider.provider().calls.load(Ordering::SeqCst));
+    }
+
+    /// Exchange rates older than the TTL are requested again.
+    #[test]
+    fn cache_expiry() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let date = NaiveDate::from_ymd_opt(2020, 2, 7).unwrap();
+
+        let provider = CachedProvider::new(CountingProvider::default(), Duration::from_millis(10));
+        provider.exchange_rate(date, usd).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        provider.exchange_rate(date, usd).unwrap();
+        assert_eq!(2, provider.provider().calls.load(Ordering::SeqCst));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_cache() {
+        use crate::provider::AsyncRateProvider;
+
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let date = NaiveDate::from_ymd_opt(2020, 2, 7).unwrap();
+
+        let provider = CachedProvider::new(CountingProvider::default(), Duration::from_secs(60));
+        AsyncRateProvider::exchange_rate(&provider, date, usd)
+            .await
+            .unwrap();
+        AsyncRateProvider::exchange_rate(&provider, date, usd)
+            .await
+            .unwrap();
+        assert_eq!(1, provider.provider().calls.load(Ordering::SeqCst));
+    }
+}
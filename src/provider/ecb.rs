@@ -0,0 +1,226 @@
+//! Parsers for the European Central Bank's euro foreign exchange
+//! reference rates, available with the `ecb` feature.
+//!
+//! The ECB publishes the rates as xml in the `eurofxref-daily.xml`
+//! (the most recent day), `eurofxref-hist-90d.xml` (the last 90 days)
+//! and `eurofxref-hist.xml` (every day since 1999) files. The rates
+//! are the amount of each currency per `EUR`, so the parsed
+//! [ExchangeRate](ExchangeRate)s all have `EUR` as their base.
+//!
+//! [parse_daily()](parse_daily) can be used as the parser for an
+//! [HttpProvider](super::HttpProvider) (with the `http` feature).
+
+use super::ProviderError;
+use crate::exchange_rate::{ExchangeRate, ExchangeRateHistory};
+use crate::CommodityTypeID;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// The url of the ECB's daily reference rates.
+pub const DAILY_URL: &str = "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-daily.xml";
+/// The url of the ECB's reference rates for the last 90 days.
+pub const HIST_90D_URL: &str = "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-hist-90d.xml";
+/// The url of the ECB's reference rates since 1999.
+pub const HIST_URL: &str = "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-hist.xml";
+
+/// Parse all of the dated exchange rates in an ECB reference rates
+/// xml document, in the order that they appear (the ECB lists the most
+/// recent first).
+pub fn parse(xml: &str) -> Result<Vec<ExchangeRate>, ProviderError> {
+    let document = roxmltree::Document::parse(xml)?;
+    let eur = CommodityTypeID::from_str("EUR").unwrap();
+
+    document
+        .descendants()
+        .filter(|node| node.has_tag_name("Cube") && node.has_attribute("time"))
+        .map(|day| {
+            let time = day.attribute("time").unwrap();
+            let date = NaiveDate::parse_from_str(time, "%Y-%m-%d")
+                .map_err(|_| ProviderError::InvalidData(format!("invalid date {:?}", time)))?;
+
+            let mut rates = BTreeMap::new();
+            for cube in day.children().filter(|node| node.has_tag_name("Cube")) {
+                let (currency, rate) = match (cube.attribute("currency"), cube.attribute("rate")) {
+                    (Some(currency), Some(rate)) => (currency, rate),
+                    _ => {
+                        return Err(ProviderError::InvalidData(format!(
+                            "missing currency or rate on {}",
+                            date
+                        )))
+                    }
+                };
+
+                let id = CommodityTypeID::from_str(currency).map_err(|_| {
+                    ProviderError::InvalidData(format!("invalid currency {:?}", currency))
+                })?;
+                let rate = Decimal::from_str(rate).map_err(|_| {
+                    ProviderError::InvalidData(format!("invalid rate {:?} for {}", rate, currency))
+                })?;
+                rates.insert(id, rate);
+            }
+
+            Ok(ExchangeRate {
+                date: Some(date),
+                obtained_datetime: None,
+                base: Some(eur),
+                rates,
+            })
+        })
+        .collect()
+}
+
+/// Parse the most recent exchange rate in an ECB reference rates xml
+/// document, such as `eurofxref-daily.xml`.
+///
+/// # Example
+/// ```
+/// # use commodity::provider::ecb;
+/// # use commodity::CommodityTypeID;
+/// use chrono::NaiveDate;
+/// use rust_decimal::Decimal;
+/// use std::str::FromStr;
+///
+/// let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+/// <gesmes:Envelope xmlns:gesmes="http://www.gesmes.org/xml/2002-08-01" xmlns="http://www.ecb.int/vocabulary/2002-08-01/eurofxref">
+///     <gesmes:subject>Reference rates</gesmes:subject>
+///     <Cube>
+///         <Cube time='2020-02-07'>
+///             <Cube currency='USD' rate='1.0951'/>
+///             <Cube currency='JPY' rate='120.29'/>
+///         </Cube>
+///     </Cube>
+/// </gesmes:Envelope>"#;
+///
+/// let exchange_rate = ecb::parse_daily(xml).unwrap();
+/// let usd = CommodityTypeID::from_str("USD").unwrap();
+/// assert_eq!(Some(CommodityTypeID::from_str("EUR").unwrap()), exchange_rate.base);
+/// assert_eq!(NaiveDate::from_ymd_opt(2020, 2, 7), exchange_rate.date);
+/// assert_eq!(Some(&Decimal::from_str("1.0951").unwrap()), exchange_rate.get_rate(&usd));
+/// ```
+pub fn parse_daily(xml: &str) -> Result<ExchangeRate, ProviderError> {
+    parse(xml)?
+        .into_iter()
+        .max_by_key(|exchange_rate| exchange_rate.date)
+        .ok_or_else(|| ProviderError::InvalidData("no exchange rates".to_string()))
+}
+
+/// Parse all of the exchange rates in an ECB reference rates xml
+/// document, such as `eurofxref-hist-90d.xml` or `eurofxref-hist.xml`,
+/// into an [ExchangeRateHistory](ExchangeRateHistory).
+pub fn parse_history(xml: &str) -> Result<ExchangeRateHistory, ProviderError> {
+    let mut history = ExchangeRateHistory::new();
+    for exchange_rate in parse(xml)? {
+        history.insert(exchange_rate)?;
+    }
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_daily, parse_history};
+    use crate::exchange_rate::LookupStrategy;
+    use crate::provider::ProviderError;
+    use crate::CommodityTypeID;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    const DAILY: &str = include_str!("../../fixtures/ecb/eurofxref-daily.xml");
+    const HIST_90D: &str = include_str!("../../fixtures/ecb/eurofxref-hist-90d.xml");
+    const HIST: &str = include_str!("../../fixtures/ecb/eurofxref-hist.xml");
+
+    #[test]
+    fn daily() {
+        let exchange_rate = parse_daily(DAILY).unwrap();
+        assert_eq!(NaiveDate::from_ymd_opt(2020, 2, 7), exchange_rate.date);
+        assert_eq!(
+            Some(CommodityTypeID::from_str("EUR").unwrap()),
+            exchange_rate.base
+        );
+        assert_eq!(32, exchange_rate.rates.len());
+
+        let rate = |id: &str| {
+            *exchange_rate
+                .get_rate(&CommodityTypeID::from_str(id).unwrap())
+                .unwrap()
+        };
+        assert_eq!(Decimal::from_str("0.84803").unwrap(), rate("GBP"));
+        assert_eq!(Decimal::from_str("15004.73").unwrap(), rate("IDR"));
+        // trailing zeros are preserved
+        assert_eq!("137.90", rate("ISK").to_string());
+        assert_eq!("78.1610", rate("INR").to_string());
+    }
+
+    #[test]
+    fn hist_90d() {
+        let exchange_rates = parse(HIST_90D).unwrap();
+        assert_eq!(3, exchange_rates.len());
+        assert_eq!(NaiveDate::from_ymd_opt(2020, 2, 7), exchange_rates[0].date);
+        assert_eq!(NaiveDate::from_ymd_opt(2020, 2, 5), exchange_rates[2].date);
+
+        let history = parse_history(HIST_90D).unwrap();
+        assert_eq!(3, history.len());
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let gbp = CommodityTypeID::from_str("GBP").unwrap();
+        assert_eq!(
+            Decimal::from_str("1.0994").unwrap(),
+            history
+                .get(&NaiveDate::from_ymd_opt(2020, 2, 6).unwrap())
+                .unwrap()
+                .rates[&usd]
+        );
+
+        // the most recent rate is the same as in the daily file
+        assert_eq!(
+            parse_daily(DAILY).unwrap().get_rate(&gbp),
+            parse_daily(HIST_90D).unwrap().get_rate(&gbp)
+        );
+    }
+
+    /// The full history includes currencies which have since been
+    /// withdrawn.
+    #[test]
+    fn hist() {
+        let history = parse_history(HIST).unwrap();
+        assert_eq!(3, history.len());
+        assert_eq!(NaiveDate::from_ymd_opt(1999, 1, 4), history.first_date());
+        assert_eq!(NaiveDate::from_ymd_opt(2020, 2, 7), history.last_date());
+
+        let eur = CommodityTypeID::from_str("EUR").unwrap();
+        let dem = CommodityTypeID::from_str("DEM").unwrap();
+        let grd = CommodityTypeID::from_str("GRD").unwrap();
+        let date = NaiveDate::from_ymd_opt(2001, 1, 2).unwrap();
+        assert_eq!(
+            Decimal::from_str("340.75").unwrap(),
+            history
+                .rate_at(date, &eur, &grd, LookupStrategy::Exact)
+                .unwrap()
+        );
+        assert!(history
+            .rate_at(date, &eur, &dem, LookupStrategy::Exact)
+            .is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(parse("<Cube"), Err(ProviderError::Xml(_))));
+        assert!(matches!(
+            parse_daily("<Cube></Cube>"),
+            Err(ProviderError::InvalidData(_))
+        ));
+        assert!(matches!(
+            parse("<Cube><Cube time='2020-02-07'><Cube currency='USD' rate='abc'/></Cube></Cube>"),
+            Err(ProviderError::InvalidData(_))
+        ));
+        assert!(matches!(
+            parse("<Cube><Cube time='2020-02-07'><Cube currency='USD'/></Cube></Cube>"),
+            Err(ProviderError::InvalidData(_))
+        ));
+        assert!(matches!(
+            parse("<Cube><Cube time='07/02/2020'></Cube></Cube>"),
+            Err(ProviderError::InvalidData(_))
+        ));
+    }
+}
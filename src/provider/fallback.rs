@@ -0,0 +1,203 @@
+//! [FallbackProvider](FallbackProvider), which tries a list of
+//! providers in turn until one of them returns an exchange rate.
+
+use super::{ProviderError, RateProvider};
+use crate::exchange_rate::ExchangeRate;
+use crate::CommodityTypeID;
+use chrono::NaiveDate;
+
+#[cfg(feature = "async")]
+use super::AsyncRateProvider;
+#[cfg(feature = "async")]
+use async_trait::async_trait;
+
+/// A provider which tries a chain of providers in order, returning
+/// the first [ExchangeRate](ExchangeRate) which is successfully
+/// provided. If all of the providers fail,
+/// [ProviderError::AllFailed](ProviderError::AllFailed) is returned
+/// with each of their errors.
+///
+/// Providers of different types can be combined using
+/// `FallbackProvider<Box<dyn RateProvider>>`.
+#[derive(Debug, Clone)]
+pub struct FallbackProvider<P> {
+    /// The providers, in the order that they are tried.
+    pub providers: Vec<P>,
+}
+
+impl<P> FallbackProvider<P> {
+    /// Create a new [FallbackProvider](FallbackProvider) without any
+    /// providers.
+    pub fn new() -> FallbackProvider<P> {
+        FallbackProvider {
+            providers: Vec::new(),
+        }
+    }
+
+    /// Add a provider to the end of the chain.
+    pub fn with_provider(mut self, provider: P) -> Self {
+        self.providers.push(provider);
+        self
+    }
+}
+
+impl<P> Default for FallbackProvider<P> {
+    fn default() -> Self {
+        FallbackProvider::new()
+    }
+}
+
+impl<P> From<Vec<P>> for FallbackProvider<P> {
+    fn from(providers: Vec<P>) -> Self {
+        FallbackProvider { providers }
+    }
+}
+
+impl<P: RateProvider> RateProvider for FallbackProvider<P> {
+    fn exchange_rate(
+        &self,
+        date: NaiveDate,
+        base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ProviderError> {
+        let mut errors = Vec::with_capacity(self.providers.len());
+
+        for provider in &self.providers {
+            match provider.exchange_rate(date, base) {
+                Ok(exchange_rate) => return Ok(exchange_rate),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        Err(ProviderError::AllFailed(errors))
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<P: AsyncRateProvider> AsyncRateProvider for FallbackProvider<P> {
+    async fn exchange_rate(
+        &self,
+        date: NaiveDate,
+        base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ProviderError> {
+        let mut errors = Vec::with_capacity(self.providers.len());
+
+        for provider in &self.providers {
+            match provider.exchange_rate(date, base).await {
+                Ok(exchange_rate) => return Ok(exchange_rate),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        Err(ProviderError::AllFailed(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FallbackProvider;
+    use crate::exchange_rate::{ExchangeRate, ExchangeRateHistory};
+    use crate::provider::{ProviderError, RateProvider};
+    use crate::CommodityTypeID;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    fn test_history(date: NaiveDate, rate: &str) -> ExchangeRateHistory {
+        let mut rates = BTreeMap::new();
+        rates.insert(
+            CommodityTypeID::from_str("AUD").unwrap(),
+            Decimal::from_str(rate).unwrap(),
+        );
+        let mut history = ExchangeRateHistory::new();
+        history
+            .insert(ExchangeRate {
+                date: Some(date),
+                obtained_datetime: None,
+                base: Some(CommodityTypeID::from_str("USD").unwrap()),
+                rates,
+            })
+            .unwrap();
+        history
+    }
+
+    #[test]
+    fn fallback() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let date_1 = NaiveDate::from_ymd_opt(2020, 2, 7).unwrap();
+        let date_2 = NaiveDate::from_ymd_opt(2020, 2, 8).unwrap();
+
+        let providers: Vec<Box<dyn RateProvider>> = vec![
+            Box::new(test_history(date_1, "1.5")),
+            Box::new(test_history(date_1, "1.6")),
+            Box::new(test_history(date_2, "1.7")),
+        ];
+        let provider = FallbackProvider::from(providers);
+
+        // the first provider which succeeds is used
+        assert_eq!(
+            Some(&Decimal::from_str("1.5").unwrap()),
+            provider.exchange_rate(date_1, usd).unwrap().get_rate(&aud)
+        );
+        assert_eq!(
+            Some(&Decimal::from_str("1.7").unwrap()),
+            provider.exchange_rate(date_2, usd).unwrap().get_rate(&aud)
+        );
+
+        let date_3 = NaiveDate::from_ymd_opt(2020, 2, 9).unwrap();
+        match provider.exchange_rate(date_3, usd) {
+            Err(ProviderError::AllFailed(errors)) => {
+                assert_eq!(3, errors.len());
+                assert!(errors
+                    .iter()
+                    .all(|error| matches!(error, ProviderError::NotAvailable { .. })));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let empty: FallbackProvider<ExchangeRateHistory> = FallbackProvider::new();
+        assert!(matches!(
+            empty.exchange_rate(date_1, usd),
+            Err(ProviderError::AllFailed(errors)) if errors.is_empty()
+        ));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_fallback() {
+        use crate::provider::AsyncRateProvider;
+        use async_trait::async_trait;
+
+        struct AsyncHistory(ExchangeRateHistory);
+
+        #[async_trait]
+        impl AsyncRateProvider for AsyncHistory {
+            async fn exchange_rate(
+                &self,
+                date: NaiveDate,
+                base: CommodityTypeID,
+            ) -> Result<ExchangeRate, ProviderError> {
+                self.0.exchange_rate(date, base)
+            }
+        }
+
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let date_1 = NaiveDate::from_ymd_opt(2020, 2, 7).unwrap();
+        let date_2 = NaiveDate::from_ymd_opt(2020, 2, 8).unwrap();
+
+        let provider = FallbackProvider::new()
+            .with_provider(AsyncHistory(test_history(date_1, "1.5")))
+            .with_provider(AsyncHistory(test_history(date_2, "1.7")));
+
+        assert_eq!(
+            Some(&Decimal::from_str("1.7").unwrap()),
+            AsyncRateProvider::exchange_rate(&provider, date_2, usd)
+                .await
+                .unwrap()
+                .get_rate(&aud)
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! [FileProvider](FileProvider), which reads exchange rates from files
+//! on disk.
+
+use super::{select_exchange_rate, ProviderError, RateProvider};
+use crate::exchange_rate::ExchangeRate;
+use crate::CommodityTypeID;
+use chrono::NaiveDate;
+use std::path::{Path, PathBuf};
+
+/// A provider which reads [ExchangeRate](ExchangeRate)s from a json
+/// file containing an array of exchange rates, available with the
+/// `json` feature.
+///
+/// The file is read every time an exchange rate is requested, so that
+/// changes to it are picked up; wrap this provider in a
+/// [CachedProvider](super::CachedProvider) to avoid reading it
+/// repeatedly. An exchange rate on the requested date which has a
+/// different base is rebased to the requested base.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileProvider {
+    path: PathBuf,
+}
+
+impl FileProvider {
+    /// Create a new [FileProvider](FileProvider) reading from the file
+    /// at `path`.
+    pub fn new<P: AsRef<Path>>(path: P) -> FileProvider {
+        FileProvider {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The path of the file which this provider reads from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read all of the exchange rates from the file.
+    pub fn read(&self) -> Result<Vec<ExchangeRate>, ProviderError> {
+        let json = std::fs::read_to_string(&self.path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+impl RateProvider for FileProvider {
+    fn exchange_rate(
+        &self,
+        date: NaiveDate,
+        base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ProviderError> {
+        select_exchange_rate(self.read()?.iter(), date, base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileProvider;
+    use crate::exchange_rate::ExchangeRate;
+    use crate::provider::{ProviderError, RateProvider};
+    use crate::CommodityTypeID;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    #[test]
+    fn file_provider() {
+        let path = std::env::temp_dir().join(format!(
+            "commodity_file_provider_test_{}.json",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            r#"[
+                {
+                    "date": "2020-02-07",
+                    "obtained_datetime": null,
+                    "base": "USD",
+                    "rates": { "AUD": "1.6", "EUR": "0.9" }
+                },
+                {
+                    "date": "2020-02-08",
+                    "obtained_datetime": null,
+                    "base": "EUR",
+                    "rates": { "USD": "1.1" }
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        let provider = FileProvider::new(&path);
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let eur = CommodityTypeID::from_str("EUR").unwrap();
+        let date_1 = NaiveDate::from_ymd_opt(2020, 2, 7).unwrap();
+        let date_2 = NaiveDate::from_ymd_opt(2020, 2, 8).unwrap();
+
+        let exchange_rate: ExchangeRate = provider.exchange_rate(date_1, usd).unwrap();
+        assert_eq!(
+            Some(&Decimal::from_str("1.6").unwrap()),
+            exchange_rate.get_rate(&aud)
+        );
+
+        let rebased = provider.exchange_rate(date_1, aud).unwrap();
+        assert_eq!(Some(aud), rebased.base);
+        assert_eq!(
+            Some(&Decimal::from_str("0.625").unwrap()),
+            rebased.get_rate(&usd)
+        );
+
+        assert_eq!(Some(eur), provider.exchange_rate(date_2, eur).unwrap().base);
+        assert!(matches!(
+            provider.exchange_rate(date_2, aud),
+            Err(ProviderError::NotAvailable { .. })
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            provider.exchange_rate(date_1, usd),
+            Err(ProviderError::Io(_))
+        ));
+    }
+}
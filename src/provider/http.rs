@@ -0,0 +1,304 @@
+//! [HttpProvider](HttpProvider), which requests exchange rates from a
+//! http server, available with the `http` feature.
+
+use super::{ProviderError, RateProvider};
+use crate::exchange_rate::ExchangeRate;
+use crate::CommodityTypeID;
+use chrono::NaiveDate;
+use std::fmt;
+
+/// A function which parses the body of a response into an
+/// [ExchangeRate](ExchangeRate).
+type Parser = Box<dyn Fn(&str) -> Result<ExchangeRate, ProviderError> + Send + Sync>;
+
+/// A provider which requests [ExchangeRate](ExchangeRate)s from a
+/// http server, available with the `http` feature (which supports both
+/// `http` and `https` urls).
+///
+/// The url is created from a template, where `{date}` is replaced with
+/// the requested date (formatted as `%Y-%m-%d`), and `{base}` with the
+/// requested base commodity type, for example
+/// `https://example.com/rates/{date}?base={base}`. By default the
+/// response is expected to be an [ExchangeRate](ExchangeRate)
+/// serialized as json, use
+/// [with_parser()](HttpProvider::with_parser) to parse other formats.
+/// If the provided exchange rate has a different base to the one
+/// requested, it is rebased. A `404` response, or an exchange rate with
+/// a `date` other than the one requested, results in a
+/// [ProviderError::NotAvailable](ProviderError::NotAvailable).
+pub struct HttpProvider {
+    url_template: String,
+    agent: ureq::Agent,
+    parser: Parser,
+}
+
+impl HttpProvider {
+    /// Create a new [HttpProvider](HttpProvider) requesting exchange
+    /// rates from urls created using `url_template`.
+    pub fn new<S: Into<String>>(url_template: S) -> HttpProvider {
+        HttpProvider {
+            url_template: url_template.into(),
+            agent: ureq::Agent::new(),
+            parser: Box::new(|body| Ok(serde_json::from_str(body)?)),
+        }
+    }
+
+    /// Use the specified `agent` to make the requests, for example to
+    /// configure timeouts or a proxy.
+    pub fn with_agent(mut self, agent: ureq::Agent) -> Self {
+        self.agent = agent;
+        self
+    }
+
+    /// Use the specified `parser` to parse the body of the responses.
+    pub fn with_parser<F>(mut self, parser: F) -> Self
+    where
+        F: Fn(&str) -> Result<ExchangeRate, ProviderError> + Send + Sync + 'static,
+    {
+        self.parser = Box::new(parser);
+        self
+    }
+
+    /// The url used to request the exchange rate on `date` with the
+    /// `base` commodity type.
+    pub fn url(&self, date: NaiveDate, base: CommodityTypeID) -> String {
+        self.url_template
+            .replace("{date}", &date.format("%Y-%m-%d").to_string())
+            .replace("{base}", &base.to_string())
+    }
+}
+
+impl fmt::Debug for HttpProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpProvider")
+            .field("url_template", &self.url_template)
+            .finish()
+    }
+}
+
+impl RateProvider for HttpProvider {
+    fn exchange_rate(
+        &self,
+        date: NaiveDate,
+        base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ProviderError> {
+        let response = match self.agent.get(&self.url(date, base)).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => {
+                return Err(ProviderError::NotAvailable { date, base })
+            }
+            Err(error) => return Err(Box::new(error).into()),
+        };
+
+        let exchange_rate = (self.parser)(&response.into_string()?)?;
+        if matches!(exchange_rate.date, Some(rate_date) if rate_date != date) {
+            return Err(ProviderError::NotAvailable { date, base });
+        }
+
+        if exchange_rate.base == Some(base) {
+            Ok(exchange_rate)
+        } else {
+            Ok(exchange_rate.rebase(base)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HttpProvider;
+    use crate::exchange_rate::{ExchangeRate, ExchangeRateError};
+    use crate::provider::{CachedProvider, FallbackProvider, ProviderError, RateProvider};
+    use crate::CommodityTypeID;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread::JoinHandle;
+    use std::time::Duration;
+
+    /// An in-process http server serving a usd based exchange rate on
+    /// 2020-02-07 (which is also served for 2020-02-08, like a server
+    /// which ignores the date), an invalid aud based exchange rate on
+    /// 2020-02-07, `500` for any url under `/unavailable/`, and `404`
+    /// for any other url.
+    struct StubServer {
+        server: Arc<tiny_http::Server>,
+        requests: Arc<AtomicUsize>,
+        handle: Option<JoinHandle<()>>,
+    }
+
+    impl StubServer {
+        fn start() -> StubServer {
+            let server = Arc::new(tiny_http::Server::http("127.0.0.1:0").unwrap());
+            let requests = Arc::new(AtomicUsize::new(0));
+
+            let handle = {
+                let server = server.clone();
+                let requests = requests.clone();
+                std::thread::spawn(move || {
+                    for request in server.incoming_requests() {
+                        requests.fetch_add(1, Ordering::SeqCst);
+                        let response = match request.url() {
+                            "/rates/2020-02-07?base=USD" | "/rates/2020-02-08?base=USD" => {
+                                tiny_http::Response::from_string(
+                                    r#"{
+                                    "date": "2020-02-07",
+                                    "obtained_datetime": null,
+                                    "base": "USD",
+                                    "rates": { "AUD": "1.6" }
+                                }"#,
+                                )
+                            }
+                            "/rates/2020-02-07?base=AUD" => {
+                                tiny_http::Response::from_string("not json")
+                            }
+                            url if url.starts_with("/unavailable/") => {
+                                tiny_http::Response::from_string("").with_status_code(500)
+                            }
+                            _ => tiny_http::Response::from_string("").with_status_code(404),
+                        };
+                        request.respond(response).unwrap();
+                    }
+                })
+            };
+
+            StubServer {
+                server,
+                requests,
+                handle: Some(handle),
+            }
+        }
+
+        fn url_template(&self) -> String {
+            format!(
+                "http://{}/rates/{{date}}?base={{base}}",
+                self.server.server_addr().to_ip().unwrap()
+            )
+        }
+
+        fn unavailable_url_template(&self) -> String {
+            format!(
+                "http://{}/unavailable/{{date}}",
+                self.server.server_addr().to_ip().unwrap()
+            )
+        }
+    }
+
+    impl Drop for StubServer {
+        fn drop(&mut self) {
+            self.server.unblock();
+            if let Some(handle) = self.handle.take() {
+                handle.join().unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn http_provider() {
+        let server = StubServer::start();
+        let provider = HttpProvider::new(server.url_template());
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let date = NaiveDate::from_ymd_opt(2020, 2, 7).unwrap();
+
+        let exchange_rate = provider.exchange_rate(date, usd).unwrap();
+        assert_eq!(Some(date), exchange_rate.date);
+        assert_eq!(
+            Some(&Decimal::from_str("1.6").unwrap()),
+            exchange_rate.get_rate(&aud)
+        );
+
+        assert!(matches!(
+            provider.exchange_rate(NaiveDate::from_ymd_opt(2020, 2, 9).unwrap(), usd),
+            Err(ProviderError::NotAvailable { .. })
+        ));
+        // the server responds with the rate for another date
+        let next_date = date.succ_opt().unwrap();
+        assert!(matches!(
+            provider.exchange_rate(next_date, usd),
+            Err(ProviderError::NotAvailable { date, .. }) if date == next_date
+        ));
+        assert_eq!(
+            ProviderError::NotAvailable {
+                date: next_date,
+                base: usd
+            }
+            .to_string(),
+            provider
+                .exchange_rate(next_date, usd)
+                .unwrap_err()
+                .to_string()
+        );
+        assert!(matches!(
+            provider.exchange_rate(date, aud),
+            Err(ProviderError::Json(_))
+        ));
+
+        let unavailable = HttpProvider::new(server.unavailable_url_template());
+        assert!(matches!(
+            unavailable.exchange_rate(date, usd),
+            Err(ProviderError::Http(_))
+        ));
+    }
+
+    /// A custom parser can be used, and a response with a different
+    /// base is rebased.
+    #[test]
+    fn http_provider_parser() {
+        let server = StubServer::start();
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let date = NaiveDate::from_ymd_opt(2020, 2, 7).unwrap();
+
+        // always request the usd based exchange rate
+        let url_template = server.url_template().replace("{base}", "USD");
+        let provider = HttpProvider::new(url_template).with_parser(move |_body| {
+            let mut rates = BTreeMap::new();
+            rates.insert(aud, Decimal::from_str("1.6").unwrap());
+            Ok(ExchangeRate {
+                date: Some(date),
+                obtained_datetime: None,
+                base: Some(usd),
+                rates,
+            })
+        });
+
+        let exchange_rate = provider.exchange_rate(date, aud).unwrap();
+        assert_eq!(Some(aud), exchange_rate.base);
+        assert_eq!(
+            Some(&Decimal::from_str("0.625").unwrap()),
+            exchange_rate.get_rate(&usd)
+        );
+
+        let nzd = CommodityTypeID::from_str("NZD").unwrap();
+        assert!(matches!(
+            provider.exchange_rate(date, nzd),
+            Err(ProviderError::ExchangeRate(
+                ExchangeRateError::CommodityTypeNotPresent(_)
+            ))
+        ));
+    }
+
+    /// The http provider combined with caching, and falling back to it
+    /// when another server is unavailable.
+    #[test]
+    fn http_provider_cache_fallback() {
+        let server = StubServer::start();
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let date = NaiveDate::from_ymd_opt(2020, 2, 7).unwrap();
+
+        let provider = CachedProvider::new(
+            FallbackProvider::new()
+                .with_provider(HttpProvider::new(server.unavailable_url_template()))
+                .with_provider(HttpProvider::new(server.url_template())),
+            Duration::from_secs(60),
+        );
+
+        assert_eq!(Some(usd), provider.exchange_rate(date, usd).unwrap().base);
+        assert_eq!(Some(usd), provider.exchange_rate(date, usd).unwrap().base);
+        // one request failing with a `500`, and one succeeding
+        assert_eq!(2, server.requests.load(Ordering::SeqCst));
+    }
+}
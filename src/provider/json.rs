@@ -0,0 +1,456 @@
+//! Adapters for the json responses of popular exchange rate services,
+//! available with the `json` feature.
+//!
+//! Each [JsonFormat](JsonFormat) parses a response into an
+//! [ExchangeRate](ExchangeRate), mapping the response's timestamp (if
+//! it has one) to `obtained_datetime`. The rates are parsed directly
+//! from the json text, so numeric rates are not rounded by converting
+//! them to floating point first, and rates given as strings are also
+//! supported.
+//!
+//! A [JsonFormat](JsonFormat) can be used as the parser for an
+//! [HttpProvider](super::HttpProvider) (with the `http` feature):
+//!
+//! ```
+//! # #[cfg(feature = "http")]
+//! # {
+//! # use commodity::provider::json::JsonFormat;
+//! # use commodity::provider::HttpProvider;
+//! let provider = HttpProvider::new("https://api.frankfurter.app/{date}?from={base}")
+//!     .with_parser(|body| JsonFormat::Frankfurter.parse(body));
+//! # }
+//! ```
+
+use super::ProviderError;
+use crate::exchange_rate::ExchangeRate;
+use crate::CommodityTypeID;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// The format of a json exchange rate response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JsonFormat {
+    /// [Open Exchange Rates](https://openexchangerates.org/), with a
+    /// unix `timestamp`, the `base` and the `rates`. The date is the
+    /// date of the timestamp.
+    OpenExchangeRates,
+    /// [Fixer](https://fixer.io/) and
+    /// [exchangerate.host](https://exchangerate.host/), with a
+    /// `success` flag, a unix `timestamp`, the `date`, the `base` and
+    /// the `rates`. An unsuccessful response results in a
+    /// [ProviderError::InvalidData](ProviderError::InvalidData) with
+    /// the error information.
+    Fixer,
+    /// [Frankfurter](https://www.frankfurter.app/), with the `amount`
+    /// of the base which the rates are for, the `base`, the `date` and
+    /// the `rates`.
+    Frankfurter,
+    /// The [Coinbase](https://www.coinbase.com/) exchange rates api,
+    /// with the base `currency` and the `rates` (as strings) in a
+    /// `data` object. The response has no date.
+    Coinbase,
+}
+
+impl JsonFormat {
+    /// Parse a json response in this format into an
+    /// [ExchangeRate](ExchangeRate).
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::provider::json::JsonFormat;
+    /// # use commodity::CommodityTypeID;
+    /// use chrono::NaiveDate;
+    /// use rust_decimal::Decimal;
+    /// use std::str::FromStr;
+    ///
+    /// let body = r#"{"amount":1.0,"base":"EUR","date":"2020-02-07","rates":{"USD":1.0951}}"#;
+    /// let exchange_rate = JsonFormat::Frankfurter.parse(body).unwrap();
+    ///
+    /// let usd = CommodityTypeID::from_str("USD").unwrap();
+    /// assert_eq!(NaiveDate::from_ymd_opt(2020, 2, 7), exchange_rate.date);
+    /// assert_eq!(Some(&Decimal::from_str("1.0951").unwrap()), exchange_rate.get_rate(&usd));
+    /// ```
+    pub fn parse(&self, body: &str) -> Result<ExchangeRate, ProviderError> {
+        match self {
+            JsonFormat::OpenExchangeRates => parse_open_exchange_rates(body),
+            JsonFormat::Fixer => parse_fixer(body),
+            JsonFormat::Frankfurter => parse_frankfurter(body),
+            JsonFormat::Coinbase => parse_coinbase(body),
+        }
+    }
+}
+
+/// The rates in a response. The keys (and other strings in the
+/// responses) are a [Cow](Cow) so that they are only copied if they
+/// contain escape sequences.
+type RawRates<'a> = BTreeMap<Cow<'a, str>, &'a RawValue>;
+
+#[derive(Deserialize)]
+struct OpenExchangeRatesResponse<'a> {
+    timestamp: i64,
+    #[serde(borrow)]
+    base: Cow<'a, str>,
+    #[serde(borrow)]
+    rates: RawRates<'a>,
+}
+
+#[derive(Deserialize)]
+struct FixerError {
+    #[serde(default)]
+    code: Option<i64>,
+    #[serde(default, rename = "type")]
+    error_type: Option<String>,
+    #[serde(default)]
+    info: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FixerResponse<'a> {
+    #[serde(default = "default_success")]
+    success: bool,
+    #[serde(default)]
+    error: Option<FixerError>,
+    #[serde(default)]
+    timestamp: Option<i64>,
+    #[serde(borrow, default)]
+    date: Option<Cow<'a, str>>,
+    #[serde(borrow, default)]
+    base: Option<Cow<'a, str>>,
+    #[serde(borrow, default)]
+    rates: RawRates<'a>,
+}
+
+fn default_success() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct FrankfurterResponse<'a> {
+    #[serde(borrow)]
+    amount: Option<&'a RawValue>,
+    #[serde(borrow)]
+    base: Cow<'a, str>,
+    #[serde(borrow)]
+    date: Cow<'a, str>,
+    #[serde(borrow)]
+    rates: RawRates<'a>,
+}
+
+#[derive(Deserialize)]
+struct CoinbaseData<'a> {
+    #[serde(borrow)]
+    currency: Cow<'a, str>,
+    #[serde(borrow)]
+    rates: RawRates<'a>,
+}
+
+#[derive(Deserialize)]
+struct CoinbaseResponse<'a> {
+    #[serde(borrow)]
+    data: CoinbaseData<'a>,
+}
+
+fn parse_open_exchange_rates(body: &str) -> Result<ExchangeRate, ProviderError> {
+    let response: OpenExchangeRatesResponse = serde_json::from_str(body)?;
+    let obtained_datetime = parse_timestamp(response.timestamp)?;
+
+    Ok(ExchangeRate {
+        date: Some(obtained_datetime.date_naive()),
+        obtained_datetime: Some(obtained_datetime),
+        base: Some(parse_id(&response.base)?),
+        rates: parse_rates(&response.rates)?,
+    })
+}
+
+fn parse_fixer(body: &str) -> Result<ExchangeRate, ProviderError> {
+    let response: FixerResponse = serde_json::from_str(body)?;
+
+    if !response.success {
+        let message = match response.error {
+            Some(error) => format!(
+                "unsuccessful response (code: {}, type: {}): {}",
+                error.code.map(|code| code.to_string()).unwrap_or_default(),
+                error.error_type.unwrap_or_default(),
+                error.info.unwrap_or_default()
+            ),
+            None => "unsuccessful response".to_string(),
+        };
+        return Err(ProviderError::InvalidData(message));
+    }
+
+    let obtained_datetime = response.timestamp.map(parse_timestamp).transpose()?;
+    let date = match &response.date {
+        Some(date) => Some(parse_date(date)?),
+        None => obtained_datetime.map(|datetime| datetime.date_naive()),
+    };
+
+    Ok(ExchangeRate {
+        date,
+        obtained_datetime,
+        base: response.base.as_deref().map(parse_id).transpose()?,
+        rates: parse_rates(&response.rates)?,
+    })
+}
+
+fn parse_frankfurter(body: &str) -> Result<ExchangeRate, ProviderError> {
+    let response: FrankfurterResponse = serde_json::from_str(body)?;
+    let mut rates = parse_rates(&response.rates)?;
+
+    // the rates are for `amount` units of the base
+    if let Some(amount) = response.amount {
+        let amount = parse_decimal(amount)
+            .ok_or_else(|| ProviderError::InvalidData(format!("invalid amount {}", amount)))?;
+        if amount.is_zero() {
+            return Err(ProviderError::InvalidData("the amount is zero".to_string()));
+        }
+
+        if amount != Decimal::ONE {
+            for rate in rates.values_mut() {
+                *rate = rate.checked_div(amount).ok_or_else(|| {
+                    ProviderError::InvalidData(format!(
+                        "unable to divide the rate {} by the amount {}",
+                        rate, amount
+                    ))
+                })?;
+            }
+        }
+    }
+
+    Ok(ExchangeRate {
+        date: Some(parse_date(&response.date)?),
+        obtained_datetime: None,
+        base: Some(parse_id(&response.base)?),
+        rates,
+    })
+}
+
+fn parse_coinbase(body: &str) -> Result<ExchangeRate, ProviderError> {
+    let response: CoinbaseResponse = serde_json::from_str(body)?;
+
+    Ok(ExchangeRate {
+        date: None,
+        obtained_datetime: None,
+        base: Some(parse_id(&response.data.currency)?),
+        rates: parse_rates(&response.data.rates)?,
+    })
+}
+
+fn parse_id(id: &str) -> Result<CommodityTypeID, ProviderError> {
+    CommodityTypeID::from_str(id)
+        .map_err(|_| ProviderError::InvalidData(format!("invalid commodity type id {:?}", id)))
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, ProviderError> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| ProviderError::InvalidData(format!("invalid date {:?}", date)))
+}
+
+fn parse_timestamp(timestamp: i64) -> Result<DateTime<Utc>, ProviderError> {
+    DateTime::from_timestamp(timestamp, 0)
+        .ok_or_else(|| ProviderError::InvalidData(format!("invalid timestamp {}", timestamp)))
+}
+
+/// Parse a json number or string into a [Decimal](Decimal), without
+/// converting it to floating point.
+fn parse_decimal(value: &RawValue) -> Option<Decimal> {
+    let text = value.get();
+    let text = if text.starts_with('"') {
+        serde_json::from_str::<String>(text).ok()?
+    } else {
+        text.to_string()
+    };
+
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .ok()
+}
+
+fn parse_rates(rates: &RawRates) -> Result<BTreeMap<CommodityTypeID, Decimal>, ProviderError> {
+    rates
+        .iter()
+        .map(|(id, rate)| {
+            let rate = parse_decimal(rate).ok_or_else(|| {
+                ProviderError::InvalidData(format!("invalid rate {} for {}", rate, id))
+            })?;
+            Ok((parse_id(id)?, rate))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonFormat;
+    use crate::provider::ProviderError;
+    use crate::CommodityTypeID;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    fn id(id: &str) -> CommodityTypeID {
+        CommodityTypeID::from_str(id).unwrap()
+    }
+
+    fn decimal(decimal: &str) -> Decimal {
+        Decimal::from_str(decimal).unwrap()
+    }
+
+    #[test]
+    fn open_exchange_rates() {
+        let exchange_rate = JsonFormat::OpenExchangeRates
+            .parse(include_str!("../../fixtures/json/openexchangerates.json"))
+            .unwrap();
+
+        assert_eq!(Some(id("USD")), exchange_rate.base);
+        assert_eq!(
+            Some(Utc.with_ymd_and_hms(2020, 2, 7, 16, 0, 0).unwrap()),
+            exchange_rate.obtained_datetime
+        );
+        assert_eq!(NaiveDate::from_ymd_opt(2020, 2, 7), exchange_rate.date);
+        assert_eq!(5, exchange_rate.rates.len());
+        assert_eq!(
+            Some(&decimal("0.913159")),
+            exchange_rate.get_rate(&id("EUR"))
+        );
+        // more significant digits than a f64 can represent
+        assert_eq!(
+            Some(&decimal("0.000101234567890123456")),
+            exchange_rate.get_rate(&id("BTC"))
+        );
+    }
+
+    #[test]
+    fn fixer() {
+        let exchange_rate = JsonFormat::Fixer
+            .parse(include_str!("../../fixtures/json/fixer.json"))
+            .unwrap();
+
+        assert_eq!(Some(id("EUR")), exchange_rate.base);
+        assert_eq!(NaiveDate::from_ymd_opt(2020, 2, 7), exchange_rate.date);
+        assert_eq!(
+            Some(Utc.with_ymd_and_hms(2020, 2, 7, 16, 59, 59).unwrap()),
+            exchange_rate.obtained_datetime
+        );
+        // trailing zeros are preserved
+        assert_eq!(
+            "1.095100",
+            exchange_rate.get_rate(&id("USD")).unwrap().to_string()
+        );
+
+        match JsonFormat::Fixer.parse(include_str!("../../fixtures/json/fixer-error.json")) {
+            Err(ProviderError::InvalidData(message)) => {
+                assert!(message.contains("104"));
+                assert!(message.contains("usage_limit_reached"));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn exchangerate_host() {
+        let exchange_rate = JsonFormat::Fixer
+            .parse(include_str!("../../fixtures/json/exchangerate-host.json"))
+            .unwrap();
+
+        assert_eq!(Some(id("USD")), exchange_rate.base);
+        assert_eq!(NaiveDate::from_ymd_opt(2020, 2, 7), exchange_rate.date);
+        assert_eq!(
+            Some(&decimal("1.552902")),
+            exchange_rate.get_rate(&id("NZD"))
+        );
+    }
+
+    #[test]
+    fn frankfurter() {
+        let exchange_rate = JsonFormat::Frankfurter
+            .parse(include_str!("../../fixtures/json/frankfurter.json"))
+            .unwrap();
+
+        assert_eq!(Some(id("EUR")), exchange_rate.base);
+        assert_eq!(NaiveDate::from_ymd_opt(2020, 2, 7), exchange_rate.date);
+        assert_eq!(None, exchange_rate.obtained_datetime);
+        assert_eq!(
+            Some(&decimal("0.84803")),
+            exchange_rate.get_rate(&id("GBP"))
+        );
+
+        // the rates are divided by the amount
+        let exchange_rate = JsonFormat::Frankfurter
+            .parse(include_str!("../../fixtures/json/frankfurter-amount.json"))
+            .unwrap();
+        assert_eq!(Some(&decimal("1.6394")), exchange_rate.get_rate(&id("AUD")));
+        assert_eq!(Some(&decimal("1.0951")), exchange_rate.get_rate(&id("USD")));
+    }
+
+    #[test]
+    fn coinbase() {
+        let exchange_rate = JsonFormat::Coinbase
+            .parse(include_str!("../../fixtures/json/coinbase.json"))
+            .unwrap();
+
+        assert_eq!(Some(id("BTC")), exchange_rate.base);
+        assert_eq!(None, exchange_rate.date);
+        assert_eq!(4, exchange_rate.rates.len());
+        assert_eq!(
+            Some(&decimal("43.6234923094827164")),
+            exchange_rate.get_rate(&id("ETH"))
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(
+            JsonFormat::Frankfurter.parse("{"),
+            Err(ProviderError::Json(_))
+        ));
+        assert!(matches!(
+            JsonFormat::Frankfurter
+                .parse(r#"{"amount":1.0,"base":"EUR","date":"2020-02-07","rates":{"USD":"abc"}}"#),
+            Err(ProviderError::InvalidData(_))
+        ));
+        assert!(matches!(
+            JsonFormat::Frankfurter
+                .parse(r#"{"amount":0,"base":"EUR","date":"2020-02-07","rates":{"USD":1}}"#),
+            Err(ProviderError::InvalidData(_))
+        ));
+        assert!(matches!(
+            JsonFormat::Coinbase.parse(r#"{"data":{"currency":"BTC","rates":{"TOOLONGID1":"1"}}}"#),
+            Err(ProviderError::InvalidData(_))
+        ));
+        assert_eq!(
+            Some(&decimal("0.00001")),
+            JsonFormat::Coinbase
+                .parse(r#"{"data":{"currency":"BTC","rates":{"USD":1e-5}}}"#)
+                .unwrap()
+                .get_rate(&id("USD"))
+        );
+    }
+
+    /// Strings containing escape sequences can't be borrowed from the
+    /// response.
+    #[test]
+    fn escaped_strings() {
+        let exchange_rate = JsonFormat::Frankfurter
+            .parse(r#"{"base":"\u0045UR","date":"2020\u002d02-07","rates":{"US\u0044":1.0951}}"#)
+            .unwrap();
+        assert_eq!(Some(id("EUR")), exchange_rate.base);
+        assert_eq!(NaiveDate::from_ymd_opt(2020, 2, 7), exchange_rate.date);
+        assert_eq!(Some(&decimal("1.0951")), exchange_rate.get_rate(&id("USD")));
+
+        let exchange_rate = JsonFormat::Fixer
+            .parse(r#"{"base":"\u0045UR","date":"2020\u002d02-07","rates":{"US\u0044":1.0951}}"#)
+            .unwrap();
+        assert_eq!(Some(id("EUR")), exchange_rate.base);
+        assert_eq!(NaiveDate::from_ymd_opt(2020, 2, 7), exchange_rate.date);
+
+        let exchange_rate = JsonFormat::Coinbase
+            .parse(r#"{"data":{"currency":"BT\u0043","rates":{"US\u0044":"1"}}}"#)
+            .unwrap();
+        assert_eq!(Some(id("BTC")), exchange_rate.base);
+        assert_eq!(Some(&decimal("1")), exchange_rate.get_rate(&id("USD")));
+    }
+}
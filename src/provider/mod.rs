@@ -0,0 +1,270 @@
+//! Providers of [ExchangeRate](crate::exchange_rate::ExchangeRate)
+//! data, and wrappers to cache them and to fall back between them.
+//!
+//! A provider implements [RateProvider](RateProvider) (or
+//! [AsyncRateProvider](AsyncRateProvider) with the `async` feature),
+//! returning the exchange rate for a date and base commodity type.
+//! Providers can be composed, for example:
+//!
+//! ```
+//! # use commodity::exchange_rate::{ExchangeRate, ExchangeRateHistory};
+//! # use commodity::provider::{CachedProvider, FallbackProvider, RateProvider};
+//! # use commodity::CommodityTypeID;
+//! use chrono::NaiveDate;
+//! use rust_decimal::Decimal;
+//! use std::collections::BTreeMap;
+//! use std::str::FromStr;
+//! use std::time::Duration;
+//!
+//! let usd = CommodityTypeID::from_str("USD").unwrap();
+//! let aud = CommodityTypeID::from_str("AUD").unwrap();
+//! let date = NaiveDate::from_ymd_opt(2020, 2, 7).unwrap();
+//!
+//! let mut rates = BTreeMap::new();
+//! rates.insert(aud, Decimal::from_str("1.5").unwrap());
+//! let mut history = ExchangeRateHistory::new();
+//! history.insert(ExchangeRate {
+//!     date: Some(date),
+//!     obtained_datetime: None,
+//!     base: Some(usd),
+//!     rates,
+//! }).unwrap();
+//!
+//! // An empty history is never able to provide a rate, so the
+//! // second history is used.
+//! let provider = CachedProvider::new(
+//!     FallbackProvider::new()
+//!         .with_provider(ExchangeRateHistory::new())
+//!         .with_provider(history),
+//!     Duration::from_secs(60),
+//! );
+//!
+//! let exchange_rate = provider.exchange_rate(date, aud).unwrap();
+//! assert_eq!(Some(aud), exchange_rate.base);
+//! assert_eq!(
+//!     Some(&Decimal::from_str("0.6666666666666666666666666667").unwrap()),
+//!     exchange_rate.get_rate(&usd)
+//! );
+//! ```
+
+use crate::exchange_rate::{ExchangeRate, ExchangeRateError, ExchangeRateHistory, HistoryError};
+use crate::CommodityTypeID;
+use chrono::NaiveDate;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[cfg(feature = "async")]
+use async_trait::async_trait;
+
+mod cache;
+#[cfg(feature = "ecb")]
+pub mod ecb;
+mod fallback;
+#[cfg(feature = "json")]
+mod file;
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "json")]
+pub mod json;
+
+pub use cache::CachedProvider;
+pub use fallback::FallbackProvider;
+#[cfg(feature = "json")]
+pub use file::FileProvider;
+#[cfg(feature = "http")]
+pub use http::HttpProvider;
+
+/// An error associated with functionality in the [provider](crate::provider) module.
+#[derive(Error, Debug)]
+pub enum ProviderError {
+    #[error("There is no exchange rate available with the base {base} on the date {date}.")]
+    NotAvailable {
+        date: NaiveDate,
+        base: CommodityTypeID,
+    },
+    #[error("All of the providers failed: {0:?}")]
+    AllFailed(Vec<ProviderError>),
+    #[error("The exchange rate data is invalid: {0}")]
+    InvalidData(String),
+    #[error("There was an error with the provided exchange rate.")]
+    ExchangeRate(#[from] ExchangeRateError),
+    #[error("There was an error with the provided exchange rate history.")]
+    History(#[from] HistoryError),
+    #[error("There was an error reading an exchange rate file.")]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "json")]
+    #[error("There was an error deserializing exchange rate json.")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "ecb")]
+    #[error("There was an error parsing exchange rate xml.")]
+    Xml(#[from] roxmltree::Error),
+    #[cfg(feature = "http")]
+    #[error("There was an error requesting exchange rates over http.")]
+    Http(#[from] Box<ureq::Error>),
+}
+
+/// A source of [ExchangeRate](ExchangeRate) data.
+pub trait RateProvider {
+    /// Get the exchange rate on the specified `date`, with the
+    /// specified `base` commodity type.
+    fn exchange_rate(
+        &self,
+        date: NaiveDate,
+        base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ProviderError>;
+}
+
+impl<P: RateProvider + ?Sized> RateProvider for Box<P> {
+    fn exchange_rate(
+        &self,
+        date: NaiveDate,
+        base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ProviderError> {
+        (**self).exchange_rate(date, base)
+    }
+}
+
+impl<P: RateProvider + ?Sized> RateProvider for Arc<P> {
+    fn exchange_rate(
+        &self,
+        date: NaiveDate,
+        base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ProviderError> {
+        (**self).exchange_rate(date, base)
+    }
+}
+
+/// Provides the snapshot from exactly the requested date, rebased to
+/// the requested base if required.
+impl RateProvider for ExchangeRateHistory {
+    fn exchange_rate(
+        &self,
+        date: NaiveDate,
+        base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ProviderError> {
+        select_exchange_rate(self.get(&date).into_iter(), date, base)
+    }
+}
+
+/// An asynchronous source of [ExchangeRate](ExchangeRate) data,
+/// available with the `async` feature.
+#[cfg(feature = "async")]
+#[async_trait]
+pub trait AsyncRateProvider: Send + Sync {
+    /// Get the exchange rate on the specified `date`, with the
+    /// specified `base` commodity type.
+    async fn exchange_rate(
+        &self,
+        date: NaiveDate,
+        base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ProviderError>;
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<P: AsyncRateProvider + ?Sized> AsyncRateProvider for Box<P> {
+    async fn exchange_rate(
+        &self,
+        date: NaiveDate,
+        base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ProviderError> {
+        (**self).exchange_rate(date, base).await
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<P: AsyncRateProvider + ?Sized> AsyncRateProvider for Arc<P> {
+    async fn exchange_rate(
+        &self,
+        date: NaiveDate,
+        base: CommodityTypeID,
+    ) -> Result<ExchangeRate, ProviderError> {
+        (**self).exchange_rate(date, base).await
+    }
+}
+
+/// Select the exchange rate on `date` with the `base` commodity type
+/// from `exchange_rates`, preferring one which already has the
+/// requested base, otherwise rebasing one which contains it.
+pub(crate) fn select_exchange_rate<'a, I>(
+    exchange_rates: I,
+    date: NaiveDate,
+    base: CommodityTypeID,
+) -> Result<ExchangeRate, ProviderError>
+where
+    I: Iterator<Item = &'a ExchangeRate>,
+{
+    let mut rebase_candidate = None;
+
+    for exchange_rate in exchange_rates.filter(|exchange_rate| exchange_rate.date == Some(date)) {
+        if exchange_rate.base == Some(base) {
+            return Ok(exchange_rate.clone());
+        }
+
+        if rebase_candidate.is_none()
+            && exchange_rate.base.is_some()
+            && exchange_rate.rates.contains_key(&base)
+        {
+            rebase_candidate = Some(exchange_rate);
+        }
+    }
+
+    match rebase_candidate {
+        Some(exchange_rate) => Ok(exchange_rate.rebase(base)?),
+        None => Err(ProviderError::NotAvailable { date, base }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProviderError, RateProvider};
+    use crate::exchange_rate::{ExchangeRate, ExchangeRateHistory};
+    use crate::CommodityTypeID;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    /// An [ExchangeRateHistory](ExchangeRateHistory) provides the
+    /// snapshot on the requested date, rebased if required.
+    #[test]
+    fn history_provider() {
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let aud = CommodityTypeID::from_str("AUD").unwrap();
+        let nzd = CommodityTypeID::from_str("NZD").unwrap();
+        let date = NaiveDate::from_ymd_opt(2020, 2, 7).unwrap();
+
+        let mut rates = BTreeMap::new();
+        rates.insert(aud, Decimal::from_str("1.6").unwrap());
+        let mut history = ExchangeRateHistory::new();
+        history
+            .insert(ExchangeRate {
+                date: Some(date),
+                obtained_datetime: None,
+                base: Some(usd),
+                rates,
+            })
+            .unwrap();
+
+        assert_eq!(Some(usd), history.exchange_rate(date, usd).unwrap().base);
+        assert_eq!(
+            Some(&Decimal::from_str("0.625").unwrap()),
+            history.exchange_rate(date, aud).unwrap().get_rate(&usd)
+        );
+
+        match history.exchange_rate(date, nzd) {
+            Err(ProviderError::NotAvailable { date: d, base }) => {
+                assert_eq!(date, d);
+                assert_eq!(nzd, base);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let next_date = NaiveDate::from_ymd_opt(2020, 2, 8).unwrap();
+        assert!(matches!(
+            history.exchange_rate(next_date, usd),
+            Err(ProviderError::NotAvailable { .. })
+        ));
+    }
+}
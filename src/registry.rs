@@ -0,0 +1,736 @@
+//! A registry of [CommodityType](crate::CommodityType)s, used to look up
+//! the metadata for a [CommodityTypeID](crate::CommodityTypeID).
+
+use crate::security::SecurityIdentifier;
+use crate::{CommodityType, CommodityTypeID};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use thiserror::Error;
+
+#[cfg(feature = "serde-support")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(any(feature = "json", feature = "toml"))]
+use std::path::Path;
+
+/// An error associated with functionality in the [registry](crate::registry) module.
+#[derive(Error, Debug)]
+pub enum CommodityTypeRegistryError {
+    #[error("A commodity type with id {0} is already registered.")]
+    Conflict(CommodityTypeID),
+    #[error("The id {0} is already registered as a commodity type or an alias.")]
+    AliasConflict(CommodityTypeID),
+    #[error("The commodity type with id {0} is not present in the registry.")]
+    CommodityTypeNotPresent(CommodityTypeID),
+    #[error("There was an error reading or writing a registry file.")]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "json")]
+    #[error("There was an error (de)serializing registry json.")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "toml")]
+    #[error("There was an error deserializing registry toml.")]
+    TomlDeserialize(#[from] toml::de::Error),
+    #[cfg(feature = "toml")]
+    #[error("There was an error serializing registry toml.")]
+    TomlSerialize(#[from] toml::ser::Error),
+}
+
+/// The contents of a [CommodityTypeRegistry](CommodityTypeRegistry),
+/// in the form in which it is saved to/loaded from a file.
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommodityTypeRegistryData {
+    /// Maps aliases to the id of the commodity type they refer to.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub aliases: BTreeMap<CommodityTypeID, CommodityTypeID>,
+    /// The registered commodity types.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub commodity_types: Vec<CommodityType>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct RegistryInner {
+    commodity_types: BTreeMap<CommodityTypeID, Arc<CommodityType>>,
+    aliases: BTreeMap<CommodityTypeID, CommodityTypeID>,
+}
+
+impl RegistryInner {
+    fn resolve(&self, id: &CommodityTypeID) -> Option<&Arc<CommodityType>> {
+        match self.commodity_types.get(id) {
+            Some(commodity_type) => Some(commodity_type),
+            None => self
+                .aliases
+                .get(id)
+                .and_then(|target| self.commodity_types.get(target)),
+        }
+    }
+
+    fn is_registered(&self, id: &CommodityTypeID) -> bool {
+        self.commodity_types.contains_key(id) || self.aliases.contains_key(id)
+    }
+
+    fn register(
+        &mut self,
+        commodity_type: CommodityType,
+    ) -> Result<(), CommodityTypeRegistryError> {
+        if self.is_registered(&commodity_type.id) {
+            return Err(CommodityTypeRegistryError::Conflict(commodity_type.id));
+        }
+
+        self.commodity_types
+            .insert(commodity_type.id, Arc::new(commodity_type));
+        Ok(())
+    }
+
+    fn add_alias(
+        &mut self,
+        alias: CommodityTypeID,
+        target: CommodityTypeID,
+    ) -> Result<(), CommodityTypeRegistryError> {
+        if self.is_registered(&alias) {
+            return Err(CommodityTypeRegistryError::AliasConflict(alias));
+        }
+
+        // aliases always point directly at a registered commodity type.
+        let target = match self.resolve(&target) {
+            Some(commodity_type) => commodity_type.id,
+            None => return Err(CommodityTypeRegistryError::CommodityTypeNotPresent(target)),
+        };
+
+        self.aliases.insert(alias, target);
+        Ok(())
+    }
+}
+
+/// A registry of [CommodityType](CommodityType)s, keyed by their
+/// [CommodityTypeID](CommodityTypeID).
+///
+/// The registry can be shared between threads (for example using an
+/// [Arc](std::sync::Arc)), all methods take `&self`.
+///
+/// # Example
+/// ```
+/// # use commodity::registry::CommodityTypeRegistry;
+/// use commodity::{CommodityType, CommodityTypeID};
+/// use std::str::FromStr;
+///
+/// let registry = CommodityTypeRegistry::new();
+/// let aud = CommodityType::from_str("AUD", "Australian dollar")
+///     .unwrap()
+///     .with_symbol("A$");
+/// registry.register(aud.clone()).unwrap();
+///
+/// let aud_id = CommodityTypeID::from_str("AUD").unwrap();
+/// assert_eq!(aud, *registry.get(&aud_id).unwrap());
+/// assert_eq!(aud, *registry.find_by_name("australian dollar").unwrap());
+/// assert_eq!(aud, *registry.find_by_symbol("A$")[0]);
+///
+/// // registering a commodity type with the same id again is an error
+/// assert!(registry.register(aud).is_err());
+/// ```
+#[derive(Debug, Default)]
+pub struct CommodityTypeRegistry {
+    inner: RwLock<RegistryInner>,
+}
+
+impl CommodityTypeRegistry {
+    /// Create a new, empty [CommodityTypeRegistry](CommodityTypeRegistry).
+    pub fn new() -> CommodityTypeRegistry {
+        CommodityTypeRegistry::default()
+    }
+
+    /// Create a new [CommodityTypeRegistry](CommodityTypeRegistry),
+    /// seeded with all the currencies in the `ISO4217` database.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::registry::CommodityTypeRegistry;
+    /// use commodity::CommodityTypeID;
+    /// use std::str::FromStr;
+    ///
+    /// let registry = CommodityTypeRegistry::with_iso4217();
+    /// let nzd = registry.get(&CommodityTypeID::from_str("NZD").unwrap()).unwrap();
+    /// assert_eq!(Some(String::from("New Zealand dollar")), nzd.name);
+    /// ```
+    #[cfg(feature = "iso4217")]
+    pub fn with_iso4217() -> CommodityTypeRegistry {
+        let registry = CommodityTypeRegistry::new();
+        registry.register_iso4217();
+        registry
+    }
+
+    /// Register all the currencies in the `ISO4217` database which
+    /// have not already been registered.
+    #[cfg(feature = "iso4217")]
+    pub fn register_iso4217(&self) {
+        let mut inner = self.inner.write().unwrap();
+        for commodity_type in crate::all_iso4217_currencies() {
+            if !inner.is_registered(&commodity_type.id) {
+                inner
+                    .commodity_types
+                    .insert(commodity_type.id, Arc::new(commodity_type));
+            }
+        }
+    }
+
+    /// Register a [CommodityType](CommodityType). Returns a
+    /// [CommodityTypeRegistryError::Conflict](CommodityTypeRegistryError::Conflict)
+    /// if a commodity type or alias with the same id has already been
+    /// registered.
+    pub fn register(
+        &self,
+        commodity_type: CommodityType,
+    ) -> Result<(), CommodityTypeRegistryError> {
+        self.inner.write().unwrap().register(commodity_type)
+    }
+
+    /// Register a [CommodityType](CommodityType), replacing any
+    /// existing commodity type with the same id. Returns the replaced
+    /// commodity type (if there was one).
+    pub fn register_or_replace(&self, commodity_type: CommodityType) -> Option<Arc<CommodityType>> {
+        let mut inner = self.inner.write().unwrap();
+        inner.aliases.remove(&commodity_type.id);
+        inner
+            .commodity_types
+            .insert(commodity_type.id, Arc::new(commodity_type))
+    }
+
+    /// Remove the [CommodityType](CommodityType) with the specified
+    /// id from the registry, along with any of its aliases.
+    pub fn unregister(&self, id: &CommodityTypeID) -> Option<Arc<CommodityType>> {
+        let mut inner = self.inner.write().unwrap();
+        let removed = inner.commodity_types.remove(id);
+        if removed.is_some() {
+            inner.aliases.retain(|_, target| target != id);
+        }
+        removed
+    }
+
+    /// Register `alias` as an alternative id for the already
+    /// registered commodity type with the id `target`.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::registry::CommodityTypeRegistry;
+    /// use commodity::{CommodityType, CommodityTypeID};
+    /// use std::str::FromStr;
+    ///
+    /// let registry = CommodityTypeRegistry::new();
+    /// registry.register(CommodityType::from_str("BTC", "Bitcoin").unwrap()).unwrap();
+    ///
+    /// let btc = CommodityTypeID::from_str("BTC").unwrap();
+    /// let xbt = CommodityTypeID::from_str("XBT").unwrap();
+    /// registry.add_alias(xbt, btc).unwrap();
+    ///
+    /// assert_eq!(btc, registry.get(&xbt).unwrap().id);
+    /// assert_eq!(Some(btc), registry.resolve_id(&xbt));
+    /// ```
+    pub fn add_alias(
+        &self,
+        alias: CommodityTypeID,
+        target: CommodityTypeID,
+    ) -> Result<(), CommodityTypeRegistryError> {
+        self.inner.write().unwrap().add_alias(alias, target)
+    }
+
+    /// Get the [CommodityType](CommodityType) with the specified id
+    /// (or alias).
+    pub fn get(&self, id: &CommodityTypeID) -> Option<Arc<CommodityType>> {
+        self.inner.read().unwrap().resolve(id).cloned()
+    }
+
+    /// Resolve an id (or alias) to the id of the registered
+    /// [CommodityType](CommodityType).
+    pub fn resolve_id(&self, id: &CommodityTypeID) -> Option<CommodityTypeID> {
+        self.inner
+            .read()
+            .unwrap()
+            .resolve(id)
+            .map(|commodity_type| commodity_type.id)
+    }
+
+    /// Returns true if there is a [CommodityType](CommodityType) with
+    /// the specified id (or alias) in the registry.
+    pub fn contains(&self, id: &CommodityTypeID) -> bool {
+        self.inner.read().unwrap().resolve(id).is_some()
+    }
+
+    /// Find all the [CommodityType](CommodityType)s with a `symbol` or
+    /// `narrow_symbol` matching the specified `symbol`.
+    pub fn find_by_symbol(&self, symbol: &str) -> Vec<Arc<CommodityType>> {
+        self.inner
+            .read()
+            .unwrap()
+            .commodity_types
+            .values()
+            .filter(|commodity_type| {
+                commodity_type.symbol.as_deref() == Some(symbol)
+                    || commodity_type.narrow_symbol.as_deref() == Some(symbol)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Find the first [CommodityType](CommodityType) (ordered by id)
+    /// with a security identifier which
+    /// [matches](crate::security::SecurityIdentifier::matches) the
+    /// specified `identifier`. An [Isin](crate::security::Isin) will
+    /// also find a commodity type with the corresponding
+    /// [Cusip](crate::security::Cusip) or
+    /// [Sedol](crate::security::Sedol), and vice versa.
+    pub fn find_by_identifier(
+        &self,
+        identifier: &SecurityIdentifier,
+    ) -> Option<Arc<CommodityType>> {
+        self.inner
+            .read()
+            .unwrap()
+            .commodity_types
+            .values()
+            .find(|commodity_type| commodity_type.has_identifier(identifier))
+            .cloned()
+    }
+
+    /// Find the first [CommodityType](CommodityType) (ordered by id)
+    /// with a `name` matching the specified `name` (case
+    /// insensitive).
+    pub fn find_by_name(&self, name: &str) -> Option<Arc<CommodityType>> {
+        let name = name.to_lowercase();
+        self.inner
+            .read()
+            .unwrap()
+            .commodity_types
+            .values()
+            .find(|commodity_type| match &commodity_type.name {
+                Some(commodity_type_name) => commodity_type_name.to_lowercase() == name,
+                None => false,
+            })
+            .cloned()
+    }
+
+    /// Find the [CommodityType](CommodityType) which has the
+    /// specified id (or alias) as itself or one of its
+    /// [Denomination](crate::Denomination)s.
+    pub fn find_family(&self, id: &CommodityTypeID) -> Option<Arc<CommodityType>> {
+        let inner = self.inner.read().unwrap();
+        if let Some(commodity_type) = inner.resolve(id) {
+            return Some(commodity_type.clone());
+        }
+
+        inner
+            .commodity_types
+            .values()
+            .find(|commodity_type| commodity_type.has_denomination(id))
+            .cloned()
+    }
+
+    /// All the [CommodityType](CommodityType)s in the registry, ordered by id.
+    pub fn all(&self) -> Vec<Arc<CommodityType>> {
+        self.inner
+            .read()
+            .unwrap()
+            .commodity_types
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// The number of [CommodityType](CommodityType)s in the registry
+    /// (not including aliases).
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().commodity_types.len()
+    }
+
+    /// Returns true if there are no [CommodityType](CommodityType)s in
+    /// the registry.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Obtain a copy of the contents of this registry.
+    pub fn data(&self) -> CommodityTypeRegistryData {
+        let inner = self.inner.read().unwrap();
+        CommodityTypeRegistryData {
+            aliases: inner.aliases.clone(),
+            commodity_types: inner
+                .commodity_types
+                .values()
+                .map(|commodity_type| CommodityType::clone(commodity_type))
+                .collect(),
+        }
+    }
+
+    /// Register the commodity types and aliases in `data`. Returns an
+    /// error upon the first conflict, in which case none of the
+    /// commodity types or aliases in `data` are registered.
+    pub fn register_data(
+        &self,
+        data: CommodityTypeRegistryData,
+    ) -> Result<(), CommodityTypeRegistryError> {
+        let mut inner = self.inner.write().unwrap();
+        // the new state is built aside, and only swapped in once all
+        // of `data` has been registered without a conflict.
+        let mut updated = inner.clone();
+
+        for commodity_type in data.commodity_types {
+            updated.register(commodity_type)?;
+        }
+
+        for (alias, target) in data.aliases {
+            updated.add_alias(alias, target)?;
+        }
+
+        *inner = updated;
+        Ok(())
+    }
+
+    /// Register the commodity types and aliases from a json string.
+    #[cfg(feature = "json")]
+    pub fn load_json_str(&self, json: &str) -> Result<(), CommodityTypeRegistryError> {
+        self.register_data(serde_json::from_str(json)?)
+    }
+
+    /// Register the commodity types and aliases from a json file.
+    #[cfg(feature = "json")]
+    pub fn load_json_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(), CommodityTypeRegistryError> {
+        self.load_json_str(&std::fs::read_to_string(path)?)
+    }
+
+    /// Serialize the contents of this registry to a json string.
+    #[cfg(feature = "json")]
+    pub fn to_json_string(&self) -> Result<String, CommodityTypeRegistryError> {
+        Ok(serde_json::to_string_pretty(&self.data())?)
+    }
+
+    /// Save the contents of this registry to a json file.
+    #[cfg(feature = "json")]
+    pub fn save_json_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(), CommodityTypeRegistryError> {
+        Ok(std::fs::write(path, self.to_json_string()?)?)
+    }
+
+    /// Register the commodity types and aliases from a toml string.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::registry::CommodityTypeRegistry;
+    /// use commodity::{CommodityKind, CommodityTypeID};
+    /// use std::str::FromStr;
+    ///
+    /// let registry = CommodityTypeRegistry::new();
+    /// registry.load_toml_str(r#"
+    ///     [aliases]
+    ///     XBT = "BTC"
+    ///
+    ///     [[commodity_types]]
+    ///     id = "BTC"
+    ///     name = "Bitcoin"
+    ///     kind = "Crypto"
+    ///     minor_units = 8
+    /// "#).unwrap();
+    ///
+    /// let btc = registry.get(&CommodityTypeID::from_str("XBT").unwrap()).unwrap();
+    /// assert_eq!(Some(CommodityKind::Crypto), btc.kind);
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn load_toml_str(&self, toml: &str) -> Result<(), CommodityTypeRegistryError> {
+        self.register_data(toml::from_str(toml)?)
+    }
+
+    /// Register the commodity types and aliases from a toml file.
+    #[cfg(feature = "toml")]
+    pub fn load_toml_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(), CommodityTypeRegistryError> {
+        self.load_toml_str(&std::fs::read_to_string(path)?)
+    }
+
+    /// Serialize the contents of this registry to a toml string.
+    #[cfg(feature = "toml")]
+    pub fn to_toml_string(&self) -> Result<String, CommodityTypeRegistryError> {
+        // toml requires plain values to be written before tables, which
+        // serializing through a toml::Value takes care of, regardless
+        // of the order in which the fields are declared.
+        let value = toml::Value::try_from(self.data())?;
+        Ok(toml::to_string(&value)?)
+    }
+
+    /// Save the contents of this registry to a toml file.
+    #[cfg(feature = "toml")]
+    pub fn save_toml_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(), CommodityTypeRegistryError> {
+        Ok(std::fs::write(path, self.to_toml_string()?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommodityTypeRegistry, CommodityTypeRegistryError};
+    use crate::{CommodityType, CommodityTypeID};
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    fn test_registry() -> CommodityTypeRegistry {
+        let registry = CommodityTypeRegistry::new();
+        registry
+            .register(
+                CommodityType::from_str("USD", "United States dollar")
+                    .unwrap()
+                    .with_symbol("US$")
+                    .with_narrow_symbol("$"),
+            )
+            .unwrap();
+        registry
+            .register(
+                CommodityType::from_str("AUD", "Australian dollar")
+                    .unwrap()
+                    .with_symbol("A$")
+                    .with_narrow_symbol("$"),
+            )
+            .unwrap();
+        registry
+            .register(
+                CommodityType::from_str("BTC", "Bitcoin")
+                    .unwrap()
+                    .with_denomination(CommodityTypeID::from_str("SAT").unwrap(), 8),
+            )
+            .unwrap();
+        registry
+            .add_alias(
+                CommodityTypeID::from_str("XBT").unwrap(),
+                CommodityTypeID::from_str("BTC").unwrap(),
+            )
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn register_conflicts() {
+        let registry = test_registry();
+        let usd = CommodityTypeID::from_str("USD").unwrap();
+        let btc = CommodityTypeID::from_str("BTC").unwrap();
+        let xbt = CommodityTypeID::from_str("XBT").unwrap();
+
+        match registry.register(CommodityType::new(usd, None)) {
+            Err(CommodityTypeRegistryError::Conflict(id)) => assert_eq!(usd, id),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        match registry.register(CommodityType::new(xbt, None)) {
+            Err(CommodityTypeRegistryError::Conflict(id)) => assert_eq!(xbt, id),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        match registry.add_alias(usd, btc) {
+            Err(CommodityTypeRegistryError::AliasConflict(id)) => assert_eq!(usd, id),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // the original usd is still registered
+        assert_eq!(
+            Some(String::from("United States dollar")),
+            registry.get(&usd).unwrap().name
+        );
+    }
+
+    #[test]
+    fn lookup() {
+        let registry = test_registry();
+        assert_eq!(3, registry.len());
+
+        let dollars: Vec<String> = registry
+            .find_by_symbol("$")
+            .iter()
+            .map(|commodity_type| commodity_type.id.to_string())
+            .collect();
+        assert_eq!(vec!["AUD", "USD"], dollars);
+        assert_eq!("USD", registry.find_by_symbol("US$")[0].id);
+        assert!(registry.find_by_symbol("€").is_empty());
+
+        assert_eq!("BTC", registry.find_by_name("BITCOIN").unwrap().id);
+        assert!(registry.find_by_name("Ethereum").is_none());
+
+        let sat = CommodityTypeID::from_str("SAT").unwrap();
+        assert_eq!("BTC", registry.find_family(&sat).unwrap().id);
+        assert!(!registry.contains(&sat));
+
+        let btc = CommodityTypeID::from_str("BTC").unwrap();
+        let xbt = CommodityTypeID::from_str("XBT").unwrap();
+        registry.unregister(&btc).unwrap();
+        assert!(!registry.contains(&btc));
+        assert!(!registry.contains(&xbt));
+    }
+
+    #[test]
+    fn lookup_by_identifier() {
+        use crate::security::{Cusip, Figi, Isin, SecurityIdentifier, Sedol};
+
+        let registry = test_registry();
+        registry
+            .register(
+                CommodityType::from_str("AAPL", "Apple Inc.")
+                    .unwrap()
+                    .with_identifier(Isin::from_str("US0378331005").unwrap())
+                    .with_identifier(Figi::from_str("BBG000B9XRY4").unwrap()),
+            )
+            .unwrap();
+        registry
+            .register(
+                CommodityType::from_str("BA", "BAE Systems")
+                    .unwrap()
+                    .with_identifier(Sedol::from_str("0263494").unwrap()),
+            )
+            .unwrap();
+
+        let cusip = SecurityIdentifier::from(Cusip::from_str("037833100").unwrap());
+        assert_eq!("AAPL", registry.find_by_identifier(&cusip).unwrap().id);
+        let figi = SecurityIdentifier::from(Figi::from_str("BBG000B9XRY4").unwrap());
+        assert_eq!("AAPL", registry.find_by_identifier(&figi).unwrap().id);
+        let isin = SecurityIdentifier::from(Isin::from_str("GB0002634946").unwrap());
+        assert_eq!("BA", registry.find_by_identifier(&isin).unwrap().id);
+
+        let msft = SecurityIdentifier::from(Isin::from_str("US5949181045").unwrap());
+        assert!(registry.find_by_identifier(&msft).is_none());
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let registry = Arc::new(CommodityTypeRegistry::new());
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let registry = registry.clone();
+                std::thread::spawn(move || {
+                    let id = format!("T{}", i);
+                    registry
+                        .register(CommodityType::from_str(&id, "").unwrap())
+                        .unwrap();
+                    assert!(registry.contains(&CommodityTypeID::from_str(&id).unwrap()));
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(4, registry.len());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_file() {
+        let registry = test_registry();
+        let path = std::env::temp_dir().join(format!(
+            "commodity_registry_test_{}.json",
+            std::process::id()
+        ));
+        registry.save_json_file(&path).unwrap();
+
+        let loaded = CommodityTypeRegistry::new();
+        loaded.load_json_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(registry.data(), loaded.data());
+
+        // loading the same file again conflicts
+        match loaded.load_json_str(&registry.to_json_string().unwrap()) {
+            Err(CommodityTypeRegistryError::Conflict(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn register_data_conflict() {
+        use super::CommodityTypeRegistryData;
+
+        let registry = test_registry();
+        let before = registry.data();
+
+        let eur = CommodityType::from_str("EUR", "Euro").unwrap();
+        let usd = CommodityType::from_str("USD", "").unwrap();
+        let data = CommodityTypeRegistryData {
+            aliases: Default::default(),
+            commodity_types: vec![eur.clone(), usd],
+        };
+        match registry.register_data(data) {
+            Err(CommodityTypeRegistryError::Conflict(id)) => assert_eq!("USD", id),
+            other => panic!("unexpected result {:?}", other),
+        }
+        // the eur registered before the conflict was not kept
+        assert!(!registry.contains(&eur.id));
+
+        // a conflicting alias also leaves the registry untouched
+        let mut aliases = std::collections::BTreeMap::new();
+        aliases.insert(CommodityTypeID::from_str("AUD").unwrap(), eur.id);
+        let data = CommodityTypeRegistryData {
+            aliases,
+            commodity_types: vec![eur.clone()],
+        };
+        match registry.register_data(data) {
+            Err(CommodityTypeRegistryError::AliasConflict(id)) => assert_eq!("AUD", id),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(!registry.contains(&eur.id));
+        assert_eq!(before, registry.data());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_round_trip() {
+        use crate::security::Isin;
+        use crate::unit::Unit;
+
+        let registry = test_registry();
+        registry
+            .register(
+                CommodityType::from_str("XAU", "Gold")
+                    .unwrap()
+                    .with_unit(Unit::TroyOunce)
+                    .with_denomination(CommodityTypeID::from_str("MXAU").unwrap(), 3),
+            )
+            .unwrap();
+        registry
+            .register(
+                CommodityType::from_str("AAPL", "Apple Inc.")
+                    .unwrap()
+                    .with_identifier(Isin::from_str("US0378331005").unwrap()),
+            )
+            .unwrap();
+        let loaded = CommodityTypeRegistry::new();
+        loaded
+            .load_toml_str(&registry.to_toml_string().unwrap())
+            .unwrap();
+
+        assert_eq!(registry.data(), loaded.data());
+        let usd = loaded
+            .get(&CommodityTypeID::from_str("USD").unwrap())
+            .unwrap();
+        assert_eq!(Some(String::from("$")), usd.narrow_symbol);
+    }
+
+    #[cfg(feature = "iso4217")]
+    #[test]
+    fn iso4217_seeding() {
+        let registry = test_registry();
+        registry.register_iso4217();
+
+        // existing registrations are kept
+        let usd = registry
+            .get(&CommodityTypeID::from_str("USD").unwrap())
+            .unwrap();
+        assert_eq!(Some(String::from("US$")), usd.symbol);
+
+        let eur = registry
+            .get(&CommodityTypeID::from_str("EUR").unwrap())
+            .unwrap();
+        assert_eq!(Some(978), eur.numeric_code);
+    }
+}
@@ -0,0 +1,768 @@
+//! Standard identifiers for securities (such as stocks and bonds)
+//! which are modelled as commodities: [Isin](Isin), [Cusip](Cusip),
+//! [Sedol](Sedol) and [Figi](Figi), each validated using its check
+//! digit.
+
+use arrayvec::ArrayString;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[cfg(feature = "serde-support")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The type used to store the value of a security identifier.
+type SecurityIdentifierArray = ArrayString<[u8; 12]>;
+
+/// An error associated with functionality in the [security](crate::security) module.
+#[derive(Error, Debug, PartialEq)]
+pub enum SecurityError {
+    #[error("The {kind} {id:?} is invalid, it should be {length} characters long.")]
+    InvalidLength {
+        kind: SecurityIdentifierKind,
+        id: String,
+        length: usize,
+    },
+    #[error("The {kind} {id:?} contains an invalid character.")]
+    InvalidCharacter {
+        kind: SecurityIdentifierKind,
+        id: String,
+    },
+    #[error("The {kind} {id:?} has the invalid prefix {prefix:?}.")]
+    InvalidPrefix {
+        kind: SecurityIdentifierKind,
+        id: String,
+        prefix: String,
+    },
+    #[error("The {kind} {id:?} has an invalid check digit, expected {expected}.")]
+    InvalidCheckDigit {
+        kind: SecurityIdentifierKind,
+        id: String,
+        expected: char,
+    },
+    #[error("The {0} {1} cannot be converted to a {2}.")]
+    NoConversion(SecurityIdentifierKind, String, SecurityIdentifierKind),
+    #[error("The provided string {0} is not a known kind of security identifier, expected one of ISIN, CUSIP, SEDOL or FIGI.")]
+    InvalidKind(String),
+    #[error("The provided string {0} is invalid, it should be a kind followed by an identifier. e.g. ISIN US0378331005")]
+    InvalidIdentifierString(String),
+}
+
+/// The kind of a [SecurityIdentifier](SecurityIdentifier).
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SecurityIdentifierKind {
+    /// International Securities Identification Number (`ISO6166`).
+    Isin,
+    /// Committee on Uniform Securities Identification Procedures
+    /// number, used in the United States and Canada.
+    Cusip,
+    /// Stock Exchange Daily Official List number, used in the United
+    /// Kingdom and Ireland.
+    Sedol,
+    /// Financial Instrument Global Identifier.
+    Figi,
+}
+
+impl SecurityIdentifierKind {
+    /// The number of characters in identifiers of this kind
+    /// (including the check digit).
+    pub fn length(&self) -> usize {
+        match self {
+            SecurityIdentifierKind::Isin => 12,
+            SecurityIdentifierKind::Cusip => 9,
+            SecurityIdentifierKind::Sedol => 7,
+            SecurityIdentifierKind::Figi => 12,
+        }
+    }
+}
+
+impl fmt::Display for SecurityIdentifierKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SecurityIdentifierKind::Isin => "ISIN",
+            SecurityIdentifierKind::Cusip => "CUSIP",
+            SecurityIdentifierKind::Sedol => "SEDOL",
+            SecurityIdentifierKind::Figi => "FIGI",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The value of an alphanumeric character used in check digit
+/// calculations, `0-9` for digits and `10-35` for the letters `A-Z`.
+fn char_value(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' | 'A'..='Z' => c.to_digit(36),
+        _ => None,
+    }
+}
+
+/// Convert a check digit value (`0-9`) into a `char`.
+fn check_char(value: u32) -> char {
+    std::char::from_digit(value, 10).expect("check digit should be between 0 and 9")
+}
+
+/// Validate the length and characters of an identifier of the
+/// specified `kind`, and check that its check digit (the last
+/// character) matches the one calculated by `check_digit`.
+fn validate(
+    id: &str,
+    kind: SecurityIdentifierKind,
+    valid_char: fn(usize, char) -> bool,
+    check_digit: fn(&str) -> char,
+) -> Result<SecurityIdentifierArray, SecurityError> {
+    if !id.is_ascii() {
+        return Err(SecurityError::InvalidCharacter {
+            kind,
+            id: String::from(id),
+        });
+    }
+
+    if id.len() != kind.length() {
+        return Err(SecurityError::InvalidLength {
+            kind,
+            id: String::from(id),
+            length: kind.length(),
+        });
+    }
+
+    let (payload, check) = id.split_at(kind.length() - 1);
+
+    if !payload.chars().enumerate().all(|(i, c)| valid_char(i, c)) {
+        return Err(SecurityError::InvalidCharacter {
+            kind,
+            id: String::from(id),
+        });
+    }
+
+    let expected = check_digit(payload);
+    if !check.starts_with(expected) {
+        return Err(SecurityError::InvalidCheckDigit {
+            kind,
+            id: String::from(id),
+            expected,
+        });
+    }
+
+    Ok(SecurityIdentifierArray::from(id).expect("length has already been checked"))
+}
+
+/// Implements the traits common to all the security identifier types.
+macro_rules! security_identifier {
+    ($name:ident) => {
+        impl $name {
+            /// The identifier as a string.
+            pub fn as_str(&self) -> &str {
+                self.0.as_str()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        impl From<$name> for SecurityIdentifier {
+            fn from(id: $name) -> SecurityIdentifier {
+                SecurityIdentifier::$name(id)
+            }
+        }
+
+        #[cfg(feature = "serde-support")]
+        impl Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        #[cfg(feature = "serde-support")]
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<$name, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let id = String::deserialize(deserializer)?;
+                $name::from_str(&id).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+/// An International Securities Identification Number (`ISO6166`),
+/// e.g. `US0378331005`. Consists of a two letter country code, a nine
+/// character national security identifier, and a check digit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Isin(SecurityIdentifierArray);
+
+security_identifier!(Isin);
+
+/// Calculate the check digit of the first 11 characters of an ISIN,
+/// using the Luhn algorithm on the digits obtained by expanding each
+/// letter into its two digit value.
+fn isin_check_digit(payload: &str) -> char {
+    let digits: Vec<u32> = payload
+        .chars()
+        .filter_map(char_value)
+        .flat_map(|value| {
+            if value >= 10 {
+                vec![value / 10, value % 10]
+            } else {
+                vec![value]
+            }
+        })
+        .collect();
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, digit)| {
+            if i % 2 == 0 {
+                let doubled = digit * 2;
+                doubled / 10 + doubled % 10
+            } else {
+                *digit
+            }
+        })
+        .sum();
+
+    check_char((10 - sum % 10) % 10)
+}
+
+/// The countries whose ISINs are formed from a [Cusip](Cusip).
+const CUSIP_COUNTRIES: &[&str] = &["US", "CA"];
+
+/// The countries whose ISINs are formed from a [Sedol](Sedol).
+const SEDOL_COUNTRIES: &[&str] = &["GB", "IE"];
+
+impl Isin {
+    /// The `ISO3166` alpha2 code of the country which issued this ISIN.
+    pub fn country(&self) -> &str {
+        &self.0[0..2]
+    }
+
+    /// The national security identifier contained in this ISIN.
+    pub fn nsin(&self) -> &str {
+        &self.0[2..11]
+    }
+
+    /// Create an ISIN for a national security identifier
+    /// (`nsin`), calculating the check digit. The `nsin` is padded
+    /// with leading zeros to 9 characters.
+    fn from_nsin(country: &str, nsin: &str) -> Result<Isin, SecurityError> {
+        let payload = format!("{}{:0>9}", country, nsin);
+        let id = format!("{}{}", payload, isin_check_digit(&payload));
+        Isin::from_str(&id)
+    }
+
+    /// Create the ISIN for a [Cusip](Cusip), issued in the specified
+    /// `country` (`"US"` or `"CA"`).
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::security::{Cusip, Isin};
+    /// use std::str::FromStr;
+    ///
+    /// let cusip = Cusip::from_str("037833100").unwrap();
+    /// let isin = Isin::from_cusip(&cusip, "US").unwrap();
+    /// assert_eq!("US0378331005", isin.as_str());
+    /// assert_eq!(cusip, isin.to_cusip().unwrap());
+    /// ```
+    pub fn from_cusip(cusip: &Cusip, country: &str) -> Result<Isin, SecurityError> {
+        if !CUSIP_COUNTRIES.contains(&country) {
+            return Err(SecurityError::NoConversion(
+                SecurityIdentifierKind::Cusip,
+                cusip.to_string(),
+                SecurityIdentifierKind::Isin,
+            ));
+        }
+        Isin::from_nsin(country, cusip.as_str())
+    }
+
+    /// Create the ISIN for a [Sedol](Sedol), issued in the specified
+    /// `country` (`"GB"` or `"IE"`).
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::security::{Isin, Sedol};
+    /// use std::str::FromStr;
+    ///
+    /// let sedol = Sedol::from_str("0263494").unwrap();
+    /// let isin = Isin::from_sedol(&sedol, "GB").unwrap();
+    /// assert_eq!("GB0002634946", isin.as_str());
+    /// assert_eq!(sedol, isin.to_sedol().unwrap());
+    /// ```
+    pub fn from_sedol(sedol: &Sedol, country: &str) -> Result<Isin, SecurityError> {
+        if !SEDOL_COUNTRIES.contains(&country) {
+            return Err(SecurityError::NoConversion(
+                SecurityIdentifierKind::Sedol,
+                sedol.to_string(),
+                SecurityIdentifierKind::Isin,
+            ));
+        }
+        Isin::from_nsin(country, sedol.as_str())
+    }
+
+    /// Obtain the [Cusip](Cusip) contained in this ISIN, if it was
+    /// issued in a country which uses CUSIPs.
+    pub fn to_cusip(&self) -> Result<Cusip, SecurityError> {
+        if !CUSIP_COUNTRIES.contains(&self.country()) {
+            return Err(self.no_conversion(SecurityIdentifierKind::Cusip));
+        }
+        Cusip::from_str(self.nsin()).map_err(|_| self.no_conversion(SecurityIdentifierKind::Cusip))
+    }
+
+    /// Obtain the [Sedol](Sedol) contained in this ISIN, if it was
+    /// issued in a country which uses SEDOLs.
+    pub fn to_sedol(&self) -> Result<Sedol, SecurityError> {
+        let nsin = self.nsin();
+        if !SEDOL_COUNTRIES.contains(&self.country()) || !nsin.starts_with("00") {
+            return Err(self.no_conversion(SecurityIdentifierKind::Sedol));
+        }
+        Sedol::from_str(&nsin[2..]).map_err(|_| self.no_conversion(SecurityIdentifierKind::Sedol))
+    }
+
+    fn no_conversion(&self, to: SecurityIdentifierKind) -> SecurityError {
+        SecurityError::NoConversion(SecurityIdentifierKind::Isin, self.to_string(), to)
+    }
+}
+
+impl FromStr for Isin {
+    type Err = SecurityError;
+
+    /// Parse and validate an [Isin](Isin).
+    fn from_str(id: &str) -> Result<Isin, SecurityError> {
+        validate(
+            id,
+            SecurityIdentifierKind::Isin,
+            |i, c| {
+                if i < 2 {
+                    c.is_ascii_uppercase()
+                } else {
+                    char_value(c).is_some()
+                }
+            },
+            isin_check_digit,
+        )
+        .map(Isin)
+    }
+}
+
+/// A CUSIP number, e.g. `037833100`. Consists of a six character
+/// issuer code, a two character issue number, and a check digit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cusip(SecurityIdentifierArray);
+
+security_identifier!(Cusip);
+
+/// The value of a character in a CUSIP, which also permits `*`,
+/// `@` and `#` for private placements.
+fn cusip_char_value(c: char) -> Option<u32> {
+    match c {
+        '*' => Some(36),
+        '@' => Some(37),
+        '#' => Some(38),
+        _ => char_value(c),
+    }
+}
+
+/// Calculate the check digit of the first 8 characters of a CUSIP.
+fn cusip_check_digit(payload: &str) -> char {
+    let sum: u32 = payload
+        .chars()
+        .filter_map(cusip_char_value)
+        .enumerate()
+        .map(|(i, value)| {
+            let value = if i % 2 == 1 { value * 2 } else { value };
+            value / 10 + value % 10
+        })
+        .sum();
+
+    check_char((10 - sum % 10) % 10)
+}
+
+impl FromStr for Cusip {
+    type Err = SecurityError;
+
+    /// Parse and validate a [Cusip](Cusip).
+    fn from_str(id: &str) -> Result<Cusip, SecurityError> {
+        validate(
+            id,
+            SecurityIdentifierKind::Cusip,
+            |_, c| cusip_char_value(c).is_some(),
+            cusip_check_digit,
+        )
+        .map(Cusip)
+    }
+}
+
+/// A SEDOL number, e.g. `0263494`. Consists of six characters (which
+/// may not be vowels) and a check digit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Sedol(SecurityIdentifierArray);
+
+security_identifier!(Sedol);
+
+/// Returns true if the character is an uppercase consonant or a digit,
+/// as used in SEDOLs and FIGIs.
+fn is_consonant_or_digit(c: char) -> bool {
+    char_value(c).is_some() && !matches!(c, 'A' | 'E' | 'I' | 'O' | 'U')
+}
+
+/// Calculate the check digit of the first 6 characters of a SEDOL.
+fn sedol_check_digit(payload: &str) -> char {
+    const WEIGHTS: [u32; 6] = [1, 3, 1, 7, 3, 9];
+    let sum: u32 = payload
+        .chars()
+        .filter_map(char_value)
+        .zip(WEIGHTS.iter())
+        .map(|(value, weight)| value * weight)
+        .sum();
+
+    check_char((10 - sum % 10) % 10)
+}
+
+impl FromStr for Sedol {
+    type Err = SecurityError;
+
+    /// Parse and validate a [Sedol](Sedol).
+    fn from_str(id: &str) -> Result<Sedol, SecurityError> {
+        validate(
+            id,
+            SecurityIdentifierKind::Sedol,
+            |_, c| is_consonant_or_digit(c),
+            sedol_check_digit,
+        )
+        .map(Sedol)
+    }
+}
+
+/// A Financial Instrument Global Identifier, e.g. `BBG000B9XRY4`.
+/// Consists of a two character prefix, the letter `G`, eight
+/// characters (which may not be vowels) and a check digit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Figi(SecurityIdentifierArray);
+
+security_identifier!(Figi);
+
+/// Two character prefixes which are not permitted in a FIGI, to avoid
+/// confusion with ISINs.
+const INVALID_FIGI_PREFIXES: &[&str] = &["BS", "BM", "GG", "GB", "GH", "KY", "VG"];
+
+/// Calculate the check digit of the first 11 characters of a FIGI.
+fn figi_check_digit(payload: &str) -> char {
+    let sum: u32 = payload
+        .chars()
+        .filter_map(char_value)
+        .enumerate()
+        .map(|(i, value)| {
+            let value = if i % 2 == 1 { value * 2 } else { value };
+            value / 10 + value % 10
+        })
+        .sum();
+
+    check_char((10 - sum % 10) % 10)
+}
+
+impl FromStr for Figi {
+    type Err = SecurityError;
+
+    /// Parse and validate a [Figi](Figi).
+    fn from_str(id: &str) -> Result<Figi, SecurityError> {
+        if let Some(prefix) = INVALID_FIGI_PREFIXES
+            .iter()
+            .find(|prefix| id.starts_with(*prefix))
+        {
+            return Err(SecurityError::InvalidPrefix {
+                kind: SecurityIdentifierKind::Figi,
+                id: String::from(id),
+                prefix: String::from(*prefix),
+            });
+        }
+
+        validate(
+            id,
+            SecurityIdentifierKind::Figi,
+            |i, c| match i {
+                0 | 1 => c.is_ascii_uppercase() && is_consonant_or_digit(c),
+                2 => c == 'G',
+                _ => is_consonant_or_digit(c),
+            },
+            figi_check_digit,
+        )
+        .map(Figi)
+    }
+}
+
+/// A standard identifier for a security, which can be attached to a
+/// [CommodityType](crate::CommodityType) using
+/// [CommodityType::with_identifier()](crate::CommodityType::with_identifier).
+///
+/// Displayed and serialized as the kind followed by the identifier,
+/// e.g. `"ISIN US0378331005"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SecurityIdentifier {
+    Isin(Isin),
+    Cusip(Cusip),
+    Sedol(Sedol),
+    Figi(Figi),
+}
+
+impl SecurityIdentifier {
+    /// Parse a security identifier of the specified `kind`.
+    pub fn parse(
+        kind: SecurityIdentifierKind,
+        id: &str,
+    ) -> Result<SecurityIdentifier, SecurityError> {
+        Ok(match kind {
+            SecurityIdentifierKind::Isin => Isin::from_str(id)?.into(),
+            SecurityIdentifierKind::Cusip => Cusip::from_str(id)?.into(),
+            SecurityIdentifierKind::Sedol => Sedol::from_str(id)?.into(),
+            SecurityIdentifierKind::Figi => Figi::from_str(id)?.into(),
+        })
+    }
+
+    /// The kind of this identifier.
+    pub fn kind(&self) -> SecurityIdentifierKind {
+        match self {
+            SecurityIdentifier::Isin(_) => SecurityIdentifierKind::Isin,
+            SecurityIdentifier::Cusip(_) => SecurityIdentifierKind::Cusip,
+            SecurityIdentifier::Sedol(_) => SecurityIdentifierKind::Sedol,
+            SecurityIdentifier::Figi(_) => SecurityIdentifierKind::Figi,
+        }
+    }
+
+    /// Returns true if this identifier refers to the same security as
+    /// `other`, either because they are equal, or because one is an
+    /// [Isin](Isin) containing the other.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::security::{Cusip, Isin, SecurityIdentifier};
+    /// use std::str::FromStr;
+    ///
+    /// let isin = SecurityIdentifier::from(Isin::from_str("US0378331005").unwrap());
+    /// let cusip = SecurityIdentifier::from(Cusip::from_str("037833100").unwrap());
+    /// assert!(isin.matches(&cusip));
+    /// assert!(cusip.matches(&isin));
+    /// ```
+    pub fn matches(&self, other: &SecurityIdentifier) -> bool {
+        match (self, other) {
+            (SecurityIdentifier::Isin(isin), SecurityIdentifier::Cusip(cusip))
+            | (SecurityIdentifier::Cusip(cusip), SecurityIdentifier::Isin(isin)) => {
+                isin.to_cusip().as_ref() == Ok(cusip)
+            }
+            (SecurityIdentifier::Isin(isin), SecurityIdentifier::Sedol(sedol))
+            | (SecurityIdentifier::Sedol(sedol), SecurityIdentifier::Isin(isin)) => {
+                isin.to_sedol().as_ref() == Ok(sedol)
+            }
+            _ => self == other,
+        }
+    }
+
+    /// The identifier as a string.
+    pub fn as_str(&self) -> &str {
+        match self {
+            SecurityIdentifier::Isin(id) => id.as_str(),
+            SecurityIdentifier::Cusip(id) => id.as_str(),
+            SecurityIdentifier::Sedol(id) => id.as_str(),
+            SecurityIdentifier::Figi(id) => id.as_str(),
+        }
+    }
+}
+
+impl fmt::Display for SecurityIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind(), self.as_str())
+    }
+}
+
+impl FromStr for SecurityIdentifierKind {
+    type Err = SecurityError;
+
+    /// Parse a [SecurityIdentifierKind](SecurityIdentifierKind) from
+    /// its name, e.g. `"ISIN"`.
+    fn from_str(kind: &str) -> Result<SecurityIdentifierKind, SecurityError> {
+        match kind {
+            "ISIN" => Ok(SecurityIdentifierKind::Isin),
+            "CUSIP" => Ok(SecurityIdentifierKind::Cusip),
+            "SEDOL" => Ok(SecurityIdentifierKind::Sedol),
+            "FIGI" => Ok(SecurityIdentifierKind::Figi),
+            _ => Err(SecurityError::InvalidKind(String::from(kind))),
+        }
+    }
+}
+
+impl FromStr for SecurityIdentifier {
+    type Err = SecurityError;
+
+    /// Parse a [SecurityIdentifier](SecurityIdentifier) from its kind
+    /// followed by the identifier, e.g. `"ISIN US0378331005"`.
+    fn from_str(identifier: &str) -> Result<SecurityIdentifier, SecurityError> {
+        let elements: Vec<&str> = identifier.split_whitespace().collect();
+
+        if elements.len() != 2 {
+            return Err(SecurityError::InvalidIdentifierString(String::from(
+                identifier,
+            )));
+        }
+
+        SecurityIdentifier::parse(SecurityIdentifierKind::from_str(elements[0])?, elements[1])
+    }
+}
+
+#[cfg(feature = "serde-support")]
+impl Serialize for SecurityIdentifier {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(feature = "serde-support")]
+impl<'de> Deserialize<'de> for SecurityIdentifier {
+    fn deserialize<D>(deserializer: D) -> Result<SecurityIdentifier, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let identifier = String::deserialize(deserializer)?;
+        SecurityIdentifier::from_str(&identifier).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Cusip, Figi, Isin, SecurityError, SecurityIdentifier, SecurityIdentifierKind, Sedol,
+    };
+    use std::str::FromStr;
+
+    /// Validate the check digits of some well known identifiers.
+    #[test]
+    fn valid_identifiers() {
+        for isin in &[
+            "US0378331005",
+            "AU0000XVGZA3",
+            "GB0002634946",
+            "US5949181045",
+        ] {
+            assert_eq!(*isin, Isin::from_str(isin).unwrap().as_str());
+        }
+        for cusip in &["037833100", "594918104", "38259P508"] {
+            assert_eq!(*cusip, Cusip::from_str(cusip).unwrap().as_str());
+        }
+        for sedol in &["0263494", "2046251", "B0YBKJ7"] {
+            assert_eq!(*sedol, Sedol::from_str(sedol).unwrap().as_str());
+        }
+        for figi in &["BBG000B9XRY4", "BBG000BLNNH6", "BBG000BPH459"] {
+            assert_eq!(*figi, Figi::from_str(figi).unwrap().as_str());
+        }
+    }
+
+    /// Invalid identifiers are rejected with an appropriate error.
+    #[test]
+    fn invalid_identifiers() {
+        assert_eq!(
+            SecurityError::InvalidCheckDigit {
+                kind: SecurityIdentifierKind::Isin,
+                id: String::from("US0378331006"),
+                expected: '5',
+            },
+            Isin::from_str("US0378331006").unwrap_err()
+        );
+        assert_eq!(
+            SecurityError::InvalidLength {
+                kind: SecurityIdentifierKind::Cusip,
+                id: String::from("03783310"),
+                length: 9,
+            },
+            Cusip::from_str("03783310").unwrap_err()
+        );
+        assert_eq!(
+            SecurityError::InvalidCharacter {
+                kind: SecurityIdentifierKind::Sedol,
+                id: String::from("A263494"),
+            },
+            Sedol::from_str("A263494").unwrap_err()
+        );
+        assert!(Figi::from_str("BBG000B9XRY5").is_err());
+        assert_eq!(
+            SecurityError::InvalidPrefix {
+                kind: SecurityIdentifierKind::Figi,
+                id: String::from("GBG000B9XRY4"),
+                prefix: String::from("GB"),
+            },
+            Figi::from_str("GBG000B9XRY4").unwrap_err()
+        );
+        assert!(Isin::from_str("us0378331005").is_err());
+        // non-ascii characters are invalid, regardless of the length
+        for id in &["\u{c9}3783310", "037833\u{c9}"] {
+            assert_eq!(
+                SecurityError::InvalidCharacter {
+                    kind: SecurityIdentifierKind::Cusip,
+                    id: String::from(*id),
+                },
+                Cusip::from_str(id).unwrap_err()
+            );
+        }
+    }
+
+    /// Convert between ISINs and CUSIPs/SEDOLs.
+    #[test]
+    fn isin_conversions() {
+        let isin = Isin::from_str("US0378331005").unwrap();
+        assert_eq!("US", isin.country());
+        assert_eq!(
+            Cusip::from_str("037833100").unwrap(),
+            isin.to_cusip().unwrap()
+        );
+        assert_eq!(
+            SecurityError::NoConversion(
+                SecurityIdentifierKind::Isin,
+                String::from("US0378331005"),
+                SecurityIdentifierKind::Sedol
+            ),
+            isin.to_sedol().unwrap_err()
+        );
+
+        let sedol = Sedol::from_str("2046251").unwrap();
+        assert!(Isin::from_sedol(&sedol, "US").is_err());
+        let isin = Isin::from_sedol(&sedol, "IE").unwrap();
+        assert_eq!(sedol, isin.to_sedol().unwrap());
+        assert!(isin.to_cusip().is_err());
+
+        let identifier =
+            SecurityIdentifier::parse(SecurityIdentifierKind::Cusip, "38259P508").unwrap();
+        assert_eq!(SecurityIdentifierKind::Cusip, identifier.kind());
+        assert_eq!("CUSIP 38259P508", identifier.to_string());
+        assert_eq!(
+            identifier,
+            SecurityIdentifier::from_str("CUSIP 38259P508").unwrap()
+        );
+        assert_eq!(
+            SecurityError::InvalidIdentifierString(String::from("38259P508")),
+            SecurityIdentifier::from_str("38259P508").unwrap_err()
+        );
+    }
+
+    #[cfg(feature = "serde-support")]
+    #[test]
+    fn test_json_serialization() {
+        use serde_json;
+
+        let identifier = SecurityIdentifier::from(Isin::from_str("US0378331005").unwrap());
+        let serialized = serde_json::to_string(&identifier).unwrap();
+        assert_eq!(r#""ISIN US0378331005""#, serialized);
+        assert_eq!(identifier, serde_json::from_str(&serialized).unwrap());
+
+        assert!(serde_json::from_str::<SecurityIdentifier>(r#""ISIN US0378331006""#).is_err());
+        assert!(serde_json::from_str::<SecurityIdentifier>(r#""WKN 865985""#).is_err());
+    }
+}
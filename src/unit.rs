@@ -0,0 +1,423 @@
+//! Units of measure for physical commodities (such as gold, oil or
+//! wheat), and pricing quantities of them in a currency.
+
+use crate::{Commodity, CommodityType, CommodityTypeID};
+use rust_decimal::Decimal;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[cfg(feature = "serde-support")]
+use serde::{Deserialize, Serialize};
+
+/// An error associated with functionality in the [unit](crate::unit) module.
+#[derive(Error, Debug, PartialEq)]
+pub enum UnitError {
+    #[error("Cannot convert between the unit {0} ({0:?}) and the unit {1} ({1:?}) because they measure different dimensions.")]
+    IncompatibleUnits(Unit, Unit),
+    #[error("There was an overflow while converting {0} to {1}.")]
+    Overflow(Quantity, Unit),
+    #[error("The provided string {0} is not a known unit of measure.")]
+    InvalidUnit(String),
+    #[error("The provided string {0} is invalid, it should be a decimal followed by a unit. e.g. 1.234 ozt")]
+    InvalidQuantityString(String),
+    #[error("The commodity {0} is not of the commodity type {1}.")]
+    IncompatibleCommodityType(Commodity, CommodityTypeID),
+    #[error("The commodity type {0} does not have a unit of measure.")]
+    NoUnit(CommodityTypeID),
+    #[error("There was an add overflow while performing the addition {0}+{1}.")]
+    AddOverflow(Decimal, Decimal),
+    #[error("There was a subtract overflow while performing the subtraction {0}-{1}.")]
+    SubtractOverflow(Decimal, Decimal),
+}
+
+/// The physical dimension measured by a [Unit](Unit).
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    Mass,
+    Volume,
+}
+
+/// A unit of measure for a physical commodity.
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Unit {
+    /// Gram (`g`), the base unit of [Dimension::Mass](Dimension::Mass).
+    Gram,
+    /// Kilogram (`kg`).
+    Kilogram,
+    /// Metric tonne (`t`).
+    Tonne,
+    /// Troy ounce (`ozt`), used for precious metals.
+    TroyOunce,
+    /// Avoirdupois ounce (`oz`).
+    Ounce,
+    /// Avoirdupois pound (`lb`).
+    Pound,
+    /// Litre (`L`), the base unit of [Dimension::Volume](Dimension::Volume).
+    Litre,
+    /// Millilitre (`mL`).
+    Millilitre,
+    /// Cubic metre (`m3`).
+    CubicMetre,
+    /// US liquid gallon (`gal`).
+    Gallon,
+    /// Oil barrel (`bbl`), 42 US gallons.
+    Barrel,
+    /// US bushel (`bu`).
+    Bushel,
+}
+
+/// All the [Unit](Unit)s, used for parsing.
+const ALL_UNITS: &[Unit] = &[
+    Unit::Gram,
+    Unit::Kilogram,
+    Unit::Tonne,
+    Unit::TroyOunce,
+    Unit::Ounce,
+    Unit::Pound,
+    Unit::Litre,
+    Unit::Millilitre,
+    Unit::CubicMetre,
+    Unit::Gallon,
+    Unit::Barrel,
+    Unit::Bushel,
+];
+
+impl Unit {
+    /// The [Dimension](Dimension) measured by this unit.
+    pub fn dimension(&self) -> Dimension {
+        match self {
+            Unit::Gram
+            | Unit::Kilogram
+            | Unit::Tonne
+            | Unit::TroyOunce
+            | Unit::Ounce
+            | Unit::Pound => Dimension::Mass,
+            Unit::Litre
+            | Unit::Millilitre
+            | Unit::CubicMetre
+            | Unit::Gallon
+            | Unit::Barrel
+            | Unit::Bushel => Dimension::Volume,
+        }
+    }
+
+    /// The exact size of this unit, in the base unit of its
+    /// [Dimension](Dimension) (grams or litres).
+    pub fn factor(&self) -> Decimal {
+        match self {
+            Unit::Gram => Decimal::new(1, 0),
+            Unit::Kilogram => Decimal::new(1_000, 0),
+            Unit::Tonne => Decimal::new(1_000_000, 0),
+            Unit::TroyOunce => Decimal::new(311_034_768, 7),
+            Unit::Ounce => Decimal::new(28_349_523_125, 9),
+            Unit::Pound => Decimal::new(45_359_237, 5),
+            Unit::Litre => Decimal::new(1, 0),
+            Unit::Millilitre => Decimal::new(1, 3),
+            Unit::CubicMetre => Decimal::new(1_000, 0),
+            Unit::Gallon => Decimal::new(3_785_411_784, 9),
+            Unit::Barrel => Decimal::new(158_987_294_928, 9),
+            Unit::Bushel => Decimal::new(3_523_907_016_688, 11),
+        }
+    }
+
+    /// The symbol used to denote this unit.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Tonne => "t",
+            Unit::TroyOunce => "ozt",
+            Unit::Ounce => "oz",
+            Unit::Pound => "lb",
+            Unit::Litre => "L",
+            Unit::Millilitre => "mL",
+            Unit::CubicMetre => "m3",
+            Unit::Gallon => "gal",
+            Unit::Barrel => "bbl",
+            Unit::Bushel => "bu",
+        }
+    }
+}
+
+impl FromStr for Unit {
+    type Err = UnitError;
+
+    /// Parse a [Unit](Unit) from its symbol, e.g. `"ozt"`.
+    fn from_str(symbol: &str) -> Result<Unit, UnitError> {
+        ALL_UNITS
+            .iter()
+            .find(|unit| unit.symbol() == symbol)
+            .copied()
+            .ok_or_else(|| UnitError::InvalidUnit(String::from(symbol)))
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+/// An amount of a physical commodity, measured in a [Unit](Unit).
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quantity {
+    /// The amount, in `unit`s.
+    pub value: Decimal,
+    /// The unit of measure of the `value`.
+    pub unit: Unit,
+}
+
+impl Quantity {
+    /// Create a new [Quantity](Quantity).
+    pub fn new(value: Decimal, unit: Unit) -> Quantity {
+        Quantity { value, unit }
+    }
+
+    /// Convert this quantity to a different [Unit](Unit) of the same
+    /// [Dimension](Dimension).
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::unit::{Quantity, Unit};
+    /// use std::str::FromStr;
+    ///
+    /// let gold = Quantity::from_str("10 ozt").unwrap();
+    /// assert_eq!(Quantity::from_str("311.034768 g").unwrap(), gold.convert(Unit::Gram).unwrap());
+    ///
+    /// assert!(gold.convert(Unit::Litre).is_err());
+    /// ```
+    pub fn convert(&self, unit: Unit) -> Result<Quantity, UnitError> {
+        if self.unit == unit {
+            return Ok(*self);
+        }
+
+        if self.unit.dimension() != unit.dimension() {
+            return Err(UnitError::IncompatibleUnits(self.unit, unit));
+        }
+
+        let value = self
+            .value
+            .checked_mul(self.unit.factor())
+            .and_then(|base| base.checked_div(unit.factor()))
+            .ok_or(UnitError::Overflow(*self, unit))?;
+
+        Ok(Quantity::new(value.normalize(), unit))
+    }
+
+    /// Add the quantity `other` to `self`, converting `other` to the
+    /// unit of `self` if required.
+    pub fn add(&self, other: &Quantity) -> Result<Quantity, UnitError> {
+        let other = other.convert(self.unit)?;
+        let value = self
+            .value
+            .checked_add(other.value)
+            .ok_or(UnitError::AddOverflow(self.value, other.value))?;
+        Ok(Quantity::new(value, self.unit))
+    }
+
+    /// Subtract the quantity `other` from `self`, converting `other`
+    /// to the unit of `self` if required.
+    pub fn sub(&self, other: &Quantity) -> Result<Quantity, UnitError> {
+        let other = other.convert(self.unit)?;
+        let value = self
+            .value
+            .checked_sub(other.value)
+            .ok_or(UnitError::SubtractOverflow(self.value, other.value))?;
+        Ok(Quantity::new(value, self.unit))
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = UnitError;
+
+    /// Parse a [Quantity](Quantity) from a string, e.g. `"10 ozt"`.
+    fn from_str(quantity_string: &str) -> Result<Quantity, UnitError> {
+        let invalid = || UnitError::InvalidQuantityString(String::from(quantity_string));
+        let elements: Vec<&str> = quantity_string.split_whitespace().collect();
+
+        if elements.len() != 2 {
+            return Err(invalid());
+        }
+
+        Ok(Quantity::new(
+            Decimal::from_str(elements[0]).map_err(|_| invalid())?,
+            Unit::from_str(elements[1])?,
+        ))
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.value, self.unit)
+    }
+}
+
+/// The price of one [Unit](Unit) of a physical commodity, in a
+/// currency (or other commodity type).
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitPrice {
+    /// The price of one `unit`, e.g. `1900.00 USD`.
+    pub price: Commodity,
+    /// The unit of measure that the `price` is for.
+    pub unit: Unit,
+}
+
+impl UnitPrice {
+    /// Create a new [UnitPrice](UnitPrice).
+    pub fn new(price: Commodity, unit: Unit) -> UnitPrice {
+        UnitPrice { price, unit }
+    }
+
+    /// Calculate the value of the specified `quantity` at this price.
+    ///
+    /// # Example
+    /// ```
+    /// # use commodity::unit::{Quantity, Unit, UnitPrice};
+    /// use commodity::Commodity;
+    /// use std::str::FromStr;
+    ///
+    /// let gold_price = UnitPrice::new(Commodity::from_str("1900 USD").unwrap(), Unit::TroyOunce);
+    ///
+    /// let value = gold_price.value_of(&Quantity::from_str("10 ozt").unwrap()).unwrap();
+    /// assert_eq!(Commodity::from_str("19000 USD").unwrap(), value);
+    ///
+    /// // one kilogram is approximately 32.15 troy ounces
+    /// let value = gold_price.value_of(&Quantity::from_str("1 kg").unwrap()).unwrap();
+    /// assert_eq!(Commodity::from_str("61086.42 USD").unwrap(), Commodity::new(value.value.round_dp(2), value.type_id));
+    /// ```
+    pub fn value_of(&self, quantity: &Quantity) -> Result<Commodity, UnitError> {
+        let quantity = quantity.convert(self.unit)?;
+        let value = quantity
+            .value
+            .checked_mul(self.price.value)
+            .ok_or(UnitError::Overflow(quantity, self.unit))?;
+        Ok(Commodity::new(value, self.price.type_id))
+    }
+
+    /// Calculate the value of an amount of a physical
+    /// [Commodity](Commodity), whose [CommodityType](CommodityType)
+    /// `commodity_type` is measured in a [Unit](Unit) (see
+    /// [CommodityType::unit](CommodityType::unit)).
+    pub fn value_of_commodity(
+        &self,
+        commodity: &Commodity,
+        commodity_type: &CommodityType,
+    ) -> Result<Commodity, UnitError> {
+        if commodity.type_id != commodity_type.id {
+            return Err(UnitError::IncompatibleCommodityType(
+                *commodity,
+                commodity_type.id,
+            ));
+        }
+
+        let unit = commodity_type
+            .unit
+            .ok_or(UnitError::NoUnit(commodity_type.id))?;
+
+        self.value_of(&Quantity::new(commodity.value, unit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Quantity, Unit, UnitError, UnitPrice};
+    use crate::{Commodity, CommodityType};
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    #[test]
+    fn unit_conversion() {
+        let ten_ozt = Quantity::from_str("10 ozt").unwrap();
+        let grams = ten_ozt.convert(Unit::Gram).unwrap();
+        assert_eq!(Quantity::from_str("311.034768 g").unwrap(), grams);
+        assert_eq!(ten_ozt, grams.convert(Unit::TroyOunce).unwrap());
+
+        let barrel = Quantity::from_str("1 bbl").unwrap();
+        assert_eq!(
+            Quantity::from_str("42 gal").unwrap(),
+            barrel.convert(Unit::Gallon).unwrap()
+        );
+        assert_eq!(
+            Quantity::from_str("0.158987294928 m3").unwrap(),
+            barrel.convert(Unit::CubicMetre).unwrap()
+        );
+
+        assert_eq!(
+            Quantity::from_str("1 lb").unwrap(),
+            Quantity::from_str("16 oz")
+                .unwrap()
+                .convert(Unit::Pound)
+                .unwrap()
+        );
+
+        assert_eq!(
+            UnitError::IncompatibleUnits(Unit::Barrel, Unit::Kilogram),
+            barrel.convert(Unit::Kilogram).unwrap_err()
+        );
+        assert_eq!(
+            UnitError::InvalidUnit(String::from("furlong")),
+            Quantity::from_str("1 furlong").unwrap_err()
+        );
+    }
+
+    #[test]
+    fn quantity_arithmetic() {
+        let kg = Quantity::from_str("1 kg").unwrap();
+        let grams = Quantity::from_str("250 g").unwrap();
+        assert_eq!(
+            Quantity::from_str("1.25 kg").unwrap(),
+            kg.add(&grams).unwrap()
+        );
+        assert_eq!(
+            Quantity::from_str("0.75 kg").unwrap(),
+            kg.sub(&grams).unwrap()
+        );
+        assert!(kg.add(&Quantity::from_str("1 L").unwrap()).is_err());
+
+        let max = Quantity::new(Decimal::MAX, Unit::Gram);
+        let one = Quantity::new(Decimal::ONE, Unit::Gram);
+        assert_eq!(
+            UnitError::AddOverflow(Decimal::MAX, Decimal::ONE),
+            max.add(&one).unwrap_err()
+        );
+        let min = Quantity::new(Decimal::MIN, Unit::Gram);
+        assert_eq!(
+            UnitError::SubtractOverflow(Decimal::MIN, Decimal::ONE),
+            min.sub(&one).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn pricing() {
+        let wheat = CommodityType::from_str("WHEAT", "Wheat")
+            .unwrap()
+            .with_unit(Unit::Bushel);
+        let price = UnitPrice::new(Commodity::from_str("5.50 USD").unwrap(), Unit::Bushel);
+
+        let holding = Commodity::from_str("1000 WHEAT").unwrap();
+        let value = price.value_of_commodity(&holding, &wheat).unwrap();
+        assert_eq!(Commodity::from_str("5500 USD").unwrap(), value);
+
+        let value = price
+            .value_of(&Quantity::new(Decimal::new(3523907016688, 11), Unit::Litre))
+            .unwrap();
+        assert_eq!(Commodity::from_str("5.50 USD").unwrap(), value);
+
+        let corn = Commodity::from_str("1 CORN").unwrap();
+        assert_eq!(
+            UnitError::IncompatibleCommodityType(corn, wheat.id),
+            price.value_of_commodity(&corn, &wheat).unwrap_err()
+        );
+
+        let wheat = CommodityType::new(wheat.id, None);
+        assert_eq!(
+            UnitError::NoUnit(wheat.id),
+            price.value_of_commodity(&holding, &wheat).unwrap_err()
+        );
+    }
+}